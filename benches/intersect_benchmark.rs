@@ -0,0 +1,100 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ray_tracer::lights::PointLight;
+use ray_tracer::matrix::Matrix;
+use ray_tracer::rays::{hit, lighting, Ray};
+use ray_tracer::shapes::{Object, Sphere};
+use ray_tracer::simd::RayPacket;
+use ray_tracer::tuple::{Color, Tuple};
+
+// Casts a 100x100 grid of rays at a lit sphere, the same work the
+// lighted_sphere example does per pixel.
+fn intersect_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("arb");
+    group.sample_size(40);
+    let sphere = Sphere::with_transform(Matrix::identity().scale(1.0, 0.8, 1.0));
+    let light = PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::white());
+    let origin = Tuple::point(0.0, 0.0, -5.0);
+
+    group.bench_function("intersect and shade", |b| {
+        b.iter(|| {
+            let mut total = Color::black();
+            for x in 0..100 {
+                for y in 0..100 {
+                    let target = Tuple::point(x as f64 * 0.04 - 2.0, 2.0 - y as f64 * 0.04, 10.0);
+                    let ray = Ray::new(origin, (target - origin).normalize());
+                    if let Some(intersections) = ray.intersect(black_box(&sphere)) {
+                        if let Some(intersection) = hit(&intersections) {
+                            let point = ray.position(intersection.time);
+                            let normal = sphere.normal_at(&point);
+                            let color = lighting(
+                                &sphere.material,
+                                &light,
+                                point,
+                                -ray.direction,
+                                normal,
+                                1.0,
+                            );
+                            total += color;
+                        }
+                    }
+                }
+            }
+            total
+        })
+    });
+    group.finish();
+}
+
+// The same grid intersected one ray at a time and four at a time, summing
+// the nearest hits so neither loop can be optimized away.
+fn packet_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("packets");
+    group.sample_size(40);
+    let sphere = Sphere::with_transform(Matrix::identity().scale(1.0, 0.8, 1.0));
+    let origin = Tuple::point(0.0, 0.0, -5.0);
+    let rays: Vec<Ray> = (0..100 * 100)
+        .map(|i| {
+            let (x, y) = (i % 100, i / 100);
+            let target = Tuple::point(x as f64 * 0.04 - 2.0, 2.0 - y as f64 * 0.04, 10.0);
+            Ray::new(origin, (target - origin).normalize())
+        })
+        .collect();
+
+    group.bench_function("single rays", |b| {
+        b.iter(|| {
+            let mut total = 0.0;
+            for ray in &rays {
+                if let Some(intersections) = ray.intersect(black_box(&sphere)) {
+                    if let Some(intersection) = hit(&intersections) {
+                        total += intersection.time;
+                    }
+                }
+            }
+            total
+        })
+    });
+    group.bench_function("ray packets", |b| {
+        b.iter(|| {
+            let mut total = 0.0;
+            for rays in rays.chunks_exact(4) {
+                let packet = RayPacket::new([&rays[0], &rays[1], &rays[2], &rays[3]]);
+                for (near, far) in black_box(&sphere)
+                    .intersect_packet(&packet)
+                    .into_iter()
+                    .flatten()
+                {
+                    if near >= 0.0 {
+                        total += near;
+                    } else if far >= 0.0 {
+                        total += far;
+                    }
+                }
+            }
+            total
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, intersect_benchmark, packet_benchmark);
+criterion_main!(benches);
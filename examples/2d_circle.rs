@@ -1,6 +1,7 @@
 use ray_tracer::canvas::Canvas;
 use ray_tracer::matrix::Matrix;
-use ray_tracer::rays::{hit, Ray, Sphere};
+use ray_tracer::rays::{hit, Ray};
+use ray_tracer::shapes::Sphere;
 use ray_tracer::tuple::{Color, Tuple};
 
 struct Wall {
@@ -8,7 +9,7 @@ struct Wall {
     size: f64,
 }
 
-fn main() {
+fn main() -> ray_tracer::Result<()> {
     // arbitrarily chosen values
     let flashlight = Tuple::point(0.0, 0.0, -5.0);
     let wall = Wall { z: 10.0, size: 8.0 };
@@ -36,13 +37,12 @@ fn main() {
 
             if let Some(intersections) = ray.intersect(&sphere) {
                 if hit(&intersections).is_some() {
-                    let point = Tuple::point(x as f64, y as f64, 0.0);
-                    canvas.write_pixel(&point, Color::red());
+                    canvas.set_pixel(x, y, Color::red());
                 }
             }
         }
     }
     println!(" done: {} seconds", now.elapsed().as_secs());
 
-    ray_tracer::save_image(canvas, "circle-2d.ppm");
+    ray_tracer::save_image(&canvas, "circle-2d.ppm")
 }
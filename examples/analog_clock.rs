@@ -1,21 +1,52 @@
 use ray_tracer::canvas::Canvas;
+use ray_tracer::draw::text_size;
 use ray_tracer::matrix::Matrix;
 use ray_tracer::tuple::{Color, Tuple};
 use std::f64::consts::PI;
 
 const RADIANS_IN_AN_HOUR: f64 = PI / 6.0;
 
-fn main() {
+fn main() -> ray_tracer::Result<()> {
     let mut canvas = Canvas::new(250, 250);
+    let center = (canvas.width / 2, canvas.height / 2);
     let start_point = Tuple::point(0.0, -100.0, 0.0);
+    let label_point = Tuple::point(0.0, -85.0, 0.0);
 
+    canvas.draw_circle(center, 112, Color::new(0.3, 0.3, 0.3));
     for hour in 1..=12 {
         let transformation = Matrix::identity()
             .rotate_z(hour as f64 * RADIANS_IN_AN_HOUR)
-            .translate((canvas.width / 2) as f64, 125.0, 0.0);
-        let new_point = transformation * start_point;
-        canvas.write_pixel(&new_point, Color::white());
+            .translate(center.0 as f64, center.1 as f64, 0.0);
+        let mark = &transformation * start_point;
+        canvas.fill_circle(
+            (mark.x.round() as i32, mark.y.round() as i32),
+            3,
+            Color::white(),
+        );
+
+        let label = hour.to_string();
+        let (width, height) = text_size(&label);
+        let at = transformation * label_point;
+        canvas.draw_text(
+            at.x.round() as i32 - width / 2,
+            at.y.round() as i32 - height / 2,
+            &label,
+            Color::white(),
+        );
     }
 
-    ray_tracer::save_image(canvas, "analog_clock.ppm");
+    // Ten past two
+    let hand = |length: f64, hours: f64| {
+        let tip = Matrix::identity()
+            .rotate_z(hours * RADIANS_IN_AN_HOUR)
+            .translate(center.0 as f64, center.1 as f64, 0.0)
+            * Tuple::point(0.0, -length, 0.0);
+        (tip.x, tip.y)
+    };
+    let middle = (center.0 as f64, center.1 as f64);
+    canvas.draw_line_smooth(middle, hand(50.0, 2.0 + 10.0 / 60.0), Color::white());
+    canvas.draw_line_smooth(middle, hand(75.0, 2.0), Color::new(1.0, 0.8, 0.2));
+    canvas.fill_circle(center, 2, Color::white());
+
+    ray_tracer::save_image(&canvas, "analog_clock.ppm")
 }
@@ -1,9 +1,11 @@
 use ray_tracer::canvas::Canvas;
+use ray_tracer::lights::PointLight;
 use ray_tracer::matrix::Matrix;
-use ray_tracer::rays::{hit, lighting, Material, Object, PointLight, Ray, Sphere};
+use ray_tracer::rays::{hit, lighting, Material, Ray};
+use ray_tracer::shapes::{Object, Sphere};
 use ray_tracer::tuple::{Color, Tuple};
 
-fn main() {
+fn main() -> ray_tracer::Result<()> {
     // arbitrarily chosen values
     let ray_origin = Tuple::point(0.0, 0.0, -5.0);
     let wall_z = 10.0;
@@ -38,14 +40,15 @@ fn main() {
                     let canvas_point = Tuple::point(x as f64, y as f64, 0.0);
 
                     let hit_point = ray.position(intersection.time);
-                    let normal = sphere.normal_at(&hit_point).unwrap();
+                    let normal = sphere.normal_at(&hit_point);
                     let eye = -ray.direction;
                     let color = lighting(
-                        &intersection.object.material,
+                        intersection.object.material(),
                         &light,
                         hit_point,
                         eye,
                         normal,
+                        1.0,
                     );
                     canvas.write_pixel(&canvas_point, color);
                 }
@@ -54,5 +57,5 @@ fn main() {
     }
     println!(" done: {} seconds", now.elapsed().as_secs());
 
-    ray_tracer::save_image(canvas, "lighted_sphere.ppm");
+    ray_tracer::save_image(&canvas, "lighted_sphere.ppm")
 }
@@ -0,0 +1,53 @@
+use ray_tracer::canvas::Canvas;
+use ray_tracer::tuple::{Color, Tuple};
+
+fn virtual_cannon() -> ray_tracer::Result<()> {
+    struct Projectile {
+        position: Tuple,
+        velocity: Tuple,
+    }
+
+    struct Environment {
+        gravity: Tuple,
+        wind: Tuple,
+    }
+
+    fn tick(env: &Environment, projectile: Projectile) -> Projectile {
+        Projectile {
+            position: projectile.position + projectile.velocity,
+            velocity: projectile.velocity + env.gravity + env.wind,
+        }
+    }
+
+    let mut projectile = Projectile {
+        position: Tuple::point(0.0, 1.0, 0.0),
+        velocity: Tuple::vector(1.0, 1.8, 0.0).normalize() * 7.85,
+    };
+
+    let env = Environment {
+        gravity: Tuple::vector(0.0, -0.1, 0.0),
+        wind: Tuple::vector(-0.01, 0.0, 0.0),
+    };
+
+    let mut canvas = Canvas::new(500, 300);
+    let color = Color::new(1.0, 0.0, 1.0);
+    // Canvas rows count down from the top
+    let height = canvas.height;
+    let on_canvas = |position: Tuple| (position.x as i32, height - position.y as i32);
+
+    let mut ticks = 0;
+    while projectile.position.y > 0.0 {
+        let from = on_canvas(projectile.position);
+        projectile = tick(&env, projectile);
+        canvas.draw_line(from, on_canvas(projectile.position), color);
+        ticks += 1;
+    }
+    let label = format!("{} TICKS, {:.0} UNITS", ticks, projectile.position.x);
+    canvas.draw_text(4, 4, &label, Color::white());
+
+    ray_tracer::save_image(&canvas, "cannon.ppm")
+}
+
+fn main() -> ray_tracer::Result<()> {
+    virtual_cannon()
+}
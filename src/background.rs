@@ -0,0 +1,128 @@
+//! What rays that miss everything see.
+
+use crate::canvas::Canvas;
+use crate::tuple::{Color, Tuple};
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+/// The world's backdrop, looked up by the direction of a ray.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Background {
+    /// The same color everywhere, black by default.
+    Color(Color),
+    /// A sky fading from `bottom`, straight down, to `top`, straight up.
+    Gradient { bottom: Color, top: Color },
+    /// An image wrapped all the way around the world.
+    Environment(EnvironmentMap),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Color(Color::black())
+    }
+}
+
+impl Background {
+    pub fn color(&self, direction: Tuple) -> Color {
+        match self {
+            Background::Color(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let up = direction.normalize().y;
+                bottom.lerp(top, (up + 1.0) / 2.0)
+            }
+            Background::Environment(map) => map.color(direction),
+        }
+    }
+}
+
+/// An equirectangular image: longitude across, from behind the camera's
+/// default view all the way around, and latitude up from straight down to
+/// straight up. Shared, since images can be large.
+#[derive(Clone)]
+pub struct EnvironmentMap(pub Arc<Canvas>);
+
+impl EnvironmentMap {
+    pub fn new(canvas: Canvas) -> Self {
+        Self(Arc::new(canvas))
+    }
+
+    pub fn color(&self, direction: Tuple) -> Color {
+        let direction = direction.normalize();
+        // +z, where the default camera looks, lands in the middle
+        let u = 0.5 + direction.x.atan2(direction.z) / (2.0 * PI);
+        let v = 0.5 + direction.y.clamp(-1.0, 1.0).asin() / PI;
+        self.0.sample(u, v)
+    }
+}
+
+impl fmt::Debug for EnvironmentMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EnvironmentMap({}x{})", self.0.width, self.0.height)
+    }
+}
+
+// Two maps are the same if they share an image
+impl PartialEq for EnvironmentMap {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradients_fade_from_bottom_to_top() {
+        let sky = Background::Gradient {
+            bottom: Color::white(),
+            top: Color::new(0.0, 0.0, 1.0),
+        };
+        assert_eq!(sky.color(Tuple::vector(0.0, -2.0, 0.0)), Color::white());
+        assert_eq!(
+            sky.color(Tuple::vector(0.0, 1.0, 0.0)),
+            Color::new(0.0, 0.0, 1.0)
+        );
+        let horizon = sky.color(Tuple::vector(1.0, 0.0, 1.0));
+        assert!(horizon.is_equal(&Color::new(0.5, 0.5, 1.0)));
+    }
+
+    #[test]
+    fn environment_maps_are_looked_up_by_direction() {
+        // Four columns for the four sides, and two rows for the hemispheres
+        let mut canvas = Canvas::new(4, 2);
+        let sides = [
+            Color::new(0.1, 0.0, 0.0),
+            Color::new(0.2, 0.0, 0.0),
+            Color::new(0.3, 0.0, 0.0),
+            Color::new(0.4, 0.0, 0.0),
+        ];
+        for (x, side) in sides.iter().enumerate() {
+            canvas.write_pixel(
+                &Tuple::point(x as f64, 0.0, 0.0),
+                *side + Color::new(0.0, 1.0, 0.0),
+            );
+            canvas.write_pixel(&Tuple::point(x as f64, 1.0, 0.0), *side);
+        }
+        let map = EnvironmentMap::new(canvas);
+        // Pixel centers, 45 degrees above and below the horizon
+        let up = 2.0_f64.sqrt();
+        let cases = [
+            (
+                Tuple::vector(-1.0, up, -1.0),
+                sides[0] + Color::new(0.0, 1.0, 0.0),
+            ),
+            (Tuple::vector(-1.0, -up, 1.0), sides[1]),
+            (
+                Tuple::vector(1.0, up, 1.0),
+                sides[2] + Color::new(0.0, 1.0, 0.0),
+            ),
+            (Tuple::vector(1.0, -up, -1.0), sides[3]),
+        ];
+        for (direction, expected) in cases {
+            let color = map.color(direction);
+            assert!(color.is_equal(&expected), "{:?}: {:?}", direction, color);
+        }
+    }
+}
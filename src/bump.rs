@@ -0,0 +1,222 @@
+//! Small surface details that catch the light without adding geometry: the
+//! shading normal is tilted, either by the slope of a height field (bump
+//! mapping) or by directions stored in an image (normal mapping).
+
+use crate::canvas::Canvas;
+use crate::geometry::{Point3, Vector3};
+use crate::noise::fractal;
+use crate::shapes::Object;
+use crate::tuple::Tuple;
+use crate::EPSILON;
+use std::fmt;
+use std::sync::Arc;
+
+/// Step used to estimate a height field's slope by finite differences.
+const SLOPE_STEP: f64 = 1e-4;
+/// Octaves of noise in `HeightField::Noise`.
+const NOISE_OCTAVES: usize = 4;
+
+/// Heights over an object's surface, in object space.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HeightField {
+    /// Random lumps from fractal noise, `frequency` of them per unit.
+    Noise { frequency: f64 },
+    /// Parallel ridges across x, `frequency` of them per unit.
+    Waves { frequency: f64 },
+}
+
+impl HeightField {
+    pub fn height(&self, point: Point3) -> f64 {
+        match *self {
+            HeightField::Noise { frequency } => fractal(
+                Tuple::point(
+                    point.x * frequency,
+                    point.y * frequency,
+                    point.z * frequency,
+                ),
+                NOISE_OCTAVES,
+            ),
+            HeightField::Waves { frequency } => (point.x * frequency * std::f64::consts::TAU).sin(),
+        }
+    }
+
+    /// Slope of the field, by central differences.
+    fn gradient(&self, point: Point3) -> Vector3 {
+        let slope = |step: Vector3| {
+            (self.height(point + step) - self.height(point - step)) / (2.0 * SLOPE_STEP)
+        };
+        Vector3::new(
+            slope(Vector3::new(SLOPE_STEP, 0.0, 0.0)),
+            slope(Vector3::new(0.0, SLOPE_STEP, 0.0)),
+            slope(Vector3::new(0.0, 0.0, SLOPE_STEP)),
+        )
+    }
+}
+
+/// A tangent-space normal map: red, green and blue store how far the
+/// normal leans along the tangent, along the bitangent and out of the
+/// surface, mapped from -1..1 to 0..1. Shared, since images can be large.
+#[derive(Clone)]
+pub struct NormalMap(pub Arc<Canvas>);
+
+impl NormalMap {
+    pub fn new(canvas: Canvas) -> Self {
+        Self(Arc::new(canvas))
+    }
+}
+
+impl fmt::Debug for NormalMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NormalMap({}x{})", self.0.width, self.0.height)
+    }
+}
+
+// Two maps are the same if they share an image
+impl PartialEq for NormalMap {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// How a material tilts its surface's normals.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Bump {
+    /// Tilts normals down the slope of the field, more so the larger
+    /// `scale`.
+    Height { field: HeightField, scale: f64 },
+    /// Takes normals from an image, for shapes with texture coordinates.
+    Map(NormalMap),
+}
+
+impl Bump {
+    /// The shading normal at a world space point on `object`, given the
+    /// geometric `normal` there.
+    pub fn perturb(
+        &self,
+        object: &dyn Object,
+        point: Tuple,
+        normal: Tuple,
+        shutter_time: f64,
+    ) -> Tuple {
+        match self {
+            Bump::Height { field, scale } => {
+                let Some(transform) = object.transform().at(shutter_time) else {
+                    return normal;
+                };
+                let object_point = transform.inverse()
+                    * Point3::try_from(point).expect("shaded points have w = 1");
+                // Slopes are like normals, so they change space the same way
+                let slope: Tuple =
+                    (transform.inverse_transpose() * field.gradient(object_point)).into();
+                let along_surface = slope - normal * slope.dot(&normal);
+                (normal - along_surface * *scale).normalize()
+            }
+            Bump::Map(map) => {
+                let Some(frame) = object.surface_frame(&point, shutter_time) else {
+                    return normal;
+                };
+                let tangent = frame.tangent - normal * frame.tangent.dot(&normal);
+                if tangent.magnitude() < EPSILON {
+                    return normal;
+                }
+                let tangent = tangent.normalize();
+                let bitangent = tangent.cross(&normal);
+                let texel = map.0.sample(frame.u, frame.v);
+                let lean = |value: f64| value * 2.0 - 1.0;
+                (tangent * lean(texel.red)
+                    + bitangent * lean(texel.green)
+                    + normal * lean(texel.blue))
+                .normalize()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix;
+    use crate::shapes::{Plane, Sphere};
+    use crate::tuple::Color;
+
+    fn up() -> Tuple {
+        Tuple::vector(0.0, 1.0, 0.0)
+    }
+
+    fn flat_map(color: Color) -> NormalMap {
+        let mut canvas = Canvas::new(2, 2);
+        for y in 0..2 {
+            for x in 0..2 {
+                canvas.write_pixel(&Tuple::point(x as f64, y as f64, 0.0), color);
+            }
+        }
+        NormalMap::new(canvas)
+    }
+
+    #[test]
+    fn finite_differences_find_the_slope() {
+        let waves = HeightField::Waves { frequency: 0.25 };
+        let gradient = waves.gradient(Point3::new(0.0, 3.0, -2.0));
+        // d/dx sin(x * pi / 2) at 0 is pi / 2
+        assert!(gradient.is_equal(&Vector3::new(std::f64::consts::FRAC_PI_2, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn bumps_tilt_normals_down_the_slope() {
+        let plane = Plane::new();
+        let bump = Bump::Height {
+            field: HeightField::Waves { frequency: 0.25 },
+            scale: 2.0 / std::f64::consts::PI,
+        };
+        // The height rises along x with a slope of pi / 2
+        let normal = bump.perturb(&plane, Tuple::point(0.0, 0.0, 0.0), up(), 0.0);
+        assert!(normal.is_equal(&Tuple::vector(-1.0, 1.0, 0.0).normalize()));
+        // On the crest the surface is level again
+        let crest = bump.perturb(&plane, Tuple::point(1.0, 0.0, 0.0), up(), 0.0);
+        assert!(crest.is_equal(&up()));
+    }
+
+    #[test]
+    fn bumps_follow_the_object_transform() {
+        let plane = Plane::with_transform(Matrix::identity().scale(2.0, 1.0, 1.0));
+        let bump = Bump::Height {
+            field: HeightField::Waves { frequency: 0.25 },
+            scale: 2.0 / std::f64::consts::PI,
+        };
+        // Stretching the plane along x halves the slope
+        let normal = bump.perturb(&plane, Tuple::point(0.0, 0.0, 0.0), up(), 0.0);
+        assert!(normal.is_equal(&Tuple::vector(-0.5, 1.0, 0.0).normalize()));
+    }
+
+    #[test]
+    fn a_flat_normal_map_keeps_the_normal() {
+        let sphere = Sphere::new();
+        let bump = Bump::Map(flat_map(Color::new(0.5, 0.5, 1.0)));
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let perturbed = bump.perturb(&sphere, Tuple::point(0.0, 0.0, -1.0), normal, 0.0);
+        assert!(perturbed.is_equal(&normal));
+    }
+
+    #[test]
+    fn normal_maps_lean_along_the_tangent_frame() {
+        let sphere = Sphere::new();
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let point = Tuple::point(0.0, 0.0, -1.0);
+        // At the front of the sphere the tangent is +x and the bitangent +y
+        let along_u = Bump::Map(flat_map(Color::new(1.0, 0.5, 0.5)));
+        assert!(along_u
+            .perturb(&sphere, point, normal, 0.0)
+            .is_equal(&Tuple::vector(1.0, 0.0, 0.0)));
+        let along_v = Bump::Map(flat_map(Color::new(0.5, 1.0, 1.0)));
+        assert!(along_v
+            .perturb(&sphere, point, normal, 0.0)
+            .is_equal(&Tuple::vector(0.0, 1.0, -1.0).normalize()));
+    }
+
+    #[test]
+    fn normal_maps_are_compared_by_image() {
+        let map = flat_map(Color::white());
+        assert_eq!(map, map.clone());
+        assert_ne!(map, flat_map(Color::white()));
+    }
+}
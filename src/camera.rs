@@ -0,0 +1,788 @@
+use crate::canvas::{heat_color, Canvas};
+use crate::error::Error;
+use crate::integrator::Integrator;
+use crate::matrix::Matrix;
+use crate::rays::Ray;
+use crate::sampling::{pixel_rng, sample_disk, sample_positions, Adaptive, Filter, SampleStrategy};
+use crate::tuple::{Color, Tuple};
+use crate::world::{World, DEFAULT_MAX_DEPTH};
+use crate::EPSILON;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Knobs for `Camera::render_with`, trading speed for quality.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenderSettings {
+    /// Worker threads, each rendering whole rows.
+    pub threads: usize,
+    /// Rays per pixel, placed by `strategy` and weighed by `filter`.
+    pub samples: usize,
+    pub strategy: SampleStrategy,
+    pub filter: Filter,
+    /// Seeds the random sample placement so renders are reproducible.
+    pub seed: u64,
+    /// How many reflections a ray may follow, or bounces a path may take.
+    pub max_depth: usize,
+    pub integrator: Integrator,
+    /// Refine only pixels on edges instead of sampling every pixel the same.
+    /// Replaces `samples`, `strategy` and `filter` when set.
+    pub adaptive: Option<Adaptive>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            samples: 1,
+            strategy: SampleStrategy::Grid,
+            filter: Filter::Box,
+            seed: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            integrator: Integrator::Whitted,
+            adaptive: None,
+        }
+    }
+}
+
+/// Maps the canvas onto a virtual screen one unit in front of the camera.
+/// The inverse transform is cached alongside the transform since every
+/// pixel's ray needs it.
+///
+/// With an `aperture` the camera becomes a thin lens: rays start anywhere on
+/// the lens and meet again `focal_distance` away, so only things at that
+/// distance are sharp.
+///
+/// While the shutter is open, from `shutter_open` to `shutter_close`, rays
+/// are cast at random times so moving objects blur.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Camera {
+    pub hsize: i32,
+    pub vsize: i32,
+    pub field_of_view: f64,
+    /// Diameter of the lens, zero for a pinhole camera.
+    pub aperture: f64,
+    /// How far in front of the camera things are in focus.
+    pub focal_distance: f64,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    transform: Matrix,
+    inverse: Matrix,
+    half_width: f64,
+    half_height: f64,
+    pixel_size: f64,
+}
+
+impl Camera {
+    pub fn new(hsize: i32, vsize: i32, field_of_view: f64) -> Self {
+        // The screen is one unit away, so half of it spans tan(fov/2) units
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = hsize as f64 / vsize as f64;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        Self {
+            hsize,
+            vsize,
+            field_of_view,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+            half_width,
+            half_height,
+            pixel_size: half_width * 2.0 / hsize as f64,
+        }
+    }
+
+    /// The same camera with an `hsize` by `vsize` canvas, keeping its field
+    /// of view, transform, lens and shutter.
+    pub fn with_size(self, hsize: i32, vsize: i32) -> Self {
+        let sized = Self::new(hsize, vsize, self.field_of_view);
+        Self {
+            hsize,
+            vsize,
+            half_width: sized.half_width,
+            half_height: sized.half_height,
+            pixel_size: sized.pixel_size,
+            ..self
+        }
+    }
+
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_size
+    }
+
+    pub fn transform(&self) -> &Matrix {
+        &self.transform
+    }
+
+    /// Fails with `Error::NonInvertibleMatrix`, leaving the transform as it
+    /// was, if the new one can't be inverted.
+    pub fn set_transform(&mut self, transform: Matrix) -> Result<(), Error> {
+        self.inverse = transform.inverse().ok_or(Error::NonInvertibleMatrix)?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// A ray from the camera through the center of the given pixel.
+    pub fn ray_for_pixel(&self, px: i32, py: i32) -> Ray {
+        self.ray_through(px as f64 + 0.5, py as f64 + 0.5)
+    }
+
+    /// A ray from the camera through a point on the canvas, in pixel units.
+    pub fn ray_through(&self, x: f64, y: f64) -> Ray {
+        // The camera looks toward -z, so +x is to the left
+        let world_x = self.half_width - x * self.pixel_size;
+        let world_y = self.half_height - y * self.pixel_size;
+
+        let pixel = &self.inverse * Tuple::point(world_x, world_y, -1.0);
+        let origin = &self.inverse * Tuple::point(0.0, 0.0, 0.0);
+        Ray::new(origin, (pixel - origin).normalize())
+    }
+
+    /// Like `ray_through`, but starting from a point on the lens, `lens`
+    /// being where on it from (0, 0) to (1, 1).
+    pub fn ray_through_lens(&self, x: f64, y: f64, lens: (f64, f64)) -> Ray {
+        let world_x = self.half_width - x * self.pixel_size;
+        let world_y = self.half_height - y * self.pixel_size;
+
+        // Every ray through this pixel meets the pinhole ray on the focal plane
+        let f = self.focal_distance;
+        let focus = &self.inverse * Tuple::point(world_x * f, world_y * f, -f);
+        let (lens_x, lens_y) = sample_disk(lens);
+        let radius = self.aperture / 2.0;
+        let origin = &self.inverse * Tuple::point(lens_x * radius, lens_y * radius, 0.0);
+        Ray::new(origin, (focus - origin).normalize())
+    }
+
+    // Pinhole cameras skip the lens, and instant exposures the shutter, so
+    // they don't use up random numbers
+    fn sample_ray(&self, x: f64, y: f64, rng: &mut StdRng) -> Ray {
+        let ray = if self.aperture <= 0.0 {
+            self.ray_through(x, y)
+        } else {
+            self.ray_through_lens(x, y, (rng.gen(), rng.gen()))
+        };
+        let exposure = self.shutter_close - self.shutter_open;
+        if exposure <= 0.0 {
+            return ray.at_shutter_time(self.shutter_open);
+        }
+        ray.at_shutter_time(self.shutter_open + rng.gen::<f64>() * exposure)
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        self.render_with(world, &RenderSettings::default(), |_| {})
+    }
+
+    /// Renders with the given settings, calling `progress` with the number of
+    /// finished rows each time a row is done.
+    pub fn render_with(
+        &self,
+        world: &World,
+        settings: &RenderSettings,
+        progress: impl Fn(usize) + Sync,
+    ) -> Canvas {
+        if let Some(adaptive) = &settings.adaptive {
+            return self
+                .render_adaptive(world, settings, adaptive, progress)
+                .image;
+        }
+
+        let rows = self.render_rows(settings.threads, progress, |x, y| {
+            self.pixel_color(world, settings, x, y)
+        });
+        self.to_canvas(&rows, |color| *color)
+    }
+
+    /// Renders one ray per pixel, then keeps subdividing the pixels whose
+    /// color differs from a neighbor's. `progress` reports the second pass.
+    pub fn render_adaptive(
+        &self,
+        world: &World,
+        settings: &RenderSettings,
+        adaptive: &Adaptive,
+        progress: impl Fn(usize) + Sync,
+    ) -> AdaptiveRender {
+        let first = self.render_rows(
+            settings.threads,
+            |_| {},
+            |x, y| {
+                let mut rng = pixel_rng(settings.seed, x, y);
+                let ray = self.sample_ray(x as f64 + 0.5, y as f64 + 0.5, &mut rng);
+                settings
+                    .integrator
+                    .color(world, &ray, settings.max_depth, &mut rng)
+            },
+        );
+        let on_edge = |x: i32, y: i32| {
+            let color = first[y as usize][x as usize];
+            [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx >= 0
+                    && ny >= 0
+                    && nx < self.hsize
+                    && ny < self.vsize
+                    && adaptive.differ(&color, &first[ny as usize][nx as usize])
+            })
+        };
+
+        let refined = self.render_rows(settings.threads, progress, |x, y| {
+            let center = first[y as usize][x as usize];
+            if adaptive.max_subdivisions == 0 || !on_edge(x, y) {
+                return (center, 1);
+            }
+            let mut refinement = Refinement {
+                samples: 1,
+                // A separate stream so the first pass's lens samples don't repeat
+                rng: pixel_rng(settings.seed ^ 1, x, y),
+            };
+            let color = self.subdivide(
+                world,
+                settings,
+                adaptive,
+                (x as f64, y as f64, 1.0),
+                1,
+                &mut refinement,
+            );
+            (color, refinement.samples)
+        });
+
+        AdaptiveRender {
+            image: self.to_canvas(&refined, |(color, _)| *color),
+            samples: refined
+                .into_iter()
+                .flat_map(|row| row.into_iter().map(|(_, samples)| samples))
+                .collect(),
+            width: self.hsize,
+            height: self.vsize,
+        }
+    }
+
+    // Averages the centers of the square's four quadrants, subdividing those
+    // quadrants further while they disagree.
+    fn subdivide(
+        &self,
+        world: &World,
+        settings: &RenderSettings,
+        adaptive: &Adaptive,
+        (left, top, size): (f64, f64, f64),
+        level: usize,
+        refinement: &mut Refinement,
+    ) -> Color {
+        let half = size / 2.0;
+        let quadrants = [
+            (left, top),
+            (left + half, top),
+            (left, top + half),
+            (left + half, top + half),
+        ];
+        let colors = quadrants.map(|(x, y)| {
+            let rng = &mut refinement.rng;
+            let ray = self.sample_ray(x + half / 2.0, y + half / 2.0, rng);
+            settings
+                .integrator
+                .color(world, &ray, settings.max_depth, rng)
+        });
+        refinement.samples += colors.len();
+
+        let uneven = colors
+            .iter()
+            .any(|color| adaptive.differ(color, &colors[0]));
+        if level >= adaptive.max_subdivisions || !uneven {
+            return colors.iter().sum::<Color>() / colors.len() as f64;
+        }
+        quadrants
+            .iter()
+            .map(|&(x, y)| {
+                self.subdivide(
+                    world,
+                    settings,
+                    adaptive,
+                    (x, y, half),
+                    level + 1,
+                    refinement,
+                )
+            })
+            .sum::<Color>()
+            / quadrants.len() as f64
+    }
+
+    /// Calls `pixel` for every pixel across `threads` threads, returning the
+    /// results row by row.
+    fn render_rows<T: Send>(
+        &self,
+        threads: usize,
+        progress: impl Fn(usize) + Sync,
+        pixel: impl Fn(i32, i32) -> T + Sync,
+    ) -> Vec<Vec<T>> {
+        // Threads take the next unrendered row until none are left
+        let next_row = AtomicUsize::new(0);
+        let finished = AtomicUsize::new(0);
+        let mut rows: Vec<(i32, Vec<T>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut rows = Vec::new();
+                        loop {
+                            let y = next_row.fetch_add(1, Ordering::Relaxed) as i32;
+                            if y >= self.vsize {
+                                break;
+                            }
+                            let row = (0..self.hsize).map(|x| pixel(x, y)).collect();
+                            rows.push((y, row));
+                            progress(finished.fetch_add(1, Ordering::Relaxed) + 1);
+                        }
+                        rows
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("render thread panicked"))
+                .collect()
+        });
+
+        rows.sort_by_key(|(y, _)| *y);
+        rows.into_iter().map(|(_, row)| row).collect()
+    }
+
+    fn to_canvas<T>(&self, rows: &[Vec<T>], color: impl Fn(&T) -> Color) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                image.write_pixel(&Tuple::point(x as f64, y as f64, 0.0), color(pixel));
+            }
+        }
+        image
+    }
+
+    fn pixel_color(&self, world: &World, settings: &RenderSettings, x: i32, y: i32) -> Color {
+        let mut rng = pixel_rng(settings.seed, x, y);
+        let positions = sample_positions(settings.strategy, settings.samples.max(1), &mut rng);
+        let width = settings.filter.radius() * 2.0;
+
+        let mut total = Color::black();
+        let mut total_weight = 0.0;
+        let mut unweighted = Color::black();
+        for (u, v) in &positions {
+            // Spread the unit square over the filter's footprint
+            let (dx, dy) = ((u - 0.5) * width, (v - 0.5) * width);
+            let ray = self.sample_ray(x as f64 + 0.5 + dx, y as f64 + 0.5 + dy, &mut rng);
+            let color = settings
+                .integrator
+                .color(world, &ray, settings.max_depth, &mut rng);
+            let weight = settings.filter.weight(dx, dy);
+            total += color * weight;
+            total_weight += weight;
+            unweighted += color;
+        }
+
+        // Negative lobes can cancel out the weights of a few unlucky samples
+        if total_weight.abs() < EPSILON {
+            return unweighted / positions.len() as f64;
+        }
+        total / total_weight
+    }
+}
+
+// What `Camera::subdivide` keeps track of while refining one pixel.
+struct Refinement {
+    samples: usize,
+    rng: StdRng,
+}
+
+/// The result of `Camera::render_adaptive`: the image, plus how many samples
+/// each pixel took.
+pub struct AdaptiveRender {
+    pub image: Canvas,
+    samples: Vec<usize>,
+    width: i32,
+    height: i32,
+}
+
+impl AdaptiveRender {
+    pub fn samples_at(&self, x: i32, y: i32) -> usize {
+        self.samples[(y * self.width + x) as usize]
+    }
+
+    pub fn total_samples(&self) -> usize {
+        self.samples.iter().sum()
+    }
+
+    /// A debug image of where samples went: black for a single sample, through
+    /// blue and green to red for the most-sampled pixels.
+    pub fn heatmap(&self) -> Canvas {
+        let most = self.samples.iter().copied().max().unwrap_or(1);
+        let mut heatmap = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = if most <= 1 {
+                    Color::black()
+                } else {
+                    heat_color((self.samples_at(x, y) - 1) as f64 / (most - 1) as f64)
+                };
+                heatmap.write_pixel(&Tuple::point(x as f64, y as f64, 0.0), color);
+            }
+        }
+        heatmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_float_equal;
+    use crate::shapes::{Keyframe, Motion, Sphere};
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+    fn default_camera(size: i32) -> Camera {
+        let mut camera = Camera::new(size, size, PI / 2.0);
+        let from = Tuple::point(0.0, 0.0, -5.0);
+        let to = Tuple::point(0.0, 0.0, 0.0);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        camera
+            .set_transform(Matrix::view_transform(from, to, up))
+            .unwrap();
+        camera
+    }
+
+    #[test]
+    fn rendering_with_many_threads_matches_one_thread() {
+        let world = World::default_world();
+        let camera = default_camera(11);
+        let settings = RenderSettings {
+            threads: 1,
+            ..RenderSettings::default()
+        };
+        let single = camera.render_with(&world, &settings, |_| {});
+        let settings = RenderSettings {
+            threads: 4,
+            ..settings
+        };
+        let many = camera.render_with(&world, &settings, |_| {});
+        assert_eq!(single.to_ppm(), many.to_ppm());
+    }
+
+    #[test]
+    fn rendering_reports_progress_for_every_row() {
+        let world = World::default_world();
+        let camera = default_camera(5);
+        let reported = AtomicUsize::new(0);
+        camera.render_with(&world, &RenderSettings::default(), |rows| {
+            reported.fetch_max(rows, Ordering::Relaxed);
+        });
+        assert_eq!(reported.into_inner(), 5);
+    }
+
+    #[test]
+    fn adaptive_rendering_refines_only_edges() {
+        let world = World::default_world();
+        let camera = default_camera(21);
+        let adaptive = Adaptive {
+            threshold: 0.1,
+            max_subdivisions: 2,
+        };
+        let render = camera.render_adaptive(&world, &RenderSettings::default(), &adaptive, |_| {});
+        // The background is flat, the sphere's silhouette is not
+        assert_eq!(render.samples_at(0, 0), 1);
+        assert!(render.samples_at(7, 10) > 1);
+        let single = render
+            .samples
+            .iter()
+            .filter(|&&samples| samples == 1)
+            .count();
+        assert!(single > 21 * 21 / 2);
+        assert!(render.samples.iter().all(|&samples| samples <= 1 + 4 + 16));
+    }
+
+    #[test]
+    fn adaptive_rendering_without_subdivisions_matches_one_sample() {
+        let world = World::default_world();
+        let camera = default_camera(11);
+        let settings = RenderSettings {
+            adaptive: Some(Adaptive {
+                threshold: 0.0,
+                max_subdivisions: 0,
+            }),
+            ..RenderSettings::default()
+        };
+        let adaptive = camera.render_with(&world, &settings, |_| {});
+        assert_eq!(adaptive.to_ppm(), camera.render(&world).to_ppm());
+    }
+
+    #[test]
+    fn heatmap_shows_where_samples_went() {
+        let render = AdaptiveRender {
+            image: Canvas::new(3, 1),
+            samples: vec![1, 3, 5],
+            width: 3,
+            height: 1,
+        };
+        let heatmap = render.heatmap();
+        let at = |x: f64| *heatmap.pixel_at(&Tuple::point(x, 0.0, 0.0)).unwrap();
+        assert_eq!(at(0.0), Color::black());
+        assert_eq!(at(1.0), Color::new(0.0, 0.5, 0.5));
+        assert_eq!(at(2.0), Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn renders_with_the_same_seed_are_identical() {
+        let world = World::default_world();
+        let camera = default_camera(11);
+        let settings = RenderSettings {
+            samples: 4,
+            strategy: SampleStrategy::Random,
+            filter: Filter::Mitchell,
+            seed: 42,
+            ..RenderSettings::default()
+        };
+        let first = camera.render_with(&world, &settings, |_| {});
+        let second = camera.render_with(&world, &settings, |_| {});
+        assert_eq!(first.to_ppm(), second.to_ppm());
+    }
+
+    #[test]
+    fn tent_filter_favors_samples_near_the_center() {
+        let world = World::default_world();
+        let camera = default_camera(11);
+        let settings = RenderSettings {
+            samples: 4,
+            filter: Filter::Tent,
+            ..RenderSettings::default()
+        };
+        // Tent samples spread over two pixels, each half a pixel off center
+        let offsets = [(4.5, 4.5), (5.5, 4.5), (4.5, 5.5), (5.5, 5.5)];
+        let expected: Color = offsets
+            .iter()
+            .map(|&(x, y)| world.color_at(&camera.ray_through(x + 0.5, y + 0.5)))
+            .sum::<Color>()
+            / 4.0;
+        let image = camera.render_with(&world, &settings, |_| {});
+        let color = image.pixel_at(&Tuple::point(5.0, 5.0, 0.0)).unwrap();
+        assert!(color.is_equal(&expected));
+    }
+
+    #[test]
+    fn supersampling_averages_rays_across_the_pixel() {
+        let world = World::default_world();
+        let camera = default_camera(11);
+        let settings = RenderSettings {
+            samples: 4,
+            ..RenderSettings::default()
+        };
+        let expected: Color = [(5.25, 5.25), (5.75, 5.25), (5.25, 5.75), (5.75, 5.75)]
+            .iter()
+            .map(|&(x, y)| world.color_at(&camera.ray_through(x, y)))
+            .sum::<Color>()
+            / 4.0;
+        let image = camera.render_with(&world, &settings, |_| {});
+        let color = image.pixel_at(&Tuple::point(5.0, 5.0, 0.0)).unwrap();
+        assert!(color.is_equal(&expected));
+    }
+
+    #[test]
+    fn lens_rays_meet_on_the_focal_plane() {
+        let mut camera = default_camera(11);
+        camera.aperture = 0.5;
+        camera.focal_distance = 5.0;
+        // The camera sits at z = -5, so the focal plane is z = 0
+        let pinhole = camera.ray_through(3.2, 7.1);
+        let focus = pinhole.position(5.0 / pinhole.direction.z);
+        for lens in [(0.0, 0.0), (0.5, 0.5), (1.0, 0.3), (0.2, 0.9)] {
+            let ray = camera.ray_through_lens(3.2, 7.1, lens);
+            assert!(is_float_equal(ray.origin.z, -5.0));
+            assert!(ray.origin.x.hypot(ray.origin.y) <= 0.25 + 1e-9);
+            let crossing = ray.position(-ray.origin.z / ray.direction.z);
+            assert!(crossing.is_equal(&focus));
+        }
+    }
+
+    #[test]
+    fn a_zero_aperture_renders_like_a_pinhole() {
+        let world = World::default_world();
+        let pinhole = default_camera(11);
+        let mut lens = pinhole.clone();
+        lens.focal_distance = 3.0;
+        let settings = RenderSettings {
+            samples: 4,
+            strategy: SampleStrategy::Jittered,
+            seed: 7,
+            ..RenderSettings::default()
+        };
+        assert_eq!(
+            pinhole.render_with(&world, &settings, |_| {}).to_ppm(),
+            lens.render_with(&world, &settings, |_| {}).to_ppm()
+        );
+    }
+
+    #[test]
+    fn a_wide_aperture_blurs_what_is_out_of_focus() {
+        let world = World::default_world();
+        let pinhole = default_camera(11);
+        let mut lens = pinhole.clone();
+        lens.aperture = 1.0;
+        lens.focal_distance = 20.0;
+        let settings = RenderSettings {
+            samples: 4,
+            ..RenderSettings::default()
+        };
+        assert_ne!(
+            pinhole.render_with(&world, &settings, |_| {}).to_ppm(),
+            lens.render_with(&world, &settings, |_| {}).to_ppm()
+        );
+    }
+
+    #[test]
+    fn moving_objects_blur_across_the_exposure() {
+        let mut world = World::default_world();
+        let end = Keyframe {
+            translation: Tuple::vector(2.0, 0.0, 0.0),
+            ..Keyframe::identity()
+        };
+        let mut sphere = Sphere::with_transform(Matrix::identity().scale(0.5, 0.5, 0.5));
+        sphere.set_motion(Motion::new(Keyframe::identity(), end));
+        world.objects = vec![Box::new(sphere)];
+        let mut camera = default_camera(11);
+        let still = camera.render(&world);
+        camera.shutter_close = 1.0;
+        let settings = RenderSettings {
+            samples: 16,
+            ..RenderSettings::default()
+        };
+        let blurred = camera.render_with(&world, &settings, |_| {});
+
+        // The sphere leaves the center pixel and ends up two pixels right
+        let brightness = |image: &Canvas, x: f64| {
+            let color = image.pixel_at(&Tuple::point(x, 5.0, 0.0)).unwrap();
+            color.red + color.green + color.blue
+        };
+        assert!(brightness(&blurred, 5.0) < brightness(&still, 5.0));
+        assert!(brightness(&blurred, 5.0) > 0.0);
+        assert_eq!(brightness(&still, 7.0), 0.0);
+        assert!(brightness(&blurred, 7.0) > 0.0);
+    }
+
+    #[test]
+    fn path_traced_renders_are_reproducible() {
+        let world = World::default_world();
+        let camera = default_camera(5);
+        let settings = RenderSettings {
+            samples: 4,
+            integrator: Integrator::PathTracing,
+            seed: 3,
+            ..RenderSettings::default()
+        };
+        let first = camera.render_with(&world, &settings, |_| {});
+        let second = camera.render_with(&world, &settings, |_| {});
+        assert_eq!(first.to_ppm(), second.to_ppm());
+        let whitted = camera.render(&world);
+        assert_ne!(first.to_ppm(), whitted.to_ppm());
+    }
+
+    #[test]
+    fn rendering_a_world_with_a_camera() {
+        let world = World::default_world();
+        let camera = default_camera(11);
+        let image = camera.render(&world);
+        let color = image.pixel_at(&Tuple::point(5.0, 5.0, 0.0)).unwrap();
+        assert!(color.is_equal(&Color::new(0.38066, 0.47583, 0.2855)));
+    }
+
+    #[test]
+    fn setting_a_non_invertible_camera_transform_is_an_error() {
+        let mut camera = default_camera(11);
+        let before = camera.transform().clone();
+        let result = camera.set_transform(Matrix::identity().scale(1.0, 0.0, 1.0));
+        assert!(matches!(result, Err(Error::NonInvertibleMatrix)));
+        assert_eq!(camera.transform(), &before);
+    }
+
+    #[test]
+    fn constructing_a_ray_when_the_camera_is_transformed() {
+        let mut camera = Camera::new(201, 101, PI / 2.0);
+        camera
+            .set_transform(
+                Matrix::identity()
+                    .translate(0.0, -2.0, 5.0)
+                    .rotate_y(PI / 4.0),
+            )
+            .unwrap();
+        let ray = camera.ray_for_pixel(100, 50);
+        assert!(ray.origin.is_equal(&Tuple::point(0.0, 2.0, -5.0)));
+        assert!(ray
+            .direction
+            .is_equal(&Tuple::vector(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2)));
+    }
+
+    #[test]
+    fn constructing_a_ray_through_a_corner_of_the_canvas() {
+        let camera = Camera::new(201, 101, PI / 2.0);
+        let ray = camera.ray_for_pixel(0, 0);
+        assert!(ray.origin.is_equal(&Tuple::point(0.0, 0.0, 0.0)));
+        assert!(ray
+            .direction
+            .is_equal(&Tuple::vector(0.66519, 0.33259, -0.66851)));
+    }
+
+    #[test]
+    fn constructing_a_ray_through_the_center_of_the_canvas() {
+        let camera = Camera::new(201, 101, PI / 2.0);
+        let ray = camera.ray_for_pixel(100, 50);
+        assert!(ray.origin.is_equal(&Tuple::point(0.0, 0.0, 0.0)));
+        assert!(ray.direction.is_equal(&Tuple::vector(0.0, 0.0, -1.0)));
+    }
+
+    #[test]
+    fn pixel_size_for_a_vertical_canvas() {
+        let camera = Camera::new(125, 200, PI / 2.0);
+        assert!(is_float_equal(camera.pixel_size(), 0.01));
+    }
+
+    #[test]
+    fn pixel_size_for_a_horizontal_canvas() {
+        let camera = Camera::new(200, 125, PI / 2.0);
+        assert!(is_float_equal(camera.pixel_size(), 0.01));
+    }
+
+    #[test]
+    fn constructing_a_camera() {
+        let camera = Camera::new(160, 120, PI / 2.0);
+        assert_eq!(camera.hsize, 160);
+        assert_eq!(camera.vsize, 120);
+        assert_eq!(camera.field_of_view, PI / 2.0);
+        assert_eq!(camera.aperture, 0.0);
+        assert_eq!(camera.focal_distance, 1.0);
+        assert_eq!((camera.shutter_open, camera.shutter_close), (0.0, 0.0));
+        assert_eq!(camera.transform(), &Matrix::identity());
+    }
+
+    #[test]
+    fn resizing_a_camera_keeps_everything_but_the_canvas() {
+        let mut camera = default_camera(11);
+        camera.aperture = 0.5;
+        camera.focal_distance = 4.0;
+        camera.shutter_close = 1.0;
+        let resized = camera.clone().with_size(200, 125);
+        assert_eq!((resized.hsize, resized.vsize), (200, 125));
+        assert_eq!(
+            resized.pixel_size(),
+            Camera::new(200, 125, PI / 2.0).pixel_size()
+        );
+        assert_eq!(resized.transform(), camera.transform());
+        assert_eq!(
+            (
+                resized.aperture,
+                resized.focal_distance,
+                resized.shutter_close
+            ),
+            (0.5, 4.0, 1.0)
+        );
+    }
+}
@@ -1,7 +1,28 @@
+use crate::error::{Error, Result};
 use crate::tuple::{Color, Tuple};
 
 const MAX_PPM_VALUE: i32 = 255;
 const PPM_LINE_SIZE: i32 = 70;
+/// Side of the square windows SSIM compares, in pixels.
+const SSIM_WINDOW: i32 = 8;
+/// Stabilizers for SSIM's ratios, (0.01 * peak)^2 and (0.03 * peak)^2 for
+/// a peak of 1.
+const SSIM_C1: f64 = 0.0001;
+const SSIM_C2: f64 = 0.0009;
+/// Lobes on either side of the center of the Lanczos kernel.
+const LANCZOS_LOBES: f64 = 3.0;
+
+/// How `Canvas::resize` works out pixels between the source's.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Resample {
+    /// Blends the four nearest pixels. Fast and smooth, but blurs when
+    /// enlarging and lets fine detail alias when shrinking.
+    Bilinear,
+    /// A windowed sinc over three pixels either way, widened when
+    /// shrinking. Sharper than bilinear, at the cost of slight ringing
+    /// around hard edges.
+    Lanczos,
+}
 
 pub struct Canvas {
     pub width: i32,
@@ -10,18 +31,143 @@ pub struct Canvas {
 }
 
 impl Canvas {
+    /// Panics if either dimension isn't positive, see `try_new`.
     pub fn new(width: i32, height: i32) -> Self {
-        let capacity = width * height;
+        Self::try_new(width, height).expect("canvas size must be positive")
+    }
+
+    pub fn try_new(width: i32, height: i32) -> Result<Self> {
+        let capacity = match width.checked_mul(height) {
+            Some(capacity) if width > 0 && height > 0 => capacity,
+            _ => return Err(Error::InvalidCanvasSize { width, height }),
+        };
         let mut pixels = Vec::with_capacity(capacity as usize);
         for _ in 0..capacity {
             pixels.push(Color::black());
         }
 
-        Self {
+        Ok(Self {
             width,
             height,
             pixels,
+        })
+    }
+
+    /// Reads a plain-text (P3) or binary (P6) PPM image, scaling its values
+    /// to between 0 and 1.
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self> {
+        let invalid = |message: &str| Error::InvalidImage(message.to_string());
+        let mut reader = PpmReader { bytes, position: 0 };
+        let magic = reader.token().ok_or_else(|| invalid("empty file"))?;
+        if magic != b"P3" && magic != b"P6" {
+            return Err(invalid("not a P3 or P6 PPM"));
+        }
+        let mut header = [0; 3];
+        for value in &mut header {
+            *value = reader.number().ok_or_else(|| invalid("bad PPM header"))?;
         }
+        let [width, height, max] = header;
+        if max == 0 || max > u16::MAX as u32 {
+            return Err(invalid("bad maximum value"));
+        }
+
+        // Checked against the data before allocating, so a header can't ask
+        // for more memory than the file backs up
+        let count = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| invalid("image too large"))?;
+        let values: Vec<u32> = if magic == b"P3" {
+            (0..count).map_while(|_| reader.number()).collect()
+        } else {
+            // A single whitespace byte separates the header from the data
+            let data = bytes.get(reader.position + 1..).unwrap_or_default();
+            if max < 256 {
+                data.iter().map(|&byte| byte as u32).collect()
+            } else {
+                let pairs = data.chunks_exact(2);
+                pairs
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as u32)
+                    .collect()
+            }
+        };
+        if values.len() < count {
+            return Err(invalid("not enough pixel data"));
+        }
+        let mut canvas = Self::try_new(width as i32, height as i32)?;
+        let scale = |value: u32| value as f64 / max as f64;
+        for (pixel, rgb) in canvas.pixels.iter_mut().zip(values.chunks_exact(3)) {
+            *pixel = Color::new(scale(rgb[0]), scale(rgb[1]), scale(rgb[2]));
+        }
+        Ok(canvas)
+    }
+
+    /// Reads a Radiance HDR (RGBE) image, flat or run-length encoded. Unlike
+    /// PPM its values can go past 1, which makes it good for lighting.
+    pub fn from_hdr(bytes: &[u8]) -> Result<Self> {
+        let invalid = |message: &str| Error::InvalidImage(message.to_string());
+        if !bytes.starts_with(b"#?") {
+            return Err(invalid("not a Radiance HDR"));
+        }
+        let mut lines = bytes.split(|&byte| byte == b'\n');
+        let mut position = 0;
+        // Header lines run up to a blank one
+        for line in lines.by_ref() {
+            position += line.len() + 1;
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with(b"FORMAT=") && line != b"FORMAT=32-bit_rle_rgbe" {
+                return Err(invalid("unsupported HDR pixel format"));
+            }
+        }
+        let resolution = lines
+            .next()
+            .ok_or_else(|| invalid("missing HDR resolution"))?;
+        position += resolution.len() + 1;
+        let resolution = std::str::from_utf8(resolution).unwrap_or_default();
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => (height.parse::<i32>().ok(), width.parse::<i32>().ok()),
+            _ => (None, None),
+        };
+        let (Some(height), Some(width)) = (height, width) else {
+            return Err(invalid("unsupported HDR orientation"));
+        };
+        // Checked against the data before allocating, like PPM headers
+        let mut data = bytes.get(position..).unwrap_or_default();
+        let scanline = min_rgbe_scanline_bytes(width.max(0) as usize);
+        match (height.max(0) as usize).checked_mul(scanline) {
+            Some(needed) if needed <= data.len() => {}
+            _ => return Err(invalid("not enough pixel data")),
+        }
+        let mut canvas = Self::try_new(width, height)?;
+        let width = width as usize;
+        for row in canvas.pixels.chunks_exact_mut(width) {
+            let scanline = read_rgbe_scanline(&mut data, width)
+                .ok_or_else(|| invalid("not enough pixel data"))?;
+            for (pixel, rgbe) in row.iter_mut().zip(scanline) {
+                *pixel = rgbe_to_color(rgbe);
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// The color at texture coordinates (`u`, `v`), blending the four
+    /// nearest pixels. `u` runs left to right and `v` bottom to top, both
+    /// wrapping around outside 0 to 1 so images tile.
+    pub(crate) fn sample(&self, u: f64, v: f64) -> Color {
+        let x = u.rem_euclid(1.0) * self.width as f64 - 0.5;
+        let y = (1.0 - v.rem_euclid(1.0)) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let pixel = |x: f64, y: f64| {
+            let x = (x as i32).rem_euclid(self.width);
+            let y = (y as i32).rem_euclid(self.height);
+            self.pixels[(y * self.width + x) as usize]
+        };
+        let top = pixel(x0, y0).lerp(&pixel(x0 + 1.0, y0), tx);
+        let bottom = pixel(x0, y0 + 1.0).lerp(&pixel(x0 + 1.0, y0 + 1.0), tx);
+        top.lerp(&bottom, ty)
     }
 
     pub fn write_pixel(&mut self, point: &Tuple, pixel: Color) {
@@ -31,11 +177,142 @@ impl Canvas {
         }
     }
 
-    fn pixel_at(&self, point: &Tuple) -> Option<&Color> {
+    #[cfg(test)]
+    pub(crate) fn pixel_at(&self, point: &Tuple) -> Option<&Color> {
         let idx = self.point_to_index(point);
         self.pixels.get(idx)
     }
 
+    /// The color at column `x` and row `y`, counting from the top left, or
+    /// `None` outside the canvas.
+    pub fn pixel(&self, x: i32, y: i32) -> Option<Color> {
+        self.index(x, y).map(|index| self.pixels[index])
+    }
+
+    /// Like `write_pixel`, by column and row. Does nothing outside the
+    /// canvas.
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = color;
+        }
+    }
+
+    /// The rows of pixels, from the top.
+    pub fn rows(&self) -> impl Iterator<Item = &[Color]> {
+        self.pixels.chunks(self.width as usize)
+    }
+
+    /// Every pixel with its column and row, row by row from the top left.
+    pub fn pixels(&self) -> impl Iterator<Item = (i32, i32, Color)> + '_ {
+        let width = self.width;
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(move |(index, color)| (index as i32 % width, index as i32 / width, *color))
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    /// A copy of the `width` by `height` pixels from column `x` and row `y`
+    /// on, clipped to the canvas. An error if nothing is left.
+    pub fn crop(&self, x: i32, y: i32, width: i32, height: i32) -> Result<Canvas> {
+        let (left, top) = (x.max(0), y.max(0));
+        let right = x.saturating_add(width).min(self.width);
+        let bottom = y.saturating_add(height).min(self.height);
+        let mut cropped = Canvas::try_new(right - left, bottom - top)?;
+        for (row, source) in cropped
+            .pixels
+            .chunks_mut((right - left) as usize)
+            .zip(self.rows().skip(top as usize))
+        {
+            row.copy_from_slice(&source[left as usize..right as usize]);
+        }
+        Ok(cropped)
+    }
+
+    /// Draws `source` over this canvas with its top left corner at column
+    /// `x` and row `y`, blended by `alpha`: 1 pastes it as it is, 0 leaves
+    /// the canvas as it was. Whatever falls outside the canvas is dropped.
+    pub fn blit(&mut self, source: &Canvas, x: i32, y: i32, alpha: f64) {
+        for (source_x, source_y, color) in source.pixels() {
+            if let Some(index) = self.index(x + source_x, y + source_y) {
+                self.pixels[index] = self.pixels[index].lerp(&color, alpha);
+            }
+        }
+    }
+
+    /// Mirrors the canvas left to right.
+    pub fn flip_horizontal(&mut self) {
+        for row in self.pixels.chunks_mut(self.width as usize) {
+            row.reverse();
+        }
+    }
+
+    /// Mirrors the canvas top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((height - 1 - y) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+    }
+
+    /// A copy scaled to `width` by `height`, an error if either isn't
+    /// positive. The picture is stretched to fit if the aspect ratio
+    /// changes.
+    pub fn resize(&self, width: i32, height: i32, resample: Resample) -> Result<Canvas> {
+        let mut resized = Canvas::try_new(width, height)?;
+        match resample {
+            Resample::Bilinear => {
+                let scale_x = self.width as f64 / width as f64;
+                let scale_y = self.height as f64 / height as f64;
+                let clamped = |x: f64, y: f64| {
+                    let x = (x as i32).clamp(0, self.width - 1);
+                    let y = (y as i32).clamp(0, self.height - 1);
+                    self.pixels[(y * self.width + x) as usize]
+                };
+                for (index, pixel) in resized.pixels.iter_mut().enumerate() {
+                    let (x, y) = (index as i32 % width, index as i32 / width);
+                    // Pixel centers line up, rather than corners
+                    let source_x = ((x as f64 + 0.5) * scale_x - 0.5).max(0.0);
+                    let source_y = ((y as f64 + 0.5) * scale_y - 0.5).max(0.0);
+                    let (x0, y0) = (source_x.floor(), source_y.floor());
+                    let (tx, ty) = (source_x - x0, source_y - y0);
+                    let top = clamped(x0, y0).lerp(&clamped(x0 + 1.0, y0), tx);
+                    let bottom = clamped(x0, y0 + 1.0).lerp(&clamped(x0 + 1.0, y0 + 1.0), tx);
+                    *pixel = top.lerp(&bottom, ty);
+                }
+            }
+            // Separable: across each row first, then down each column
+            Resample::Lanczos => {
+                let across = lanczos_weights(self.width, width);
+                let down = lanczos_weights(self.height, height);
+                let mut wide = Canvas::try_new(width, self.height)?;
+                for (source, row) in self.rows().zip(wide.pixels.chunks_mut(width as usize)) {
+                    for (pixel, taps) in row.iter_mut().zip(&across) {
+                        *pixel = taps.iter().map(|&(i, weight)| source[i] * weight).sum();
+                    }
+                }
+                for (index, pixel) in resized.pixels.iter_mut().enumerate() {
+                    let (x, y) = (index % width as usize, index / width as usize);
+                    *pixel = down[y]
+                        .iter()
+                        .map(|&(i, weight)| wide.pixels[i * width as usize + x] * weight)
+                        .sum();
+                }
+            }
+        }
+        Ok(resized)
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let inside = (0..self.width).contains(&x) && (0..self.height).contains(&y);
+        inside.then(|| (y * self.width + x) as usize)
+    }
+
     fn point_to_index(&self, point: &Tuple) -> usize {
         (point.y as i32 * self.width + point.x as i32) as usize
     }
@@ -66,6 +343,286 @@ impl Canvas {
         ppm.push('\n');
         ppm
     }
+
+    /// The binary (P6) flavor of PPM: one byte per channel, much smaller and
+    /// faster to write than `to_ppm`.
+    pub fn to_ppm_binary(&self) -> Vec<u8> {
+        let header = format!("P6\n{} {}\n{}\n", self.width, self.height, MAX_PPM_VALUE);
+        let mut ppm = header.into_bytes();
+        ppm.reserve(self.pixels.len() * 3);
+        for color in &self.pixels {
+            for value in color.iter() {
+                ppm.push(scale_value(value, MAX_PPM_VALUE) as u8);
+            }
+        }
+        ppm
+    }
+
+    /// Mean squared error between every channel of the two canvases.
+    /// Panics if the canvases differ in size, as do the other comparisons.
+    pub fn mse(&self, other: &Canvas) -> f64 {
+        let squared: f64 = self
+            .channel_pairs(other)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum();
+        squared / (self.pixels.len() * 3) as f64
+    }
+
+    /// Peak signal-to-noise ratio in decibels, taking white as the peak.
+    /// Higher is closer, and identical canvases give infinity.
+    pub fn psnr(&self, other: &Canvas) -> f64 {
+        -10.0 * self.mse(other).log10()
+    }
+
+    /// The largest difference in any channel of any pixel.
+    pub fn max_abs_error(&self, other: &Canvas) -> f64 {
+        self.channel_pairs(other)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Structural similarity of the two canvases' luminance, averaged over
+    /// every 8x8 window (or the whole canvas, if smaller). 1 for identical
+    /// canvases, less the more their local brightness, contrast and
+    /// structure differ. Unlike MSE it shrugs off noise that keeps the
+    /// picture recognizable.
+    pub fn ssim(&self, other: &Canvas) -> f64 {
+        self.assert_same_size(other);
+        let luma = |canvas: &Canvas| -> Vec<f64> {
+            canvas
+                .pixels
+                .iter()
+                .map(|color| 0.2126 * color.red + 0.7152 * color.green + 0.0722 * color.blue)
+                .collect()
+        };
+        let (a, b) = (luma(self), luma(other));
+        let (window_width, window_height) =
+            (SSIM_WINDOW.min(self.width), SSIM_WINDOW.min(self.height));
+        let count = (window_width * window_height) as f64;
+
+        let mut total = 0.0;
+        let mut windows = 0;
+        for top in 0..=self.height - window_height {
+            for left in 0..=self.width - window_width {
+                let indices = (top..top + window_height).flat_map(|y| {
+                    (left..left + window_width).map(move |x| (y * self.width + x) as usize)
+                });
+                let (mut sum_a, mut sum_b) = (0.0, 0.0);
+                let (mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0);
+                for i in indices {
+                    sum_a += a[i];
+                    sum_b += b[i];
+                    sum_aa += a[i] * a[i];
+                    sum_bb += b[i] * b[i];
+                    sum_ab += a[i] * b[i];
+                }
+                let (mean_a, mean_b) = (sum_a / count, sum_b / count);
+                let variance_a = sum_aa / count - mean_a * mean_a;
+                let variance_b = sum_bb / count - mean_b * mean_b;
+                let covariance = sum_ab / count - mean_a * mean_b;
+                total += (2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2)
+                    / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1)
+                        * (variance_a + variance_b + SSIM_C2));
+                windows += 1;
+            }
+        }
+        total / windows as f64
+    }
+
+    /// A false-color picture of where the canvases differ, by the largest
+    /// difference in any channel of each pixel: black where they agree,
+    /// through blue and green to red where they are `full_scale` or more
+    /// apart.
+    pub fn difference(&self, other: &Canvas, full_scale: f64) -> Canvas {
+        self.assert_same_size(other);
+        let mut difference = Canvas::new(self.width, self.height);
+        for (pixel, (a, b)) in difference
+            .pixels
+            .iter_mut()
+            .zip(self.pixels.iter().zip(&other.pixels))
+        {
+            let error = a
+                .iter()
+                .zip(b.iter())
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            *pixel = heat_color(error / full_scale);
+        }
+        difference
+    }
+
+    // Matching channels of matching pixels
+    fn channel_pairs<'a>(&'a self, other: &'a Canvas) -> impl Iterator<Item = (f64, f64)> + 'a {
+        self.assert_same_size(other);
+        self.pixels
+            .iter()
+            .zip(&other.pixels)
+            .flat_map(|(a, b)| a.iter().zip(b.iter()))
+    }
+
+    fn assert_same_size(&self, other: &Canvas) {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "can't compare a {}x{} canvas with a {}x{} one",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+    }
+}
+
+/// For each of `to` pixels in a row or column resampled from `from` ones,
+/// which source pixels it takes from and by how much. Pixels past the ends
+/// stand in for the edge pixels.
+fn lanczos_weights(from: i32, to: i32) -> Vec<Vec<(usize, f64)>> {
+    let scale = from as f64 / to as f64;
+    // Shrinking stretches the kernel, so it also filters out detail too
+    // fine to show
+    let stretch = scale.max(1.0);
+    let radius = LANCZOS_LOBES * stretch;
+    let kernel = |x: f64| {
+        if x == 0.0 {
+            1.0
+        } else if x.abs() >= LANCZOS_LOBES {
+            0.0
+        } else {
+            let pi_x = std::f64::consts::PI * x;
+            LANCZOS_LOBES * pi_x.sin() * (pi_x / LANCZOS_LOBES).sin() / (pi_x * pi_x)
+        }
+    };
+    (0..to)
+        .map(|target| {
+            let center = (target as f64 + 0.5) * scale - 0.5;
+            let first = (center - radius).ceil() as i32;
+            let last = (center + radius).floor() as i32;
+            let taps: Vec<(usize, f64)> = (first..=last)
+                .map(|i| {
+                    let source = i.clamp(0, from - 1) as usize;
+                    (source, kernel((i as f64 - center) / stretch))
+                })
+                .collect();
+            let total: f64 = taps.iter().map(|(_, weight)| weight).sum();
+            taps.into_iter()
+                .map(|(source, weight)| (source, weight / total))
+                .collect()
+        })
+        .collect()
+}
+
+/// A color for `t` between 0 and 1 on a heat scale, for debug images: black,
+/// through blue and green, to red. Values outside are clamped.
+pub(crate) fn heat_color(t: f64) -> Color {
+    let ramp = [
+        Color::black(),
+        Color::new(0.0, 0.0, 1.0),
+        Color::new(0.0, 1.0, 0.0),
+        Color::new(1.0, 0.0, 0.0),
+    ];
+    let scaled = t.clamp(0.0, 1.0) * (ramp.len() - 1) as f64;
+    let index = (scaled.floor() as usize).min(ramp.len() - 2);
+    ramp[index].lerp(&ramp[index + 1], scaled - index as f64)
+}
+
+/// One row of RGBE pixels, taken off the front of `data`.
+/// The fewest bytes a scanline `width` pixels wide can take: runs of 127
+/// pixels in every channel if it can be run-length encoded, or 4 bytes a
+/// pixel if not.
+fn min_rgbe_scanline_bytes(width: usize) -> usize {
+    if (8..0x8000).contains(&width) {
+        4 + 4 * 2 * width.div_ceil(127)
+    } else {
+        4 * width
+    }
+}
+
+fn read_rgbe_scanline(data: &mut &[u8], width: usize) -> Option<Vec<[u8; 4]>> {
+    let header: [u8; 4] = data.get(..4)?.try_into().ok()?;
+    let mut take = |count: usize| {
+        let (taken, rest) = (data.get(..count)?, data.get(count..)?);
+        *data = rest;
+        Some(taken)
+    };
+    let run_length_encoded = (8..0x8000).contains(&width)
+        && header[0] == 2
+        && header[1] == 2
+        && usize::from(header[2]) << 8 | usize::from(header[3]) == width;
+    if !run_length_encoded {
+        let flat = take(width * 4)?;
+        return Some(
+            flat.chunks_exact(4)
+                .map(|rgbe| [rgbe[0], rgbe[1], rgbe[2], rgbe[3]])
+                .collect(),
+        );
+    }
+
+    // Each channel is stored separately, as runs of one repeated byte and
+    // stretches of literal bytes
+    take(4)?;
+    let mut scanline = vec![[0; 4]; width];
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = take(1)?[0] as usize;
+            let (length, bytes) = if count > 128 {
+                (count - 128, vec![take(1)?[0]; count - 128])
+            } else {
+                (count, take(count)?.to_vec())
+            };
+            if length == 0 || x + length > width {
+                return None;
+            }
+            for (pixel, byte) in scanline[x..x + length].iter_mut().zip(bytes) {
+                pixel[channel] = byte;
+            }
+            x += length;
+        }
+    }
+    Some(scanline)
+}
+
+/// Three mantissas sharing one exponent.
+fn rgbe_to_color([red, green, blue, exponent]: [u8; 4]) -> Color {
+    if exponent == 0 {
+        return Color::black();
+    }
+    let scale = 2f64.powi(exponent as i32 - (128 + 8));
+    Color::new(
+        red as f64 * scale,
+        green as f64 * scale,
+        blue as f64 * scale,
+    )
+}
+
+struct PpmReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PpmReader<'a> {
+    // The next run of non-whitespace, skipping comments
+    fn token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.bytes.get(self.position)? {
+                b'#' => {
+                    while !matches!(self.bytes.get(self.position), Some(b'\n') | None) {
+                        self.position += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+        let start = self.position;
+        while matches!(self.bytes.get(self.position), Some(byte) if !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+        Some(&self.bytes[start..self.position])
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        std::str::from_utf8(self.token()?).ok()?.parse().ok()
+    }
 }
 
 fn scale_value(value: f64, max: i32) -> i32 {
@@ -77,6 +634,7 @@ fn scale_value(value: f64, max: i32) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::is_float_equal;
 
     #[test]
     fn scale_value_clamps_values_bewteen_zero_and_max() {
@@ -125,6 +683,343 @@ mod tests {
         assert_eq!(ppm, expected_ppm);
     }
 
+    #[test]
+    fn canvas_to_binary_ppm() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(&Tuple::point(1.0, 0.0, 0.0), Color::new(1.5, 0.5, -0.5));
+        let ppm = canvas.to_ppm_binary();
+        let mut expected = b"P6\n2 1\n255\n".to_vec();
+        expected.extend([0, 0, 0, 255, 128, 0]);
+        assert_eq!(ppm, expected);
+    }
+
+    #[test]
+    fn reading_a_plain_ppm() {
+        let ppm = b"P3\n# a comment\n2 2\n10\n10 0 5  0 10 0\n0 0 10 5 5 5\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!((canvas.width, canvas.height), (2, 2));
+        assert_eq!(canvas.pixels[0], Color::new(1.0, 0.0, 0.5));
+        assert_eq!(canvas.pixels[3], Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn reading_a_binary_ppm() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(&Tuple::point(1.0, 0.0, 0.0), Color::new(1.0, 0.0, 1.0));
+        let read = Canvas::from_ppm(&canvas.to_ppm_binary()).unwrap();
+        assert_eq!(read.pixels, canvas.pixels);
+    }
+
+    #[test]
+    fn reading_a_malformed_ppm_is_an_error() {
+        for ppm in [&b""[..], b"P5\n1 1\n255\n", b"P3\n2 1\n255\n1 2 3\n"] {
+            assert!(matches!(Canvas::from_ppm(ppm), Err(Error::InvalidImage(_))));
+        }
+    }
+
+    #[test]
+    fn huge_ppm_headers_without_the_data_are_an_error() {
+        for ppm in [
+            &b"P3 100000 100000 255\n1 2 3\n"[..],
+            b"P6 100000 100000 255\n\x01",
+        ] {
+            assert!(matches!(Canvas::from_ppm(ppm), Err(Error::InvalidImage(_))));
+        }
+    }
+
+    #[test]
+    fn reading_a_flat_hdr() {
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        // 128 with an exponent of 129 is 1, and 130 doubles that
+        hdr.extend([128, 64, 0, 129, 128, 128, 128, 130]);
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+        assert_eq!(
+            canvas.pixels,
+            [Color::new(1.0, 0.5, 0.0), Color::new(2.0, 2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn reading_a_run_length_encoded_hdr() {
+        let mut hdr = b"#?RGBE\n\n-Y 1 +X 8\n".to_vec();
+        hdr.extend([2, 2, 0, 8]);
+        // Red: a run of 8, green: 8 literals, blue: two runs, exponent: a run
+        hdr.extend([128 + 8, 128]);
+        hdr.extend([8, 0, 16, 32, 48, 64, 80, 96, 112]);
+        hdr.extend([128 + 3, 0, 128 + 5, 64]);
+        hdr.extend([128 + 8, 129]);
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+        assert_eq!(canvas.pixels[0], Color::new(1.0, 0.0, 0.0));
+        assert_eq!(canvas.pixels[7], Color::new(1.0, 0.875, 0.5));
+    }
+
+    #[test]
+    fn reading_a_malformed_hdr_is_an_error() {
+        for hdr in [
+            &b"P3\n1 1\n255\n0 0 0\n"[..],
+            b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n",
+            b"#?RADIANCE\n\n+Y 1 +X 1\n1 1 1 1",
+            b"#?RADIANCE\n\n-Y 2 +X 1\n\x80\x80\x80\x81",
+        ] {
+            assert!(matches!(Canvas::from_hdr(hdr), Err(Error::InvalidImage(_))));
+        }
+    }
+
+    #[test]
+    fn huge_hdr_headers_without_the_data_are_an_error() {
+        for hdr in [
+            &b"#?RADIANCE\n\n-Y 46000 +X 46000\n\x02\x02\xb3\xb0"[..],
+            b"#?RADIANCE\n\n-Y 46000 +X 4\n\x01\x01\x01\x80",
+        ] {
+            assert!(matches!(Canvas::from_hdr(hdr), Err(Error::InvalidImage(_))));
+        }
+    }
+
+    #[test]
+    fn sampling_blends_neighboring_pixels_and_wraps() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(&Tuple::point(1.0, 0.0, 0.0), Color::white());
+        // Pixel centers sit at u = 0.25 and 0.75
+        assert_eq!(canvas.sample(0.25, 0.5), Color::black());
+        assert_eq!(canvas.sample(0.5, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(canvas.sample(1.75, 0.5), Color::white());
+        assert_eq!(canvas.sample(0.0, 0.5), Color::new(0.5, 0.5, 0.5));
+    }
+
+    fn filled(width: i32, height: i32, color: impl Fn(i32, i32) -> Color) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                canvas.write_pixel(&Tuple::point(x as f64, y as f64, 0.0), color(x, y));
+            }
+        }
+        canvas
+    }
+
+    #[test]
+    fn identical_canvases_compare_perfectly() {
+        let canvas = filled(10, 10, |x, y| {
+            Color::new(x as f64 / 10.0, y as f64 / 10.0, 0.5)
+        });
+        assert_eq!(canvas.mse(&canvas), 0.0);
+        assert_eq!(canvas.psnr(&canvas), f64::INFINITY);
+        assert_eq!(canvas.max_abs_error(&canvas), 0.0);
+        assert!(is_float_equal(canvas.ssim(&canvas), 1.0));
+    }
+
+    #[test]
+    fn errors_between_canvases() {
+        let gray = filled(4, 4, |_, _| Color::new(0.5, 0.5, 0.5));
+        let mut brighter = filled(4, 4, |_, _| Color::new(0.6, 0.5, 0.5));
+        // A tenth off in a third of the channels
+        assert!(is_float_equal(gray.mse(&brighter), 0.01 / 3.0));
+        assert!(is_float_equal(
+            gray.psnr(&brighter),
+            20.0 + 10.0 * 3.0_f64.log10()
+        ));
+        brighter.write_pixel(&Tuple::point(1.0, 2.0, 0.0), Color::new(0.5, 0.5, 0.9));
+        assert!(is_float_equal(gray.max_abs_error(&brighter), 0.4));
+    }
+
+    #[test]
+    fn ssim_cares_more_about_structure_than_brightness() {
+        let stripes = filled(16, 16, |x, _| {
+            if x % 2 == 0 {
+                Color::new(0.8, 0.8, 0.8)
+            } else {
+                Color::new(0.2, 0.2, 0.2)
+            }
+        });
+        let brighter = filled(16, 16, |x, _| {
+            if x % 2 == 0 {
+                Color::new(0.9, 0.9, 0.9)
+            } else {
+                Color::new(0.3, 0.3, 0.3)
+            }
+        });
+        let inverted = filled(16, 16, |x, _| {
+            if x % 2 == 0 {
+                Color::new(0.2, 0.2, 0.2)
+            } else {
+                Color::new(0.8, 0.8, 0.8)
+            }
+        });
+        let gray = filled(16, 16, |_, _| Color::new(0.5, 0.5, 0.5));
+        assert!(stripes.ssim(&brighter) > 0.95);
+        assert!(stripes.ssim(&gray) < 0.1);
+        assert!(stripes.ssim(&inverted) < 0.0);
+        // And the other way round for MSE
+        assert!(stripes.mse(&brighter) < stripes.mse(&gray));
+        assert!(stripes.mse(&gray) < stripes.mse(&inverted));
+    }
+
+    #[test]
+    fn small_canvases_are_one_ssim_window() {
+        let dark = filled(3, 2, |_, _| Color::new(0.1, 0.1, 0.1));
+        let light = filled(3, 2, |_, _| Color::new(0.9, 0.9, 0.9));
+        // Flat, so only the brightness term counts
+        let expected = (2.0 * 0.1 * 0.9 + SSIM_C1) / (0.1 * 0.1 + 0.9 * 0.9 + SSIM_C1);
+        assert!(is_float_equal(dark.ssim(&light), expected));
+    }
+
+    #[test]
+    fn difference_images_heat_up_with_the_error() {
+        let black = Canvas::new(3, 1);
+        let errors = filled(3, 1, |x, _| Color::new(0.0, x as f64 * 0.1, 0.0));
+        let difference = black.difference(&errors, 0.2);
+        let at = |x: f64| *difference.pixel_at(&Tuple::point(x, 0.0, 0.0)).unwrap();
+        assert_eq!(at(0.0), Color::black());
+        assert!(at(1.0).is_equal(&Color::new(0.0, 0.5, 0.5)));
+        assert_eq!(at(2.0), Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "can't compare a 2x2 canvas with a 2x3 one")]
+    fn comparing_canvases_of_different_sizes_panics() {
+        Canvas::new(2, 2).mse(&Canvas::new(2, 3));
+    }
+
+    fn gray(value: f64) -> Color {
+        Color::new(value, value, value)
+    }
+
+    // A canvas whose pixels are numbered row by row, in the red channel
+    fn numbered(width: i32, height: i32) -> Canvas {
+        filled(width, height, |x, y| {
+            Color::new((y * width + x) as f64, 0.0, 0.0)
+        })
+    }
+
+    fn reds(canvas: &Canvas) -> Vec<f64> {
+        canvas.pixels().map(|(_, _, color)| color.red).collect()
+    }
+
+    #[test]
+    fn reading_and_setting_pixels_by_column_and_row() {
+        let mut canvas = numbered(3, 2);
+        assert_eq!(canvas.pixel(2, 1), Some(Color::new(5.0, 0.0, 0.0)));
+        for (x, y) in [(-1, 0), (3, 0), (0, 2)] {
+            assert_eq!(canvas.pixel(x, y), None);
+        }
+        canvas.set_pixel(1, 1, Color::white());
+        canvas.set_pixel(3, 0, Color::white());
+        assert_eq!(canvas.pixel(1, 1), Some(Color::white()));
+        assert_eq!(canvas.pixel(0, 1), Some(Color::new(3.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn iterating_over_rows_and_pixels() {
+        let canvas = numbered(3, 2);
+        let rows: Vec<&[Color]> = canvas.rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][0], Color::new(3.0, 0.0, 0.0));
+        let pixels: Vec<(i32, i32, Color)> = canvas.pixels().collect();
+        assert_eq!(pixels.len(), 6);
+        assert_eq!(pixels[4], (1, 1, Color::new(4.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn filling_a_canvas() {
+        let mut canvas = numbered(3, 2);
+        canvas.fill(gray(0.5));
+        assert!(canvas.pixels().all(|(_, _, color)| color == gray(0.5)));
+    }
+
+    #[test]
+    fn cropping_clips_to_the_canvas() {
+        let canvas = numbered(4, 3);
+        let middle = canvas.crop(1, 1, 2, 2).unwrap();
+        assert_eq!((middle.width, middle.height), (2, 2));
+        assert_eq!(reds(&middle), vec![5.0, 6.0, 9.0, 10.0]);
+        let corner = canvas.crop(-2, 2, 3, 5).unwrap();
+        assert_eq!(reds(&corner), vec![8.0]);
+        assert!(matches!(
+            canvas.crop(4, 0, 2, 2),
+            Err(Error::InvalidCanvasSize { .. })
+        ));
+    }
+
+    #[test]
+    fn blitting_blends_by_alpha_and_clips() {
+        let mut canvas = Canvas::new(3, 3);
+        let white = filled(2, 2, |_, _| Color::white());
+        canvas.blit(&white, 2, -1, 1.0);
+        canvas.blit(&white, 0, 1, 0.25);
+        assert_eq!(canvas.pixel(2, 0), Some(Color::white()));
+        assert_eq!(canvas.pixel(1, 0), Some(Color::black()));
+        assert_eq!(canvas.pixel(1, 2), Some(gray(0.25)));
+        assert_eq!(canvas.pixel(2, 2), Some(Color::black()));
+    }
+
+    #[test]
+    fn flipping_mirrors_the_canvas() {
+        let mut canvas = numbered(3, 3);
+        canvas.flip_horizontal();
+        assert_eq!(
+            reds(&canvas),
+            vec![2.0, 1.0, 0.0, 5.0, 4.0, 3.0, 8.0, 7.0, 6.0]
+        );
+        canvas.flip_vertical();
+        assert_eq!(
+            reds(&canvas),
+            vec![8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0, 0.0]
+        );
+        let mut even = numbered(1, 4);
+        even.flip_vertical();
+        assert_eq!(reds(&even), vec![3.0, 2.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn bilinear_resizing_blends_between_pixel_centers() {
+        let canvas = filled(2, 1, |x, _| gray(x as f64));
+        let wide = canvas.resize(4, 1, Resample::Bilinear).unwrap();
+        let values: Vec<f64> = wide.pixels().map(|(_, _, color)| color.green).collect();
+        assert_eq!(values, vec![0.0, 0.25, 0.75, 1.0]);
+        let narrow = wide.resize(2, 2, Resample::Bilinear).unwrap();
+        assert!(narrow.pixel(0, 1).unwrap().is_equal(&gray(0.125)));
+    }
+
+    #[test]
+    fn resizing_to_the_same_size_changes_nothing() {
+        let canvas = filled(5, 4, |x, y| Color::new(x as f64 / 5.0, y as f64 / 4.0, 0.5));
+        for resample in [Resample::Bilinear, Resample::Lanczos] {
+            let same = canvas.resize(5, 4, resample).unwrap();
+            assert!(same.max_abs_error(&canvas) < 1e-9, "{:?}", resample);
+        }
+        assert!(matches!(
+            canvas.resize(0, 4, Resample::Bilinear),
+            Err(Error::InvalidCanvasSize { .. })
+        ));
+    }
+
+    #[test]
+    fn lanczos_resizing_keeps_flat_areas_flat() {
+        let canvas = filled(7, 5, |_, _| Color::new(0.2, 0.4, 0.6));
+        for (width, height) in [(3, 2), (16, 11)] {
+            let resized = canvas.resize(width, height, Resample::Lanczos).unwrap();
+            assert!(resized
+                .pixels()
+                .all(|(_, _, color)| color.is_equal(&Color::new(0.2, 0.4, 0.6))));
+        }
+    }
+
+    #[test]
+    fn lanczos_shrinking_averages_away_fine_detail() {
+        let stripes = filled(16, 16, |x, _| gray((x % 2) as f64));
+        let thumbnail = stripes.resize(4, 4, Resample::Lanczos).unwrap();
+        // Away from the edges, which repeat the outermost stripe
+        for (_, _, color) in thumbnail.pixels().filter(|(x, _, _)| (1..3).contains(x)) {
+            assert!((color.red - 0.5).abs() < 0.05, "{:?}", color);
+        }
+        // Sharper than bilinear when enlarging an edge
+        let edge = filled(4, 1, |x, _| gray(if x < 2 { 0.0 } else { 1.0 }));
+        let lanczos = edge.resize(16, 1, Resample::Lanczos).unwrap();
+        let bilinear = edge.resize(16, 1, Resample::Bilinear).unwrap();
+        let slope =
+            |canvas: &Canvas| canvas.pixel(8, 0).unwrap().red - canvas.pixel(7, 0).unwrap().red;
+        assert!(slope(&lanczos) > slope(&bilinear));
+    }
+
     #[test]
     fn canvas_to_ppm_with_no_pixels_has_header() {
         let canvas = Canvas::new(5, 3);
@@ -149,6 +1044,16 @@ mod tests {
         assert_eq!(canvas.pixel_at(&point).unwrap(), &Color::new(1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn canvas_sizes_must_be_positive() {
+        for (width, height) in [(0, 5), (5, -1), (i32::MAX, 2)] {
+            assert!(matches!(
+                Canvas::try_new(width, height),
+                Err(Error::InvalidCanvasSize { .. })
+            ));
+        }
+    }
+
     #[test]
     fn creating_a_new_canvas() {
         let canvas = Canvas::new(10, 20);
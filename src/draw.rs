@@ -0,0 +1,444 @@
+//! Drawing on a canvas in two dimensions: lines, shapes and text, for plots
+//! and debug overlays on renders. Coordinates are columns and rows from the
+//! top left, like `Canvas::pixel`, and whatever falls outside the canvas is
+//! dropped.
+
+use crate::canvas::Canvas;
+use crate::tuple::Color;
+
+/// Size of the font's glyphs, in pixels.
+pub const GLYPH_WIDTH: i32 = 3;
+pub const GLYPH_HEIGHT: i32 = 5;
+/// How far each character moves the next one along, and each line down.
+const ADVANCE: i32 = GLYPH_WIDTH + 1;
+const LINE_HEIGHT: i32 = GLYPH_HEIGHT + 1;
+
+/// A tiny font: each glyph is five rows of three pixels, the highest bit on
+/// the left. Lowercase letters are drawn as uppercase.
+const FONT: [(char, [u8; 5]); 50] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('%', [0b101, 0b001, 0b010, 0b100, 0b101]),
+    ('(', [0b010, 0b100, 0b100, 0b100, 0b010]),
+    (')', [0b010, 0b001, 0b001, 0b001, 0b010]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('?', [0b110, 0b001, 0b010, 0b000, 0b010]),
+    ('\'', [0b010, 0b010, 0b000, 0b000, 0b000]),
+];
+
+impl Canvas {
+    /// A one pixel wide line between two pixels, both included, by
+    /// Bresenham's algorithm.
+    pub fn draw_line(&mut self, (x0, y0): (i32, i32), (x1, y1): (i32, i32), color: Color) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+        // How far off the true line the next pixel would be, doubled
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+        loop {
+            self.set_pixel(x, y, color);
+            if (x, y) == (x1, y1) {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// A smooth line between two points, at pixel centers on whole
+    /// coordinates, by Wu's algorithm: each step along the line shades the
+    /// two pixels it passes between by how close it comes to each, blending
+    /// `color` over what is already there.
+    pub fn draw_line_smooth(&mut self, from: (f64, f64), to: (f64, f64), color: Color) {
+        let ((mut x0, mut y0), (mut x1, mut y1)) = (from, to);
+        // Walk along whichever axis the line covers more of
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            (x0, y0, x1, y1) = (y0, x0, y1, x1);
+        }
+        if x0 > x1 {
+            (x0, y0, x1, y1) = (x1, y1, x0, y0);
+        }
+        let mut plot = |x: f64, y: f64, coverage: f64| {
+            let (x, y) = if steep { (y, x) } else { (x, y) };
+            let (x, y) = (x as i32, y as i32);
+            if let Some(pixel) = self.pixel(x, y) {
+                self.set_pixel(x, y, pixel.lerp(&color, coverage));
+            }
+        };
+        let gradient = if x1 == x0 { 1.0 } else { (y1 - y0) / (x1 - x0) };
+
+        // The ends are shaded by how much of their pixel the line covers
+        let mut end = |x: f64, y: f64, overlap: f64| {
+            let x_end = x.round();
+            let y_end = y + gradient * (x_end - x);
+            let row = y_end.floor();
+            let fraction = y_end - row;
+            plot(x_end, row, (1.0 - fraction) * overlap);
+            plot(x_end, row + 1.0, fraction * overlap);
+            (x_end, y_end)
+        };
+        let (first, y_start) = end(x0, y0, 1.0 - (x0 + 0.5).fract());
+        let (last, _) = end(x1, y1, (x1 + 0.5).fract());
+
+        let mut y = y_start + gradient;
+        for x in (first as i32 + 1)..(last as i32) {
+            let row = y.floor();
+            let fraction = y - row;
+            plot(x as f64, row, 1.0 - fraction);
+            plot(x as f64, row + 1.0, fraction);
+            y += gradient;
+        }
+    }
+
+    /// The outline of the `width` by `height` rectangle whose top left
+    /// pixel is (`x`, `y`).
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.draw_line((x, y), (right, y), color);
+        self.draw_line((x, bottom), (right, bottom), color);
+        self.draw_line((x, y), (x, bottom), color);
+        self.draw_line((right, y), (right, bottom), color);
+    }
+
+    /// Like `draw_rect`, but filled in.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        for row in y.max(0)..(y + height).min(self.height) {
+            for column in x.max(0)..(x + width).min(self.width) {
+                self.set_pixel(column, row, color);
+            }
+        }
+    }
+
+    /// The outline of a circle around the pixel (`x`, `y`), by the midpoint
+    /// algorithm.
+    pub fn draw_circle(&mut self, (x, y): (i32, i32), radius: i32, color: Color) {
+        if radius < 0 {
+            return;
+        }
+        // One eighth of the circle, mirrored into the other seven
+        let (mut dx, mut dy) = (radius, 0);
+        let mut error = 1 - radius;
+        while dx >= dy {
+            for (px, py) in [
+                (dx, dy),
+                (dy, dx),
+                (-dy, dx),
+                (-dx, dy),
+                (-dx, -dy),
+                (-dy, -dx),
+                (dy, -dx),
+                (dx, -dy),
+            ] {
+                self.set_pixel(x + px, y + py, color);
+            }
+            dy += 1;
+            if error < 0 {
+                error += 2 * dy + 1;
+            } else {
+                dx -= 1;
+                error += 2 * (dy - dx) + 1;
+            }
+        }
+    }
+
+    /// Like `draw_circle`, but filled in, covering its outline.
+    pub fn fill_circle(&mut self, (x, y): (i32, i32), radius: i32, color: Color) {
+        if radius < 0 {
+            return;
+        }
+        // Allowing for the outline straying up to half a pixel outside
+        let reach = radius * radius + radius;
+        for dy in -radius..=radius {
+            let half = (0..=radius)
+                .take_while(|dx| dx * dx + dy * dy <= reach)
+                .last();
+            if let Some(half) = half {
+                self.fill_rect(x - half, y + dy, 2 * half + 1, 1, color);
+            }
+        }
+    }
+
+    /// Lines joining the points in turn, and the last back to the first.
+    pub fn draw_polygon(&mut self, points: &[(i32, i32)], color: Color) {
+        for (i, &from) in points.iter().enumerate() {
+            let to = points[(i + 1) % points.len()];
+            self.draw_line(from, to, color);
+        }
+    }
+
+    /// Fills the pixels whose centers are inside the polygon, taking the
+    /// points as pixel corners, so a rectangle's corners fill the same
+    /// pixels as `fill_rect`. Where edges cross, the parts inside an odd
+    /// number of edges are filled.
+    pub fn fill_polygon(&mut self, points: &[(i32, i32)], color: Color) {
+        let Some(top) = points.iter().map(|point| point.1).min() else {
+            return;
+        };
+        let bottom = points.iter().map(|point| point.1).max().unwrap();
+        for row in top.max(0)..bottom.min(self.height) {
+            let center = row as f64 + 0.5;
+            // Where the edges cross this row, paired up left to right
+            let mut crossings: Vec<f64> = (0..points.len())
+                .filter_map(|i| {
+                    let (x0, y0) = points[i];
+                    let (x1, y1) = points[(i + 1) % points.len()];
+                    let (y0, y1) = (y0 as f64, y1 as f64);
+                    if (y0 <= center) == (y1 <= center) {
+                        return None;
+                    }
+                    let t = (center - y0) / (y1 - y0);
+                    Some(x0 as f64 + t * (x1 - x0) as f64)
+                })
+                .collect();
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                // Pixels whose centers fall between the crossings
+                let left = (span[0] - 0.5).ceil() as i32;
+                let right = (span[1] - 0.5).ceil() as i32;
+                self.fill_rect(left, row, right - left, 1, color);
+            }
+        }
+    }
+
+    /// Writes `text` in the tiny built-in font with its top left corner at
+    /// (`x`, `y`). Each character takes 4 pixels across and each line 6
+    /// down. Characters the font lacks show as `?`.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: Color) {
+        for (line_number, line) in text.lines().enumerate() {
+            let top = y + line_number as i32 * LINE_HEIGHT;
+            for (i, character) in line.chars().enumerate() {
+                let left = x + i as i32 * ADVANCE;
+                for (row, bits) in glyph(character).iter().enumerate() {
+                    for column in 0..GLYPH_WIDTH {
+                        if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                            self.set_pixel(left + column, top + row as i32, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The width and height `text` takes up when drawn by `Canvas::draw_text`.
+pub fn text_size(text: &str) -> (i32, i32) {
+    let lines = text.lines().count() as i32;
+    let longest = text.lines().map(|line| line.chars().count()).max();
+    match longest {
+        Some(longest) if longest > 0 => (longest as i32 * ADVANCE - 1, lines * LINE_HEIGHT - 1),
+        _ => (0, 0),
+    }
+}
+
+fn glyph(character: char) -> [u8; 5] {
+    let character = character.to_ascii_uppercase();
+    let find = |wanted: char| FONT.iter().find(|(c, _)| *c == wanted).map(|(_, g)| *g);
+    find(character).or_else(|| find('?')).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INK: Color = Color {
+        red: 1.0,
+        green: 1.0,
+        blue: 1.0,
+    };
+
+    // Rows of the canvas, `#` for inked pixels and `.` for black ones
+    fn picture(canvas: &Canvas) -> Vec<String> {
+        canvas
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .map(|color| if *color == Color::black() { '.' } else { '#' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn inked(canvas: &Canvas) -> usize {
+        canvas
+            .pixels()
+            .filter(|(_, _, color)| *color != Color::black())
+            .count()
+    }
+
+    #[test]
+    fn lines_include_both_ends_in_any_direction() {
+        let mut canvas = Canvas::new(5, 4);
+        canvas.draw_line((4, 3), (0, 1), INK);
+        assert_eq!(picture(&canvas), [".....", "##...", "..##.", "....#"]);
+        let mut steep = Canvas::new(3, 5);
+        steep.draw_line((0, 0), (2, 4), INK);
+        assert_eq!(picture(&steep), ["#..", ".#.", ".#.", "..#", "..#"]);
+        let mut dot = Canvas::new(2, 2);
+        dot.draw_line((1, 1), (1, 1), INK);
+        assert_eq!(picture(&dot), ["..", ".#"]);
+    }
+
+    #[test]
+    fn drawing_off_the_canvas_clips() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.draw_line((-10, -10), (10, 10), INK);
+        canvas.draw_circle((0, 0), 100, INK);
+        canvas.fill_circle((100, 100), 3, INK);
+        canvas.fill_rect(-5, 3, 100, 100, INK);
+        canvas.draw_text(3, -2, "HELLO", INK);
+        canvas.draw_line_smooth((-3.0, 1.5), (9.0, 2.5), INK);
+        assert_eq!(picture(&canvas)[3], "####");
+    }
+
+    #[test]
+    fn smooth_lines_shade_the_pixels_they_pass_between() {
+        let mut canvas = Canvas::new(5, 3);
+        canvas.draw_line_smooth((0.0, 1.0), (4.0, 1.0), INK);
+        assert_eq!(picture(&canvas), [".....", "#####", "....."]);
+
+        // Halfway between two rows, both get half the color
+        let mut between = Canvas::new(5, 3);
+        between.draw_line_smooth((1.0, 0.5), (3.0, 0.5), INK);
+        for y in [0, 1] {
+            assert_eq!(between.pixel(2, y), Some(Color::new(0.5, 0.5, 0.5)));
+        }
+        assert_eq!(between.pixel(2, 2), Some(Color::black()));
+    }
+
+    #[test]
+    fn smooth_lines_blend_over_the_canvas() {
+        let mut canvas = Canvas::new(3, 3);
+        canvas.fill(Color::new(0.0, 0.0, 1.0));
+        canvas.draw_line_smooth((1.0, 0.0), (1.0, 2.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(canvas.pixel(1, 1), Some(Color::new(1.0, 0.0, 0.0)));
+        assert_eq!(canvas.pixel(0, 1), Some(Color::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn outlined_and_filled_rectangles() {
+        let mut outline = Canvas::new(5, 4);
+        outline.draw_rect(1, 0, 4, 3, INK);
+        assert_eq!(picture(&outline), [".####", ".#..#", ".####", "....."]);
+        let mut filled = Canvas::new(5, 4);
+        filled.fill_rect(1, 0, 4, 3, INK);
+        assert_eq!(picture(&filled), [".####", ".####", ".####", "....."]);
+        filled.draw_rect(0, 0, 0, 3, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(filled.pixel(0, 0), Some(Color::black()));
+    }
+
+    #[test]
+    fn circles_are_round_and_fills_cover_their_outline() {
+        let mut outline = Canvas::new(7, 7);
+        outline.draw_circle((3, 3), 3, INK);
+        assert_eq!(
+            picture(&outline),
+            ["..###..", ".#...#.", "#.....#", "#.....#", "#.....#", ".#...#.", "..###..",]
+        );
+        let mut filled = Canvas::new(7, 7);
+        filled.fill_circle((3, 3), 3, INK);
+        for (x, y, color) in outline.pixels() {
+            if color == INK {
+                assert_eq!(filled.pixel(x, y), Some(INK), "({}, {})", x, y);
+            }
+        }
+        assert_eq!(inked(&filled), 37);
+    }
+
+    #[test]
+    fn polygons_outline_and_fill() {
+        let square = [(1, 1), (4, 1), (4, 4), (1, 4)];
+        let mut outline = Canvas::new(6, 6);
+        outline.draw_polygon(&square, INK);
+        assert_eq!(inked(&outline), 12);
+        // Filled, the corners are pixel corners, like `fill_rect` takes
+        let mut filled = Canvas::new(6, 6);
+        filled.fill_polygon(&square, INK);
+        let mut rect = Canvas::new(6, 6);
+        rect.fill_rect(1, 1, 3, 3, INK);
+        assert_eq!(picture(&filled), picture(&rect));
+
+        let mut triangle = Canvas::new(4, 4);
+        triangle.fill_polygon(&[(0, 0), (4, 4), (0, 4)], INK);
+        assert_eq!(picture(&triangle), ["....", "#...", "##..", "###."]);
+    }
+
+    #[test]
+    fn self_crossing_polygons_fill_by_odd_crossings() {
+        // A star drawn in one stroke goes around its middle twice
+        let mut canvas = Canvas::new(21, 21);
+        canvas.fill_polygon(&[(10, 0), (16, 18), (0, 7), (20, 7), (4, 18)], INK);
+        assert_eq!(canvas.pixel(10, 2), Some(INK));
+        assert_eq!(canvas.pixel(10, 10), Some(Color::black()));
+        canvas.fill_polygon(&[], INK);
+        assert_eq!(canvas.pixel(10, 10), Some(Color::black()));
+    }
+
+    #[test]
+    fn text_in_the_tiny_font() {
+        let mut canvas = Canvas::new(8, 11);
+        canvas.draw_text(0, 0, "1a\nt", INK);
+        assert_eq!(
+            picture(&canvas),
+            [
+                ".#...#..", "##..#.#.", ".#..###.", ".#..#.#.", "###.#.#.", "........", "###.....",
+                ".#......", ".#......", ".#......", ".#......",
+            ]
+        );
+        assert_eq!(text_size("1a\nt"), (7, 11));
+        assert_eq!(text_size(""), (0, 0));
+        assert_eq!(glyph('~'), glyph('?'));
+    }
+}
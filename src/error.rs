@@ -0,0 +1,90 @@
+use crate::scene::SceneError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong in the crate's fallible operations.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed.
+    Io { path: PathBuf, source: io::Error },
+    /// A scene file is malformed.
+    Parse(SceneError),
+    /// A transform was given that can't be undone, e.g. a scale by zero.
+    NonInvertibleMatrix,
+    /// A canvas needs a positive width and height.
+    InvalidCanvasSize { width: i32, height: i32 },
+    /// An image file isn't in a format that can be read.
+    InvalidImage(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parse(err) => err.fmt(f),
+            Error::NonInvertibleMatrix => write!(f, "matrix is not invertible"),
+            Error::InvalidCanvasSize { width, height } => {
+                write!(f, "invalid canvas size {}x{}", width, height)
+            }
+            Error::InvalidImage(message) => write!(f, "invalid image: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SceneError> for Error {
+    fn from(err: SceneError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_describe_themselves() {
+        let not_found = io::Error::new(io::ErrorKind::NotFound, "not found");
+        assert_eq!(
+            Error::io("images/out.ppm", not_found).to_string(),
+            "images/out.ppm: not found"
+        );
+        assert_eq!(
+            Error::from(SceneError::new(2, "unknown entry `cube`")).to_string(),
+            "line 2: unknown entry `cube`"
+        );
+        assert_eq!(
+            Error::InvalidCanvasSize {
+                width: 0,
+                height: 5
+            }
+            .to_string(),
+            "invalid canvas size 0x5"
+        );
+        assert_eq!(
+            Error::InvalidImage("unknown format".to_string()).to_string(),
+            "invalid image: unknown format"
+        );
+    }
+}
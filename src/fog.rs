@@ -0,0 +1,101 @@
+//! Haze that thickens with distance, fading what the eye sees toward the
+//! fog's color.
+
+use crate::tuple::Color;
+
+/// Fog filling the whole world.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Fog {
+    pub color: Color,
+    pub falloff: FogFalloff,
+}
+
+/// How quickly fog hides things as they get further away.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FogFalloff {
+    /// Every unit of distance hides the same share of what is left, more
+    /// the larger `density`. Like real haze, nothing is ever fully hidden.
+    Exponential { density: f64 },
+    /// Nothing hidden up to `start`, fading evenly to fully hidden at `end`.
+    Linear { start: f64, end: f64 },
+}
+
+impl Fog {
+    pub fn new(color: Color, falloff: FogFalloff) -> Self {
+        Self { color, falloff }
+    }
+
+    /// How much of what lies `distance` away the fog hides, from 0 to 1.
+    pub fn amount(&self, distance: f64) -> f64 {
+        match self.falloff {
+            FogFalloff::Exponential { density } => 1.0 - (-density * distance).exp(),
+            FogFalloff::Linear { start, end } => {
+                if distance >= end {
+                    1.0
+                } else {
+                    ((distance - start) / (end - start)).clamp(0.0, 1.0)
+                }
+            }
+        }
+    }
+
+    /// `color` seen through `distance` of fog.
+    pub fn apply(&self, color: Color, distance: f64) -> Color {
+        color.lerp(&self.color, self.amount(distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_float_equal;
+
+    #[test]
+    fn exponential_fog_hides_a_share_of_what_is_left_every_unit() {
+        let fog = Fog::new(Color::white(), FogFalloff::Exponential { density: 0.5 });
+        assert_eq!(fog.amount(0.0), 0.0);
+        let one = fog.amount(1.0);
+        assert!(is_float_equal(
+            1.0 - fog.amount(2.0),
+            (1.0 - one) * (1.0 - one)
+        ));
+        assert_eq!(fog.amount(f64::INFINITY), 1.0);
+    }
+
+    #[test]
+    fn linear_fog_fades_between_start_and_end() {
+        let fog = Fog::new(
+            Color::white(),
+            FogFalloff::Linear {
+                start: 2.0,
+                end: 6.0,
+            },
+        );
+        assert_eq!(fog.amount(1.0), 0.0);
+        assert_eq!(fog.amount(3.0), 0.25);
+        assert_eq!(fog.amount(6.0), 1.0);
+        assert_eq!(fog.amount(f64::INFINITY), 1.0);
+        let wall = Fog::new(
+            Color::white(),
+            FogFalloff::Linear {
+                start: 2.0,
+                end: 2.0,
+            },
+        );
+        assert_eq!(wall.amount(1.9), 0.0);
+        assert_eq!(wall.amount(2.0), 1.0);
+    }
+
+    #[test]
+    fn fog_blends_toward_its_color() {
+        let fog = Fog::new(
+            Color::new(0.5, 0.5, 0.5),
+            FogFalloff::Linear {
+                start: 0.0,
+                end: 4.0,
+            },
+        );
+        let seen = fog.apply(Color::new(1.0, 0.0, 0.5), 2.0);
+        assert!(seen.is_equal(&Color::new(0.75, 0.25, 0.5)));
+    }
+}
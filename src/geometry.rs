@@ -0,0 +1,261 @@
+use crate::is_float_equal;
+use crate::tuple::Tuple;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A position in space.
+/// Unlike a `Tuple` with `w` set to 1, only the operations that make sense for
+/// a point are implemented, so adding two points together won't compile.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// A direction and magnitude in space.
+/// Transforming a vector ignores translation, so there is no `w` to clean up.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn origin() -> Self {
+        Self::new(0.0, 0.0, 0.0)
+    }
+
+    pub fn is_equal(&self, other: &Self) -> bool {
+        is_float_equal(self.x, other.x)
+            && is_float_equal(self.y, other.y)
+            && is_float_equal(self.z, other.z)
+    }
+}
+
+impl Vector3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn is_equal(&self, other: &Self) -> bool {
+        is_float_equal(self.x, other.x)
+            && is_float_equal(self.y, other.y)
+            && is_float_equal(self.z, other.z)
+    }
+
+    pub fn magnitude(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        *self / self.magnitude()
+    }
+
+    pub fn dot(&self, other: &Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Order is important, see `Tuple::cross`.
+    pub fn cross(&self, other: &Self) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// Reflect an incoming vector around the normal of the surface.
+    pub fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal * 2.0 * self.dot(normal)
+    }
+}
+
+impl From<Point3> for Tuple {
+    fn from(point: Point3) -> Self {
+        Tuple::point(point.x, point.y, point.z)
+    }
+}
+
+impl From<Vector3> for Tuple {
+    fn from(vector: Vector3) -> Self {
+        Tuple::vector(vector.x, vector.y, vector.z)
+    }
+}
+
+/// Only a tuple with `w` of 1 is a point; anything else is handed back.
+impl TryFrom<Tuple> for Point3 {
+    type Error = Tuple;
+
+    fn try_from(tuple: Tuple) -> Result<Self, Tuple> {
+        if !is_float_equal(tuple.w, 1.0) {
+            return Err(tuple);
+        }
+        Ok(Self::new(tuple.x, tuple.y, tuple.z))
+    }
+}
+
+/// Only a tuple with `w` of 0 is a vector; anything else is handed back.
+impl TryFrom<Tuple> for Vector3 {
+    type Error = Tuple;
+
+    fn try_from(tuple: Tuple) -> Result<Self, Tuple> {
+        if !is_float_equal(tuple.w, 0.0) {
+            return Err(tuple);
+        }
+        Ok(Self::new(tuple.x, tuple.y, tuple.z))
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Vector3;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Add<Vector3> for Point3 {
+    type Output = Self;
+
+    fn add(self, vector: Vector3) -> Self::Output {
+        Self::new(self.x + vector.x, self.y + vector.y, self.z + vector.z)
+    }
+}
+
+impl Sub<Vector3> for Point3 {
+    type Output = Self;
+
+    fn sub(self, vector: Vector3) -> Self::Output {
+        Self::new(self.x - vector.x, self.y - vector.y, self.z - vector.z)
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Add<Point3> for Vector3 {
+    type Output = Point3;
+
+    fn add(self, point: Point3) -> Self::Output {
+        point + self
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Self::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<f64> for Vector3 {
+    type Output = Self;
+
+    fn mul(self, scalar: f64) -> Self::Output {
+        Self::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+impl Div<f64> for Vector3 {
+    type Output = Self;
+
+    fn div(self, scalar: f64) -> Self::Output {
+        Self::new(self.x / scalar, self.y / scalar, self.z / scalar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converting_a_tuple_checks_w() {
+        let (point, vector) = (Tuple::point(1.0, 2.0, 3.0), Tuple::vector(1.0, 2.0, 3.0));
+        assert_eq!(Point3::try_from(point), Ok(Point3::new(1.0, 2.0, 3.0)));
+        assert_eq!(Vector3::try_from(vector), Ok(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(Point3::try_from(vector), Err(vector));
+        assert_eq!(Vector3::try_from(point), Err(point));
+        let mut tuple = vector;
+        tuple.w = 0.7;
+        assert!(Point3::try_from(tuple).is_err() && Vector3::try_from(tuple).is_err());
+    }
+
+    #[test]
+    fn converting_to_a_tuple_sets_w() {
+        assert_eq!(
+            Tuple::from(Point3::new(1.0, 2.0, 3.0)),
+            Tuple::point(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            Tuple::from(Vector3::new(1.0, 2.0, 3.0)),
+            Tuple::vector(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn reflecting_a_vector_off_slanted_surface() {
+        let slant = 2.0_f64.sqrt() / 2.0;
+        let vector = Vector3::new(0.0, -1.0, 0.0);
+        let normal = Vector3::new(slant, slant, 0.0);
+        assert!(vector
+            .reflect(&normal)
+            .is_equal(&Vector3::new(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn cross_product_of_two_vectors() {
+        let v1 = Vector3::new(1.0, 2.0, 3.0);
+        let v2 = Vector3::new(2.0, 3.0, 4.0);
+        assert_eq!(v1.cross(&v2), Vector3::new(-1.0, 2.0, -1.0));
+        assert_eq!(v2.cross(&v1), Vector3::new(1.0, -2.0, 1.0));
+    }
+
+    #[test]
+    fn normalizing_a_vector() {
+        let sqrt = 14.0_f64.sqrt();
+        let normal = Vector3::new(1.0, 2.0, 3.0).normalize();
+        assert!(normal.is_equal(&Vector3::new(1.0 / sqrt, 2.0 / sqrt, 3.0 / sqrt)));
+        assert!(is_float_equal(normal.magnitude(), 1.0));
+    }
+
+    #[test]
+    fn adding_a_vector_to_a_point_creates_a_point() {
+        let point = Point3::new(-2.0, 3.0, 1.0);
+        let vector = Vector3::new(3.0, -2.0, 5.0);
+        assert_eq!(point + vector, Point3::new(1.0, 1.0, 6.0));
+        assert_eq!(vector + point, Point3::new(1.0, 1.0, 6.0));
+    }
+
+    #[test]
+    fn subtracting_a_vector_from_a_point_creates_a_point() {
+        let point = Point3::new(3.0, 2.0, 1.0);
+        let vector = Vector3::new(5.0, 6.0, 7.0);
+        assert_eq!(point - vector, Point3::new(-2.0, -4.0, -6.0));
+    }
+
+    #[test]
+    fn subtracting_two_points_creates_a_vector() {
+        let p1 = Point3::new(3.0, 2.0, 1.0);
+        let p2 = Point3::new(5.0, 6.0, 7.0);
+        assert_eq!(p1 - p2, Vector3::new(-2.0, -4.0, -6.0));
+    }
+}
@@ -0,0 +1,387 @@
+//! Ways of working out the color seen along a camera ray.
+
+use crate::lights::{Light, ShapeLight};
+use crate::rays::{hit, oren_nayar, Computations, Material, Ray, ShadingModel, ISOTROPIC_SCATTER};
+use crate::sampling::{cosine_hemisphere, uniform_sphere};
+use crate::tuple::{Color, Tuple};
+use crate::world::World;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// Bounces every path gets before Russian roulette may end it.
+const ROULETTE_DEPTH: usize = 3;
+
+/// How a render turns rays into colors. Either works with any scene.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Integrator {
+    /// Classic recursive ray tracing: Phong shading where the ray hits, plus
+    /// mirror reflections. Fast and noise free, but without indirect light.
+    Whitted,
+    /// Monte Carlo path tracing: light bounces off matte surfaces in random
+    /// directions and glowing materials light up their surroundings. Needs
+    /// many samples per pixel to lose its noise.
+    PathTracing,
+}
+
+impl Integrator {
+    /// Color seen along the ray, following at most `max_depth` bounces.
+    pub fn color(&self, world: &World, ray: &Ray, max_depth: usize, rng: &mut StdRng) -> Color {
+        match self {
+            Integrator::Whitted => world.color_at_depth(ray, max_depth),
+            Integrator::PathTracing => path_trace(world, ray, max_depth, rng),
+        }
+    }
+}
+
+// Follows one path, adding up what reaches the camera at every bounce.
+// `throughput` is how much of the light found at the current hit makes it
+// back along the path.
+fn path_trace(world: &World, ray: &Ray, max_depth: usize, rng: &mut StdRng) -> Color {
+    let mut radiance = Color::black();
+    let mut throughput = Color::white();
+    let mut ray = Ray::new(ray.origin, ray.direction).at_shutter_time(ray.shutter_time);
+    // Glowing shapes already counted as direct light at the last bounce
+    // mustn't be counted again when a path happens to hit them. The same
+    // goes for the background, unless it lights the world.
+    let mut count_emitters = true;
+    for bounce in 0..=max_depth {
+        let intersections = world.intersect(&ray);
+        let comps = match hit(&intersections) {
+            Some(intersection) => intersection.prepare_computations(&ray),
+            None => {
+                if count_emitters || world.image_based_lighting {
+                    radiance += throughput * world.background.color(ray.direction);
+                }
+                break;
+            }
+        };
+        // Fog hides part of what lies beyond it, showing its own color
+        if let Some(fog) = &world.fog {
+            let amount = fog.amount(comps.time * ray.direction.magnitude());
+            radiance += throughput * fog.color * amount;
+            throughput *= 1.0 - amount;
+        }
+        let material = comps.object.material();
+        if material.shading == ShadingModel::Unlit {
+            radiance += throughput * material.color;
+            break;
+        }
+        if count_emitters || !ShapeLight::can_light(comps.object) {
+            radiance += throughput * material.emission;
+        }
+        // Glossy reflections of small glowing shapes are too narrow for
+        // random points on the shapes to find, so they are left to the
+        // bounces below instead
+        let sample_emitters = !matches!(material.shading, ShadingModel::Microfacet(_));
+        radiance += throughput * direct_light(world, &comps, sample_emitters);
+        count_emitters = !sample_emitters;
+        if bounce == max_depth {
+            break;
+        }
+
+        let direction = match &material.shading {
+            ShadingModel::Microfacet(microfacet) => {
+                let numbers = (rng.gen(), rng.gen(), rng.gen());
+                match microfacet.sample(material.color, comps.normal, comps.eye, numbers) {
+                    Some((direction, weight)) => {
+                        throughput *= weight;
+                        direction
+                    }
+                    None => break,
+                }
+            }
+            // The phase function over its pdf is 1, leaving just the albedo
+            ShadingModel::Isotropic => {
+                throughput *= material.color * material.diffuse;
+                uniform_sphere((rng.gen(), rng.gen()))
+            }
+            shading => {
+                // Mirror some paths and scatter the rest, in proportion to
+                // how reflective the surface is
+                let reflective = material.reflective.clamp(0.0, 1.0);
+                if rng.gen::<f64>() < reflective {
+                    // Mirrors can't sample lights, only see them
+                    count_emitters = true;
+                    comps.reflect
+                } else {
+                    // Cosine-weighted sampling cancels the cosine and the
+                    // 1/pi of the Lambertian BRDF, leaving just the albedo
+                    let direction = cosine_hemisphere(comps.normal, (rng.gen(), rng.gen()));
+                    let mut albedo = material.color * material.diffuse;
+                    if let ShadingModel::OrenNayar { roughness } = *shading {
+                        albedo *= oren_nayar(roughness, comps.normal, comps.eye, direction);
+                    }
+                    throughput = throughput * albedo / (1.0 - reflective);
+                    direction
+                }
+            }
+        };
+
+        // Russian roulette: end dim paths early, and make up for it by
+        // boosting the ones that survive
+        if bounce + 1 >= ROULETTE_DEPTH {
+            let survival = throughput.iter().fold(0.0, f64::max).min(0.95);
+            if rng.gen::<f64>() >= survival {
+                break;
+            }
+            throughput /= survival;
+        }
+        ray = Ray::new(comps.over_point, direction).at_shutter_time(comps.shutter_time);
+    }
+    radiance
+}
+
+// Light arriving straight from the world's lights, and its glowing shapes if
+// `sample_emitters`, reflected toward the eye.
+fn direct_light(world: &World, comps: &Computations, sample_emitters: bool) -> Color {
+    let material = comps.object.material();
+    let point = comps.over_point;
+    let emitters = if sample_emitters {
+        world.emitters(comps.shutter_time)
+    } else {
+        vec![]
+    };
+    world
+        .lights
+        .iter()
+        .map(AsRef::as_ref)
+        .chain(emitters.iter().map(|emitter| emitter as &dyn Light))
+        .map(|light| {
+            let samples = light.samples(point);
+            let total: Color = samples
+                .iter()
+                .filter(|sample| !world.is_shadowed(point, sample, comps.shutter_time))
+                .map(|sample| {
+                    let intensity = sample.intensity * light.attenuation(sample.distance);
+                    intensity * reflectance(material, comps, sample.direction)
+                })
+                .sum();
+            total / samples.len() as f64
+        })
+        .sum()
+}
+
+// How much of the light arriving from `direction` the surface sends toward
+// the eye, cosine included. Scaled like the diffuse term of Phong shading,
+// so a path traced scene lit by lights is as bright as its Phong render.
+fn reflectance(material: &Material, comps: &Computations, direction: Tuple) -> Color {
+    let lambert = || material.color * material.diffuse * direction.dot(&comps.normal).max(0.0);
+    match &material.shading {
+        ShadingModel::Microfacet(microfacet) => {
+            microfacet.evaluate(material.color, comps.normal, comps.eye, direction) * PI
+        }
+        ShadingModel::OrenNayar { roughness } => {
+            lambert() * oren_nayar(*roughness, comps.normal, comps.eye, direction)
+        }
+        ShadingModel::Isotropic => material.color * material.diffuse * ISOTROPIC_SCATTER,
+        // Path tracing has no use for highlights around point lights, and
+        // unlit surfaces never get here
+        _ => lambert(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::Background;
+    use crate::fog::{Fog, FogFalloff};
+    use crate::lights::PointLight;
+    use crate::matrix::Matrix;
+    use crate::sampling::pixel_rng;
+    use crate::shapes::{Plane, Sphere, Triangle, Volume};
+
+    #[test]
+    fn whitted_integration_is_plain_ray_tracing() {
+        let world = World::default_world();
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let mut rng = pixel_rng(0, 0, 0);
+        assert_eq!(
+            Integrator::Whitted.color(&world, &ray, 5, &mut rng),
+            world.color_at(&ray)
+        );
+    }
+
+    #[test]
+    fn path_tracing_lights_matte_surfaces_like_phong_diffuse() {
+        let mut world = World::new();
+        let mut floor = Plane::new();
+        floor.material = Material::new().color(Color::new(1.0, 0.5, 0.25));
+        world.objects.push(Box::new(floor));
+        world.lights.push(Box::new(PointLight::new(
+            Tuple::point(0.0, 10.0, 0.0),
+            Color::white(),
+        )));
+        let ray = Ray::new(
+            Tuple::point(0.0, 1.0, -1.0),
+            Tuple::vector(0.0, -1.0, 1.0).normalize(),
+        );
+        let mut rng = pixel_rng(0, 0, 0);
+        // Bounced light escapes into the empty sky, leaving only direct light
+        let color = Integrator::PathTracing.color(&world, &ray, 5, &mut rng);
+        // The light is straight above the hit, so the cosine is 1
+        assert!(color.is_equal(&(Color::new(1.0, 0.5, 0.25) * 0.9)));
+    }
+
+    #[test]
+    fn glowing_triangles_light_the_floor_in_either_integrator() {
+        let mut world = World::new();
+        let mut floor = Plane::new();
+        floor.material = Material::new().ambient(0.0);
+        world.objects.push(Box::new(floor));
+        // A small lamp of area 0.005, 2 above where the ray hits the floor
+        let mut lamp = Triangle::new(
+            Tuple::point(-0.05, 2.0, -0.05),
+            Tuple::point(0.05, 2.0, -0.05),
+            Tuple::point(-0.05, 2.0, 0.05),
+        );
+        lamp.material = Material::new().emission(Color::new(100.0, 100.0, 100.0));
+        world.objects.push(Box::new(lamp));
+        let ray = Ray::new(
+            Tuple::point(0.0, 1.0, -1.0),
+            Tuple::vector(0.0, -1.0, 1.0).normalize(),
+        );
+        // Like a point light of intensity 100 * 0.005 / (pi * 2 * 2)
+        let expected = 0.9 * 0.5 / (4.0 * PI);
+        for integrator in [Integrator::Whitted, Integrator::PathTracing] {
+            let mut rng = pixel_rng(0, 0, 0);
+            let color = integrator.color(&world, &ray, 0, &mut rng);
+            assert!((color.red / expected - 1.0).abs() < 0.01, "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn glowing_surfaces_are_seen_directly() {
+        let mut world = World::new();
+        let mut lamp = Sphere::new();
+        lamp.material = Material::new()
+            .color(Color::black())
+            .emission(Color::new(2.0, 1.0, 0.5));
+        world.objects.push(Box::new(lamp));
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let mut rng = pixel_rng(0, 0, 0);
+        let color = Integrator::PathTracing.color(&world, &ray, 5, &mut rng);
+        assert_eq!(color, Color::new(2.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn path_tracing_gathers_light_bounced_inside_a_glowing_sphere() {
+        // A furnace: every bounce sees emission 0.25 and keeps half the light,
+        // so the radiance is 0.25 * (1 + 1/2 + 1/4 + ...) = 0.5
+        let mut world = World::new();
+        let mut furnace = Sphere::with_transform(Matrix::identity().scale(10.0, 10.0, 10.0));
+        furnace.material = Material::new()
+            .color(Color::white())
+            .diffuse(0.5)
+            .emission(Color::new(0.25, 0.25, 0.25));
+        world.objects.push(Box::new(furnace));
+        let mut rng = pixel_rng(1, 2, 3);
+        let paths = 4000;
+        let total: Color = (0..paths)
+            .map(|_| {
+                let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+                Integrator::PathTracing.color(&world, &ray, 30, &mut rng)
+            })
+            .sum();
+        let average = total / paths as f64;
+        assert!((average.red - 0.5).abs() < 0.02, "{:?}", average);
+    }
+
+    #[test]
+    fn the_background_lights_the_world_only_when_asked_to() {
+        // Seen from above, a matte floor under a uniform sky reflects its
+        // albedo times the sky, whatever the direction
+        let mut world = World::new();
+        let mut floor = Plane::new();
+        floor.material = Material::new()
+            .color(Color::new(1.0, 0.5, 0.5))
+            .diffuse(0.8);
+        world.objects.push(Box::new(floor));
+        world.background = Background::Color(Color::new(0.5, 0.5, 0.5));
+        let ray = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let mut rng = pixel_rng(0, 0, 0);
+        let unlit = Integrator::PathTracing.color(&world, &ray, 5, &mut rng);
+        assert_eq!(unlit, Color::black());
+
+        world.image_based_lighting = true;
+        let lit = Integrator::PathTracing.color(&world, &ray, 5, &mut rng);
+        assert!(lit.is_equal(&Color::new(0.4, 0.2, 0.2)), "{:?}", lit);
+        let sky = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+        let seen = Integrator::PathTracing.color(&world, &sky, 5, &mut rng);
+        assert_eq!(seen, Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn fog_fades_hits_alike_in_either_integrator() {
+        let mut world = World::new();
+        let mut wall = Plane::with_transform(
+            Matrix::identity()
+                .rotate_x(PI / 2.0)
+                .translate(0.0, 0.0, 4.0),
+        );
+        wall.material = Material::new()
+            .color(Color::new(0.0, 0.0, 1.0))
+            .shading_model(ShadingModel::Unlit);
+        world.objects.push(Box::new(wall));
+        world.fog = Some(Fog::new(
+            Color::new(1.0, 1.0, 0.0),
+            FogFalloff::Exponential { density: 0.25 },
+        ));
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        // Four units of fog hide 1 - 1/e of the wall
+        let hidden = 1.0 - (-1.0_f64).exp();
+        let expected = Color::new(hidden, hidden, 1.0 - hidden);
+        for integrator in [Integrator::Whitted, Integrator::PathTracing] {
+            let mut rng = pixel_rng(0, 0, 0);
+            let color = integrator.color(&world, &ray, 5, &mut rng);
+            assert!(color.is_equal(&expected), "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn white_smoke_under_a_white_sky_is_white() {
+        // Light is scattered but never absorbed, so every path that gets out
+        // brings back the sky
+        let mut world = World::new();
+        let mut smoke = Volume::new(Box::new(Sphere::new()), 2.0);
+        smoke.material = smoke.material.clone().diffuse(1.0);
+        world.objects.push(Box::new(smoke));
+        world.background = Background::Color(Color::white());
+        world.image_based_lighting = true;
+        let mut rng = pixel_rng(0, 0, 0);
+        let paths = 500;
+        let total: Color = (0..paths)
+            .map(|i| {
+                let ray = Ray::new(
+                    Tuple::point(0.0, i as f64 * 0.001, -5.0),
+                    Tuple::vector(0.0, 0.0, 1.0),
+                );
+                Integrator::PathTracing.color(&world, &ray, 100, &mut rng)
+            })
+            .sum();
+        let average = total / paths as f64;
+        assert!((average.red - 1.0).abs() < 0.05, "{:?}", average);
+    }
+
+    #[test]
+    fn path_tracing_a_furnace_of_white_metal() {
+        let mut world = World::new();
+        let mut furnace = Sphere::with_transform(Matrix::identity().scale(10.0, 10.0, 10.0));
+        furnace.material = Material::new()
+            .emission(Color::new(0.25, 0.25, 0.25))
+            .metallic_roughness(1.0, 0.1);
+        world.objects.push(Box::new(furnace));
+        let mut rng = pixel_rng(1, 2, 3);
+        let paths = 2000;
+        let total: Color = (0..paths)
+            .map(|_| {
+                let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+                Integrator::PathTracing.color(&world, &ray, 10, &mut rng)
+            })
+            .sum();
+        // Smooth white metal hit head on keeps nearly all the light, so
+        // every one of the 11 hits adds about 0.25
+        let average = total / paths as f64;
+        assert!((average.red - 2.75).abs() < 0.05, "{:?}", average);
+    }
+}
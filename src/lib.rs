@@ -1,9 +1,28 @@
+pub mod background;
+pub mod bump;
+pub mod camera;
 pub mod canvas;
+pub mod draw;
+mod error;
+pub mod fog;
+pub mod geometry;
+pub mod integrator;
+pub mod lights;
 pub mod matrix;
+pub mod microfacet;
+pub mod noise;
 pub mod rays;
+pub mod sampling;
+pub mod scene;
+pub mod shapes;
+pub mod simd;
 pub mod tuple;
+pub mod world;
 
 use crate::canvas::Canvas;
+use std::path::Path;
+
+pub use crate::error::{Error, Result};
 
 const EPSILON: f64 = 0.00001;
 
@@ -11,11 +30,84 @@ pub fn is_float_equal(a: f64, b: f64) -> bool {
     (a - b).abs() < EPSILON
 }
 
-pub fn save_image(canvas: Canvas, filename: &str) {
-    use std::fs::File;
-    use std::io::prelude::*;
+/// How `save_image_as` writes an image.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImageFormat {
+    /// Plain-text (P3) PPM.
+    Ppm,
+    /// Binary (P6) PPM, a fraction of the size.
+    PpmBinary,
+}
+
+/// Writes the canvas to `path` as a plain-text PPM image.
+pub fn save_image(canvas: &Canvas, path: impl AsRef<Path>) -> Result<()> {
+    save_image_as(canvas, path, ImageFormat::Ppm)
+}
+
+/// Writes the canvas to `path` in the given format.
+pub fn save_image_as(canvas: &Canvas, path: impl AsRef<Path>, format: ImageFormat) -> Result<()> {
+    let path = path.as_ref();
+    let bytes = match format {
+        ImageFormat::Ppm => canvas.to_ppm().into_bytes(),
+        ImageFormat::PpmBinary => canvas.to_ppm_binary(),
+    };
+    std::fs::write(path, bytes).map_err(|err| Error::io(path, err))
+}
+
+/// Reads a PPM image, plain-text (P3) or binary (P6), or a Radiance HDR
+/// image into a canvas.
+pub fn load_image(path: impl AsRef<Path>) -> Result<Canvas> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|err| Error::io(path, err))?;
+    if bytes.starts_with(b"#?") {
+        Canvas::from_hdr(&bytes)
+    } else {
+        Canvas::from_ppm(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_an_image_to_a_path() {
+        let path = std::env::temp_dir().join("ray_tracer_save_image.ppm");
+        let canvas = Canvas::new(2, 1);
+        save_image(&canvas, &path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved, canvas.to_ppm());
+    }
+
+    #[test]
+    fn saving_an_image_in_binary() {
+        let path = std::env::temp_dir().join("ray_tracer_save_image_as.ppm");
+        let canvas = Canvas::new(2, 1);
+        save_image_as(&canvas, &path, ImageFormat::PpmBinary).unwrap();
+        let saved = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved, canvas.to_ppm_binary());
+    }
+
+    #[test]
+    fn loading_a_saved_image() {
+        let path = std::env::temp_dir().join("ray_tracer_load_image.ppm");
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(
+            &crate::tuple::Tuple::point(1.0, 0.0, 0.0),
+            crate::tuple::Color::white(),
+        );
+        save_image(&canvas, &path).unwrap();
+        let loaded = load_image(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.to_ppm(), canvas.to_ppm());
+    }
 
-    println!("Saving image...");
-    let mut file = File::create(format!("images/{}", filename)).unwrap();
-    file.write_all(canvas.to_ppm().as_bytes()).unwrap();
+    #[test]
+    fn saving_into_a_missing_directory_is_an_error() {
+        let path = std::env::temp_dir().join("ray_tracer_missing/image.ppm");
+        let result = save_image(&Canvas::new(2, 1), &path);
+        assert!(matches!(result, Err(Error::Io { path: failed, .. }) if failed == path));
+    }
 }
@@ -0,0 +1,636 @@
+use crate::geometry::Point3;
+use crate::shapes::Object;
+use crate::tuple::{Color, Tuple};
+use crate::EPSILON;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+/// Light arriving at a point from one spot on a light.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LightSample {
+    /// Unit vector from the point toward the light.
+    pub direction: Tuple,
+    /// How far shadow rays must travel to reach the light, infinite for
+    /// lights at infinity.
+    pub distance: f64,
+    /// Color and brightness of the light once it reaches the point.
+    pub intensity: Color,
+}
+
+/// Anything that lights up a world.
+pub trait Light: Debug + Send + Sync {
+    /// The light's nominal color and brightness.
+    fn intensity(&self) -> Color;
+
+    /// Light added everywhere, shadowed or not, before a material's
+    /// `ambient` scales it.
+    fn ambient(&self) -> Color {
+        self.intensity()
+    }
+
+    /// Unit vector from `point` toward the light.
+    fn direction_from(&self, point: Tuple) -> Tuple;
+
+    /// How far the light is from `point`, for shadow tests.
+    fn distance_from(&self, point: Tuple) -> f64;
+
+    /// Color and brightness of the light once it reaches `point`.
+    fn intensity_at(&self, point: Tuple) -> Color;
+
+    /// The share of a sample's intensity left after traveling `distance`.
+    fn attenuation(&self, _distance: f64) -> f64 {
+        1.0
+    }
+
+    /// The spots on the light that shading and shadow rays from `point` aim
+    /// for, each standing for an equal share of the light.
+    fn samples(&self, point: Tuple) -> Vec<LightSample> {
+        vec![LightSample {
+            direction: self.direction_from(point),
+            distance: self.distance_from(point),
+            intensity: self.intensity_at(point),
+        }]
+    }
+}
+
+/// How a light weakens as it travels.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Attenuation {
+    /// Divides the intensity by `constant + linear * d + quadratic * d * d`
+    /// at distance `d`. The default, a constant of 1, never weakens.
+    Polynomial {
+        constant: f64,
+        linear: f64,
+        quadratic: f64,
+    },
+    /// Physically based falloff, dividing the intensity by `d * d`.
+    InverseSquare,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation::Polynomial {
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
+        }
+    }
+}
+
+impl Attenuation {
+    /// The share of the intensity left at `distance`.
+    pub fn factor(&self, distance: f64) -> f64 {
+        let denominator = match *self {
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => constant + linear * distance + quadratic * distance * distance,
+            Attenuation::InverseSquare => distance * distance,
+        };
+        // Right next to the light the falloff blows up, so cap it
+        1.0 / denominator.max(EPSILON)
+    }
+}
+
+/// A light with no size: everything is either fully lit or fully shadowed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PointLight {
+    pub position: Tuple,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+impl PointLight {
+    /// The light doesn't weaken with distance until `attenuation` is set.
+    pub fn new(position: Tuple, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            attenuation: Attenuation::default(),
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: Tuple) -> Tuple {
+        (self.position - point).normalize()
+    }
+
+    fn distance_from(&self, point: Tuple) -> f64 {
+        (self.position - point).magnitude()
+    }
+
+    fn intensity_at(&self, _point: Tuple) -> Color {
+        self.intensity
+    }
+
+    fn attenuation(&self, distance: f64) -> f64 {
+        self.attenuation.factor(distance)
+    }
+}
+
+/// A light so far away that its rays arrive parallel and never weaken, like
+/// the sun.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DirectionalLight {
+    /// The way the light travels, from the light toward the scene.
+    pub direction: Tuple,
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Tuple, intensity: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, _point: Tuple) -> Tuple {
+        -self.direction
+    }
+
+    fn distance_from(&self, _point: Tuple) -> f64 {
+        f64::INFINITY
+    }
+
+    fn intensity_at(&self, _point: Tuple) -> Color {
+        self.intensity
+    }
+}
+
+/// A point light that only shines in a cone. Points within `inner_angle` of
+/// the cone's axis get the full intensity, which fades smoothly to nothing
+/// at `outer_angle`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpotLight {
+    pub position: Tuple,
+    /// Where the cone points.
+    pub direction: Tuple,
+    /// Angles from the axis, in radians.
+    pub inner_angle: f64,
+    pub outer_angle: f64,
+    pub intensity: Color,
+}
+
+impl SpotLight {
+    /// `inner_angle` shouldn't be wider than `outer_angle`.
+    pub fn new(
+        position: Tuple,
+        direction: Tuple,
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: Color,
+    ) -> Self {
+        Self {
+            position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+            intensity,
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: Tuple) -> Tuple {
+        (self.position - point).normalize()
+    }
+
+    fn distance_from(&self, point: Tuple) -> f64 {
+        (self.position - point).magnitude()
+    }
+
+    fn intensity_at(&self, point: Tuple) -> Color {
+        // No angle to the axis from the light itself
+        let to_point = point - self.position;
+        if to_point.magnitude() < EPSILON {
+            return self.intensity;
+        }
+        let cos_angle = to_point.normalize().dot(&self.direction);
+        let (cos_outer, cos_inner) = (self.outer_angle.cos(), self.inner_angle.cos());
+        if cos_angle >= cos_inner {
+            return self.intensity;
+        }
+        if cos_angle <= cos_outer {
+            return Color::black();
+        }
+        // Smoothstep between the edges of the cones
+        let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+        self.intensity * (t * t * (3.0 - 2.0 * t))
+    }
+}
+
+/// A flat rectangle of light, split into `usteps` by `vsteps` cells with one
+/// sample per cell. Points that see only some of the cells end up in
+/// penumbra, giving soft shadows.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AreaLight {
+    pub corner: Tuple,
+    /// One cell's edge along the first side.
+    pub uvec: Tuple,
+    pub usteps: usize,
+    /// One cell's edge along the second side.
+    pub vvec: Tuple,
+    pub vsteps: usize,
+    pub intensity: Color,
+    /// Sample a random spot in each cell instead of its center, trading
+    /// banding for noise.
+    pub jitter: bool,
+}
+
+impl AreaLight {
+    /// `full_uvec` and `full_vvec` are the rectangle's two sides, starting at
+    /// `corner`. Jitter is on.
+    pub fn new(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        let (usteps, vsteps) = (usteps.max(1), vsteps.max(1));
+        Self {
+            corner,
+            uvec: full_uvec / usteps as f64,
+            usteps,
+            vvec: full_vvec / vsteps as f64,
+            vsteps,
+            intensity,
+            jitter: true,
+        }
+    }
+
+    /// How many cells, and so samples, the light has.
+    pub fn cell_count(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    /// The middle of the rectangle.
+    pub fn position(&self) -> Tuple {
+        self.corner
+            + self.uvec * (self.usteps as f64 / 2.0)
+            + self.vvec * (self.vsteps as f64 / 2.0)
+    }
+
+    /// A point in the cell at (`u`, `v`), `offset` being where in the cell
+    /// from (0, 0) to (1, 1).
+    pub fn point_on_light(&self, u: usize, v: usize, offset: (f64, f64)) -> Tuple {
+        self.corner + self.uvec * (u as f64 + offset.0) + self.vvec * (v as f64 + offset.1)
+    }
+
+    /// Points on the light for shading `point`, one per cell.
+    pub fn sample_points(&self, point: Tuple) -> Vec<Tuple> {
+        let mut rng = point_rng(point);
+
+        let mut points = Vec::with_capacity(self.cell_count());
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                let offset = if self.jitter {
+                    (rng.gen(), rng.gen())
+                } else {
+                    (0.5, 0.5)
+                };
+                points.push(self.point_on_light(u, v, offset));
+            }
+        }
+        points
+    }
+}
+
+impl Light for AreaLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn direction_from(&self, point: Tuple) -> Tuple {
+        (self.position() - point).normalize()
+    }
+
+    fn distance_from(&self, point: Tuple) -> f64 {
+        (self.position() - point).magnitude()
+    }
+
+    fn intensity_at(&self, _point: Tuple) -> Color {
+        self.intensity
+    }
+
+    fn samples(&self, point: Tuple) -> Vec<LightSample> {
+        self.sample_points(point)
+            .into_iter()
+            .map(|sample| {
+                let to_light = sample - point;
+                LightSample {
+                    direction: to_light.normalize(),
+                    distance: to_light.magnitude(),
+                    intensity: self.intensity,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A glowing shape lighting the world, sampled at random points on its
+/// surface. Two-sided, like emission seen directly.
+#[derive(Debug)]
+pub struct ShapeLight<'a> {
+    pub shape: &'a dyn Object,
+    /// How many points on the shape each shaded point aims for.
+    pub samples: usize,
+    /// Where a moving shape is while lighting.
+    pub shutter_time: f64,
+}
+
+impl<'a> ShapeLight<'a> {
+    /// `None` unless the shape glows and can be sampled.
+    pub fn new(shape: &'a dyn Object, samples: usize, shutter_time: f64) -> Option<Self> {
+        Self::can_light(shape).then_some(Self {
+            shape,
+            samples: samples.max(1),
+            shutter_time,
+        })
+    }
+
+    pub fn can_light(shape: &dyn Object) -> bool {
+        shape.material().emission != Color::black()
+            && shape.local_sample_surface((0.5, 0.5)).is_some()
+    }
+
+    fn center(&self) -> Tuple {
+        // Needs no inverse, so it is there even if scaled to nothing
+        let transform = self.shape.transform();
+        let center = transform.matrix() * Point3::origin();
+        match transform.motion() {
+            Some(motion) => (&motion.at(self.shutter_time) * center).into(),
+            None => center.into(),
+        }
+    }
+}
+
+impl Light for ShapeLight<'_> {
+    fn intensity(&self) -> Color {
+        self.shape.material().emission
+    }
+
+    // Glowing shapes can be anywhere in the scene, so their light only
+    // reaches what they cast it on, falling off with distance
+    fn ambient(&self) -> Color {
+        Color::black()
+    }
+
+    fn direction_from(&self, point: Tuple) -> Tuple {
+        (self.center() - point).normalize()
+    }
+
+    fn distance_from(&self, point: Tuple) -> f64 {
+        (self.center() - point).magnitude()
+    }
+
+    fn intensity_at(&self, _point: Tuple) -> Color {
+        self.intensity()
+    }
+
+    fn samples(&self, point: Tuple) -> Vec<LightSample> {
+        let mut rng = point_rng(point);
+        let emission = self.intensity();
+        (0..self.samples)
+            .filter_map(|_| {
+                let surface = self
+                    .shape
+                    .sample_surface((rng.gen(), rng.gen()), self.shutter_time)?;
+                let to_light = surface.point - point;
+                let distance = to_light.magnitude();
+                let direction = to_light / distance;
+                // Light from a patch of the surface falls off with distance
+                // and with how obliquely the patch faces the point. Over pi
+                // since light intensities are scaled like Phong's diffuse term.
+                let cosine = surface.normal.dot(&direction).abs();
+                let scale = cosine / (surface.pdf * distance * distance * PI);
+                Some(LightSample {
+                    direction,
+                    // Stop shadow rays short of the shape itself
+                    distance: distance - EPSILON,
+                    intensity: emission * scale,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A random number generator for the points on lights aimed for from
+/// `point`. Seeding from the point keeps renders reproducible while still
+/// varying the samples from point to point.
+fn point_rng(point: Tuple) -> StdRng {
+    let seed = [point.x, point.y, point.z]
+        .iter()
+        .fold(0u64, |seed, value| seed.rotate_left(21) ^ value.to_bits());
+    StdRng::seed_from_u64(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area_light() -> AreaLight {
+        AreaLight::new(
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::vector(2.0, 0.0, 0.0),
+            4,
+            Tuple::vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        )
+    }
+
+    #[test]
+    fn creating_an_area_light() {
+        let light = area_light();
+        assert_eq!(light.corner, Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(light.uvec, Tuple::vector(0.5, 0.0, 0.0));
+        assert_eq!(light.usteps, 4);
+        assert_eq!(light.vvec, Tuple::vector(0.0, 0.0, 0.5));
+        assert_eq!(light.vsteps, 2);
+        assert_eq!(light.cell_count(), 8);
+        assert_eq!(light.position(), Tuple::point(1.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn finding_points_on_an_area_light() {
+        let light = area_light();
+        let cases = [
+            (0, 0, Tuple::point(0.25, 0.0, 0.25)),
+            (1, 0, Tuple::point(0.75, 0.0, 0.25)),
+            (0, 1, Tuple::point(0.25, 0.0, 0.75)),
+            (2, 0, Tuple::point(1.25, 0.0, 0.25)),
+            (3, 1, Tuple::point(1.75, 0.0, 0.75)),
+        ];
+        for (u, v, expected) in cases {
+            assert_eq!(light.point_on_light(u, v, (0.5, 0.5)), expected);
+        }
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells() {
+        let light = area_light();
+        let point = Tuple::point(1.0, 2.0, 3.0);
+        let samples = light.sample_points(point);
+        assert_eq!(samples.len(), 8);
+        for (i, sample) in samples.iter().enumerate() {
+            let (u, v) = ((i % 4) as f64, (i / 4) as f64);
+            assert!(sample.x >= u * 0.5 && sample.x <= (u + 1.0) * 0.5);
+            assert!(sample.z >= v * 0.5 && sample.z <= (v + 1.0) * 0.5);
+        }
+        // The same point always gets the same samples
+        assert_eq!(samples, light.sample_points(point));
+    }
+
+    #[test]
+    fn area_light_samples_aim_at_each_cell() {
+        let mut light = area_light();
+        light.jitter = false;
+        let point = Tuple::point(0.25, -2.0, 0.25);
+        let samples = light.samples(point);
+        assert_eq!(samples.len(), 8);
+        assert_eq!(samples[0].direction, Tuple::vector(0.0, 1.0, 0.0));
+        assert_eq!(samples[0].distance, 2.0);
+        assert_eq!(samples[0].intensity, Color::white());
+    }
+
+    #[test]
+    fn point_lights_have_a_single_sample() {
+        let light = PointLight::new(Tuple::point(0.0, 3.0, 4.0), Color::white());
+        let samples = light.samples(Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(
+            samples,
+            vec![LightSample {
+                direction: Tuple::vector(0.0, 0.6, 0.8),
+                distance: 5.0,
+                intensity: Color::white(),
+            }]
+        );
+    }
+
+    #[test]
+    fn directional_lights_shine_from_infinitely_far_away() {
+        let light = DirectionalLight::new(Tuple::vector(0.0, -2.0, 0.0), Color::white());
+        for point in [Tuple::point(0.0, 0.0, 0.0), Tuple::point(10.0, -5.0, 3.0)] {
+            assert_eq!(light.direction_from(point), Tuple::vector(0.0, 1.0, 0.0));
+            assert_eq!(light.distance_from(point), f64::INFINITY);
+            assert_eq!(light.intensity_at(point), Color::white());
+        }
+    }
+
+    #[test]
+    fn spot_lights_fade_between_their_cones() {
+        let light = SpotLight::new(
+            Tuple::point(0.0, 10.0, 0.0),
+            Tuple::vector(0.0, -1.0, 0.0),
+            PI / 8.0,
+            PI / 4.0,
+            Color::white(),
+        );
+        let intensity_at_angle = |angle: f64| {
+            let point = Tuple::point(10.0 * angle.tan(), 0.0, 0.0);
+            light.intensity_at(point).red
+        };
+        assert_eq!(intensity_at_angle(0.0), 1.0);
+        assert_eq!(intensity_at_angle(PI / 10.0), 1.0);
+        assert_eq!(intensity_at_angle(PI / 3.0), 0.0);
+        let between = intensity_at_angle(3.0 * PI / 16.0);
+        assert!(between > 0.0 && between < 1.0);
+        assert!(intensity_at_angle(PI / 5.0) < between);
+        assert_eq!(
+            light.direction_from(Tuple::point(0.0, 0.0, 0.0)),
+            Tuple::vector(0.0, 1.0, 0.0)
+        );
+        assert_eq!(light.distance_from(Tuple::point(0.0, 0.0, 0.0)), 10.0);
+        assert_eq!(light.intensity_at(light.position), Color::white());
+    }
+
+    #[test]
+    fn point_light_has_position_and_intensity() {
+        let intensity = Color::black();
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let light = PointLight::new(position, intensity);
+        assert_eq!(light.position, Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(light.intensity, Color::black());
+        assert_eq!(light.attenuation, Attenuation::default());
+    }
+
+    #[test]
+    fn attenuation_weakens_light_with_distance() {
+        let polynomial = Attenuation::Polynomial {
+            constant: 1.0,
+            linear: 0.5,
+            quadratic: 0.25,
+        };
+        assert_eq!(Attenuation::default().factor(100.0), 1.0);
+        assert_eq!(polynomial.factor(2.0), 1.0 / 3.0);
+        assert_eq!(Attenuation::InverseSquare.factor(4.0), 1.0 / 16.0);
+        assert_eq!(Attenuation::InverseSquare.factor(0.0), 1.0 / EPSILON);
+    }
+
+    #[test]
+    fn point_lights_attenuate_by_their_setting() {
+        let mut light = PointLight::new(Tuple::point(0.0, 0.0, 0.0), Color::white());
+        assert_eq!(light.attenuation(10.0), 1.0);
+        light.attenuation = Attenuation::InverseSquare;
+        assert_eq!(light.attenuation(10.0), 0.01);
+    }
+
+    #[test]
+    fn only_glowing_shapes_with_a_surface_to_sample_are_lights() {
+        use crate::rays::Material;
+        use crate::shapes::{Plane, Sphere};
+
+        let glow = Material::new().emission(Color::white());
+        let mut sphere = Sphere::new();
+        assert!(ShapeLight::new(&sphere, 4, 0.0).is_none());
+        sphere.material = glow.clone();
+        assert!(ShapeLight::new(&sphere, 4, 0.0).is_some());
+        let mut plane = Plane::new();
+        plane.material = glow;
+        assert!(ShapeLight::new(&plane, 4, 0.0).is_none());
+    }
+
+    #[test]
+    fn small_shape_lights_fall_off_like_point_lights() {
+        use crate::rays::Material;
+        use crate::shapes::Triangle;
+
+        // A triangle with an area of 0.005, facing the origin from 10 away
+        let mut triangle = Triangle::new(
+            Tuple::point(0.0, 0.0, 10.0),
+            Tuple::point(0.1, 0.0, 10.0),
+            Tuple::point(0.0, 0.1, 10.0),
+        );
+        triangle.material = Material::new().emission(Color::new(2.0, 2.0, 2.0));
+        let light = ShapeLight::new(&triangle, 8, 0.0).unwrap();
+        let samples = light.samples(Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(samples.len(), 8);
+        let expected = 2.0 * 0.005 / (100.0 * PI);
+        for sample in samples {
+            assert!((sample.intensity.red / expected - 1.0).abs() < 0.01);
+            assert!(sample.direction.z > 0.999);
+            assert!(sample.distance < 10.01);
+        }
+    }
+}
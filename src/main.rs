@@ -1,53 +1,358 @@
+use ray_tracer::camera::RenderSettings;
 use ray_tracer::canvas::Canvas;
-use ray_tracer::tuple::{Color, Tuple};
-use std::fs::File;
-use std::io::prelude::*;
+use ray_tracer::integrator::Integrator;
+use ray_tracer::sampling::{Adaptive, Filter, SampleStrategy};
+use ray_tracer::{Error, ImageFormat};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
-fn virtual_cannon() {
-    struct Projectile {
-        position: Tuple,
-        velocity: Tuple,
+const USAGE: &str = "\
+Usage: ray_tracer <scene file> [options]
+
+Options:
+  -o, --output <path>      where to write the image (default: the scene file
+                           with a .ppm extension)
+  -f, --format <format>    ppm-binary (default) or ppm
+  -s, --size <W>x<H>       render at this resolution instead of the camera's
+  -t, --threads <n>        worker threads (default: one per CPU)
+  -n, --samples <n>        rays per pixel (default: 1)
+      --strategy <name>    where samples go: grid (default), jittered or
+                           random
+      --filter <name>      how samples are weighed: box (default), tent,
+                           gaussian or mitchell
+      --seed <n>           seed for jittered and random samples (default: 0)
+      --adaptive <t>       refine only pixels differing from a neighbor by
+                           more than t in some channel, instead of sampling
+                           every pixel the same
+      --subdivisions <n>   with --adaptive, how often pixels may split
+                           (default: 3)
+      --heatmap <path>     with --adaptive, also save where samples went
+  -d, --depth <n>          maximum reflection depth, or path length when path
+                           tracing (default: 5)
+  -i, --integrator <name>  whitted (default) ray tracing, or path tracing with
+                           `path`; path tracing needs plenty of --samples
+  -h, --help               show this message";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    scene: PathBuf,
+    output: PathBuf,
+    format: ImageFormat,
+    size: Option<(i32, i32)>,
+    settings: RenderSettings,
+    heatmap: Option<PathBuf>,
+}
+
+enum Command {
+    Render(Options),
+    Help,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let mut scene = None;
+    let mut output = None;
+    let mut format = ImageFormat::PpmBinary;
+    let mut size = None;
+    let mut settings = RenderSettings::default();
+    let mut threshold = None;
+    let mut subdivisions = None;
+    let mut heatmap = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("`{}` needs a value", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-o" | "--output" => output = Some(PathBuf::from(value(&arg)?)),
+            "-f" | "--format" => {
+                format = match value(&arg)?.as_str() {
+                    "ppm" => ImageFormat::Ppm,
+                    "ppm-binary" => ImageFormat::PpmBinary,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "-s" | "--size" => size = Some(parse_size(&value(&arg)?)?),
+            "-t" | "--threads" => settings.threads = parse_count(&arg, &value(&arg)?)?,
+            "-n" | "--samples" => settings.samples = parse_count(&arg, &value(&arg)?)?,
+            "--strategy" => {
+                settings.strategy = match value(&arg)?.as_str() {
+                    "grid" => SampleStrategy::Grid,
+                    "jittered" => SampleStrategy::Jittered,
+                    "random" => SampleStrategy::Random,
+                    other => return Err(format!("unknown sampling strategy `{}`", other)),
+                }
+            }
+            "--filter" => {
+                settings.filter = match value(&arg)?.as_str() {
+                    "box" => Filter::Box,
+                    "tent" => Filter::Tent,
+                    "gaussian" => Filter::Gaussian,
+                    "mitchell" => Filter::Mitchell,
+                    other => return Err(format!("unknown filter `{}`", other)),
+                }
+            }
+            "--seed" => {
+                settings.seed = value(&arg)?
+                    .parse()
+                    .map_err(|_| format!("`{}` needs a whole number", arg))?
+            }
+            "--adaptive" => {
+                threshold = match value(&arg)?.parse::<f64>() {
+                    Ok(threshold) if threshold >= 0.0 => Some(threshold),
+                    _ => return Err(format!("`{}` needs a non-negative number", arg)),
+                }
+            }
+            "--subdivisions" => {
+                subdivisions = Some(
+                    value(&arg)?
+                        .parse()
+                        .map_err(|_| format!("`{}` needs a whole number", arg))?,
+                )
+            }
+            "--heatmap" => heatmap = Some(PathBuf::from(value(&arg)?)),
+            "-i" | "--integrator" => {
+                settings.integrator = match value(&arg)?.as_str() {
+                    "whitted" => Integrator::Whitted,
+                    "path" => Integrator::PathTracing,
+                    other => return Err(format!("unknown integrator `{}`", other)),
+                }
+            }
+            "-d" | "--depth" => {
+                settings.max_depth = value(&arg)?
+                    .parse()
+                    .map_err(|_| format!("`{}` needs a whole number", arg))?
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if scene.is_none() => scene = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
     }
 
-    struct Environment {
-        gravity: Tuple,
-        wind: Tuple,
+    let scene = scene.ok_or("no scene file given")?;
+    let output = output.unwrap_or_else(|| scene.with_extension("ppm"));
+    settings.adaptive = threshold.map(|threshold| Adaptive {
+        threshold,
+        max_subdivisions: subdivisions.unwrap_or(3),
+    });
+    if heatmap.is_some() && settings.adaptive.is_none() {
+        return Err("`--heatmap` needs `--adaptive`".to_string());
+    }
+    if subdivisions.is_some() && settings.adaptive.is_none() {
+        return Err("`--subdivisions` needs `--adaptive`".to_string());
     }
+    Ok(Command::Render(Options {
+        scene,
+        output,
+        format,
+        size,
+        settings,
+        heatmap,
+    }))
+}
 
-    fn tick(env: &Environment, projectile: Projectile) -> Projectile {
-        Projectile {
-            position: projectile.position + projectile.velocity,
-            velocity: projectile.velocity + env.gravity + env.wind,
-        }
+fn parse_size(text: &str) -> Result<(i32, i32), String> {
+    let invalid = || format!("size `{}` should look like 640x480", text);
+    let (width, height) = text.split_once('x').ok_or_else(invalid)?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
     }
+}
 
-    let mut projectile = Projectile {
-        position: Tuple::point(0.0, 1.0, 0.0),
-        velocity: Tuple::vector(1.0, 1.8, 0.0).normalize() * 7.85,
-    };
+fn parse_count(name: &str, text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("`{}` needs a positive whole number", name)),
+    }
+}
 
-    let env = Environment {
-        gravity: Tuple::vector(0.0, -0.1, 0.0),
-        wind: Tuple::vector(-0.01, 0.0, 0.0),
-    };
+fn render(options: &Options) -> Result<(), String> {
+    let scene = ray_tracer::scene::load(&options.scene).map_err(|err| match err {
+        Error::Parse(err) => format!("{}:{}: {}", options.scene.display(), err.line, err.message),
+        err => format!("can't read {}", err),
+    })?;
 
-    let mut canvas = Canvas::new(500, 300);
+    let mut camera = scene.camera;
+    if let Some((width, height)) = options.size {
+        camera = camera.with_size(width, height);
+    }
 
-    while projectile.position.y > 0.0 {
-        projectile = tick(&env, projectile);
-        let pos = projectile.position;
-        let pos_y = canvas.height - (pos.y as i32);
-        if pos_y <= canvas.height {
-            let color = Color::new(1.0, 0.0, 1.0);
-            canvas.write_pixel(&Tuple::point(pos.x, pos_y as f64, 0.0), color);
+    eprintln!(
+        "Rendering {}x{} with {} thread(s), {} sample(s) per pixel...",
+        camera.hsize, camera.vsize, options.settings.threads, options.settings.samples
+    );
+    let started = Instant::now();
+    let rows = camera.vsize as usize;
+    let shown = AtomicUsize::new(0);
+    let progress = |finished| {
+        let percent = finished * 100 / rows;
+        if shown.fetch_max(percent, Ordering::Relaxed) < percent {
+            eprint!("\r{:3}%", percent);
+            let _ = std::io::stderr().flush();
+        }
+    };
+    let (image, heatmap) = match (&options.settings.adaptive, &options.heatmap) {
+        (Some(adaptive), Some(path)) => {
+            let render =
+                camera.render_adaptive(&scene.world, &options.settings, adaptive, progress);
+            let heatmap = render.heatmap();
+            (render.image, Some((heatmap, path)))
         }
+        _ => (
+            camera.render_with(&scene.world, &options.settings, progress),
+            None,
+        ),
+    };
+    eprintln!("\rRendered in {:.2?}", started.elapsed());
+
+    save(&image, options.format, &options.output)?;
+    if let Some((heatmap, path)) = heatmap {
+        save(&heatmap, options.format, path)?;
     }
+    Ok(())
+}
 
-    println!("Writing PPM image file...");
-    let mut file = File::create("images/cannon.ppm").unwrap();
-    file.write_all(canvas.to_ppm().as_bytes()).unwrap();
+fn save(image: &Canvas, format: ImageFormat, path: &Path) -> Result<(), String> {
+    ray_tracer::save_image_as(image, path, format).map_err(|err| format!("can't write {}", err))?;
+    eprintln!("Saved {}", path.display());
+    Ok(())
 }
 
 fn main() {
-    virtual_cannon()
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Render(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(message) = render(&options) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        match parse_args(args.iter().map(|arg| arg.to_string()))? {
+            Command::Render(options) => Ok(options),
+            Command::Help => Err("help".to_string()),
+        }
+    }
+
+    #[test]
+    fn defaults_write_next_to_the_scene() {
+        let options = parse(&["scenes/spheres.yml"]).unwrap();
+        assert_eq!(options.output, PathBuf::from("scenes/spheres.ppm"));
+        assert_eq!(options.format, ImageFormat::PpmBinary);
+        assert_eq!(options.size, None);
+        assert_eq!(options.settings, RenderSettings::default());
+    }
+
+    #[test]
+    fn parsing_every_option() {
+        let options = parse(&[
+            "-o",
+            "out.ppm",
+            "scene.yml",
+            "--format",
+            "ppm",
+            "--size",
+            "320x200",
+            "-t",
+            "3",
+            "--samples",
+            "4",
+            "--depth",
+            "0",
+            "--strategy",
+            "jittered",
+            "--filter",
+            "mitchell",
+            "--seed",
+            "9",
+            "--adaptive",
+            "0.05",
+            "--heatmap",
+            "heat.ppm",
+            "--integrator",
+            "path",
+        ])
+        .unwrap();
+        assert_eq!(options.scene, PathBuf::from("scene.yml"));
+        assert_eq!(options.output, PathBuf::from("out.ppm"));
+        assert_eq!(options.format, ImageFormat::Ppm);
+        assert_eq!(options.size, Some((320, 200)));
+        assert_eq!(
+            options.settings,
+            RenderSettings {
+                threads: 3,
+                samples: 4,
+                strategy: SampleStrategy::Jittered,
+                filter: Filter::Mitchell,
+                seed: 9,
+                max_depth: 0,
+                integrator: Integrator::PathTracing,
+                adaptive: Some(Adaptive {
+                    threshold: 0.05,
+                    max_subdivisions: 3,
+                }),
+            }
+        );
+        assert_eq!(options.heatmap, Some(PathBuf::from("heat.ppm")));
+    }
+
+    #[test]
+    fn invalid_arguments_are_explained() {
+        let cases: [(&[&str], &str); 11] = [
+            (&[], "no scene file given"),
+            (&["a.yml", "b.yml"], "unexpected argument `b.yml`"),
+            (&["a.yml", "--fast"], "unknown option `--fast`"),
+            (
+                &["a.yml", "--size", "640"],
+                "size `640` should look like 640x480",
+            ),
+            (&["a.yml", "-t", "0"], "`-t` needs a positive whole number"),
+            (&["a.yml", "--output"], "`--output` needs a value"),
+            (
+                &["a.yml", "--filter", "lanczos"],
+                "unknown filter `lanczos`",
+            ),
+            (&["a.yml", "--seed", "-1"], "`--seed` needs a whole number"),
+            (
+                &["a.yml", "-i", "radiosity"],
+                "unknown integrator `radiosity`",
+            ),
+            (
+                &["a.yml", "--heatmap", "h.ppm"],
+                "`--heatmap` needs `--adaptive`",
+            ),
+            (
+                &["a.yml", "--subdivisions", "2"],
+                "`--subdivisions` needs `--adaptive`",
+            ),
+        ];
+        for (args, message) in cases {
+            assert_eq!(parse(args), Err(message.to_string()));
+        }
+    }
+
+    #[test]
+    fn asking_for_help() {
+        assert_eq!(parse(&["scene.yml", "--help"]), Err("help".to_string()));
+    }
 }
@@ -1,5 +1,6 @@
 mod transformations;
 
+use crate::geometry::{Point3, Vector3};
 use crate::tuple::Tuple;
 use std::ops::{Index, IndexMut, Mul};
 
@@ -12,6 +13,7 @@ pub struct Matrix {
 }
 
 impl Matrix {
+    #[cfg(test)]
     fn new(row_count: i32, col_count: usize) -> Self {
         let mut rows = Vec::new();
         for _ in 0..row_count {
@@ -83,6 +85,8 @@ impl Matrix {
     }
 
     // when specified matrix index is odd, negate the minor
+    // `is_multiple_of` needs Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
     fn cofactor(&self, row: usize, col: usize) -> f64 {
         if (row + col) % 2 == 0 {
             self.minor(row, col)
@@ -174,25 +178,7 @@ impl Mul<Tuple> for Matrix {
 
     // Hardcoded for a 4x4 matrix
     fn mul(self, other: Tuple) -> Self::Output {
-        let x = self[0][0] * other.x
-            + self[0][1] * other.y
-            + self[0][2] * other.z
-            + self[0][3] * other.w;
-        let y = self[1][0] * other.x
-            + self[1][1] * other.y
-            + self[1][2] * other.z
-            + self[1][3] * other.w;
-        let z = self[2][0] * other.x
-            + self[2][1] * other.y
-            + self[2][2] * other.z
-            + self[2][3] * other.w;
-        let w = self[3][0] * other.x
-            + self[3][1] * other.y
-            + self[3][2] * other.z
-            + self[3][3] * other.w;
-        let mut point = Tuple::point(x, y, z);
-        point.w = w;
-        point
+        &self * other
     }
 }
 
@@ -223,6 +209,32 @@ impl Mul<Tuple> for &Matrix {
     }
 }
 
+impl Mul<Point3> for &Matrix {
+    type Output = Point3;
+
+    // Hardcoded for a 4x4 affine matrix, w is implicitly 1
+    fn mul(self, point: Point3) -> Self::Output {
+        Point3::new(
+            self[0][0] * point.x + self[0][1] * point.y + self[0][2] * point.z + self[0][3],
+            self[1][0] * point.x + self[1][1] * point.y + self[1][2] * point.z + self[1][3],
+            self[2][0] * point.x + self[2][1] * point.y + self[2][2] * point.z + self[2][3],
+        )
+    }
+}
+
+impl Mul<Vector3> for &Matrix {
+    type Output = Vector3;
+
+    // Hardcoded for a 4x4 matrix, w is implicitly 0 so translation is ignored
+    fn mul(self, vector: Vector3) -> Self::Output {
+        Vector3::new(
+            self[0][0] * vector.x + self[0][1] * vector.y + self[0][2] * vector.z,
+            self[1][0] * vector.x + self[1][1] * vector.y + self[1][2] * vector.z,
+            self[2][0] * vector.x + self[2][1] * vector.y + self[2][2] * vector.z,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -497,6 +509,28 @@ mod tests {
         assert_eq!(matrix.transpose(), transposed);
     }
 
+    #[test]
+    fn multiplying_a_matrix_with_a_typed_point_applies_translation() {
+        let matrix = Matrix::identity()
+            .scale(2.0, 2.0, 2.0)
+            .translate(1.0, 2.0, 3.0);
+        assert_eq!(
+            &matrix * Point3::new(1.0, 1.0, 1.0),
+            Point3::new(3.0, 4.0, 5.0)
+        );
+    }
+
+    #[test]
+    fn multiplying_a_matrix_with_a_typed_vector_ignores_translation() {
+        let matrix = Matrix::identity()
+            .scale(2.0, 2.0, 2.0)
+            .translate(1.0, 2.0, 3.0);
+        assert_eq!(
+            &matrix * Vector3::new(1.0, 1.0, 1.0),
+            Vector3::new(2.0, 2.0, 2.0)
+        );
+    }
+
     #[test]
     fn multiplying_identity_matrix_reference_with_point_returns_point() {
         let tuple = Tuple::point(1.0, 2.0, 3.0);
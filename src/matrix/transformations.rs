@@ -1,4 +1,5 @@
 use super::Matrix;
+use crate::tuple::Tuple;
 
 impl Matrix {
     pub fn translate(&self, x: f64, y: f64, z: f64) -> Self {
@@ -17,7 +18,7 @@ impl Matrix {
         &transform * self
     }
 
-    fn rotate_x(&self, radians: f64) -> Self {
+    pub fn rotate_x(&self, radians: f64) -> Self {
         let mut transform = Matrix::identity();
         transform[1][1] = radians.cos();
         transform[1][2] = -radians.sin();
@@ -54,15 +55,79 @@ impl Matrix {
         transform[2][1] = zy;
         &transform * self
     }
+
+    /// Orients the world relative to an eye at `from` looking at `to`.
+    /// `up` only needs to point roughly upwards.
+    pub fn view_transform(from: Tuple, to: Tuple, up: Tuple) -> Self {
+        let forward = (to - from).normalize();
+        let left = forward.cross(&up.normalize());
+        let true_up = left.cross(&forward);
+        let orientation = Matrix::populate(vec![
+            vec![left.x, left.y, left.z, 0.0],
+            vec![true_up.x, true_up.y, true_up.z, 0.0],
+            vec![-forward.x, -forward.y, -forward.z, 0.0],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ]);
+        // Move the eye to the origin first, then orient
+        &orientation * &Matrix::identity().translate(-from.x, -from.y, -from.z)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::is_float_equal;
-    use crate::tuple::Tuple;
     use std::f64::consts::PI;
 
+    #[test]
+    fn arbitrary_view_transformation() {
+        let from = Tuple::point(1.0, 3.0, 2.0);
+        let to = Tuple::point(4.0, -2.0, 8.0);
+        let up = Tuple::vector(1.0, 1.0, 0.0);
+        let transform = Matrix::view_transform(from, to, up);
+        let expected = Matrix::populate(vec![
+            vec![-0.50709, 0.50709, 0.67612, -2.36643],
+            vec![0.76772, 0.60609, 0.12122, -2.82843],
+            vec![-0.35857, 0.59761, -0.71714, 0.0],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ]);
+        for row in 0..4 {
+            for col in 0..4 {
+                assert!(is_float_equal(transform[row][col], expected[row][col]));
+            }
+        }
+    }
+
+    #[test]
+    fn view_transformation_moves_the_world() {
+        let from = Tuple::point(0.0, 0.0, 8.0);
+        let to = Tuple::point(0.0, 0.0, 0.0);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        assert_eq!(
+            Matrix::view_transform(from, to, up),
+            Matrix::identity().translate(0.0, 0.0, -8.0)
+        );
+    }
+
+    #[test]
+    fn view_transformation_looking_in_positive_z_direction() {
+        let from = Tuple::point(0.0, 0.0, 0.0);
+        let to = Tuple::point(0.0, 0.0, 1.0);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        assert_eq!(
+            Matrix::view_transform(from, to, up),
+            Matrix::identity().scale(-1.0, 1.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn view_transformation_for_default_orientation() {
+        let from = Tuple::point(0.0, 0.0, 0.0);
+        let to = Tuple::point(0.0, 0.0, -1.0);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        assert_eq!(Matrix::view_transform(from, to, up), Matrix::identity());
+    }
+
     #[test]
     fn chaining_transformations() {
         let transform = Matrix::identity()
@@ -0,0 +1,263 @@
+//! Physically based metallic-roughness shading, the material model most
+//! content creation tools export.
+//!
+//! The specular lobe is a Cook-Torrance microfacet BRDF: a GGX distribution
+//! of microfacet normals, Smith's masking-shadowing term and Schlick's
+//! approximation of Fresnel reflectance. Whatever isn't reflected there is
+//! scattered diffusely, except by metals, which have no diffuse lobe.
+
+use crate::sampling::{cosine_hemisphere, orthonormal_basis};
+use crate::tuple::{Color, Tuple};
+use std::f64::consts::PI;
+
+/// How much light a dielectric reflects when viewed head on.
+const DIELECTRIC_REFLECTANCE: f64 = 0.04;
+/// Perfectly smooth surfaces make the distribution a spike, so roughness is
+/// kept just above zero.
+const MIN_ALPHA: f64 = 1e-3;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Microfacet {
+    /// 0 for dielectrics like plastic or wood, 1 for bare metal.
+    pub metallic: f64,
+    /// From 0, a perfect mirror, to 1, a very rough surface.
+    pub roughness: f64,
+}
+
+impl Default for Microfacet {
+    fn default() -> Self {
+        Self {
+            metallic: 0.0,
+            roughness: 0.5,
+        }
+    }
+}
+
+impl Microfacet {
+    pub fn new(metallic: f64, roughness: f64) -> Self {
+        Self {
+            metallic: metallic.clamp(0.0, 1.0),
+            roughness: roughness.clamp(0.0, 1.0),
+        }
+    }
+
+    // Squaring the roughness makes it feel perceptually linear
+    fn alpha(&self) -> f64 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+
+    /// Reflectance at normal incidence: 4% for dielectrics, the base color
+    /// for metals.
+    fn f0(&self, base_color: Color) -> Color {
+        let dielectric = Color::new(
+            DIELECTRIC_REFLECTANCE,
+            DIELECTRIC_REFLECTANCE,
+            DIELECTRIC_REFLECTANCE,
+        );
+        dielectric * (1.0 - self.metallic) + base_color * self.metallic
+    }
+
+    /// The BRDF times the cosine at the surface, for light arriving from
+    /// `light` and leaving toward `eye`. All vectors point away from the
+    /// surface.
+    pub fn evaluate(&self, base_color: Color, normal: Tuple, eye: Tuple, light: Tuple) -> Color {
+        let n_dot_l = normal.dot(&light);
+        let n_dot_v = normal.dot(&eye);
+        if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+            return Color::black();
+        }
+        let half = (eye + light).normalize();
+        let alpha = self.alpha();
+
+        let fresnel = fresnel_schlick(self.f0(base_color), eye.dot(&half).max(0.0));
+        let specular = fresnel
+            * (ggx_distribution(normal.dot(&half), alpha)
+                * smith_g1(n_dot_v, alpha)
+                * smith_g1(n_dot_l, alpha)
+                / (4.0 * n_dot_v * n_dot_l));
+        let diffuse = (Color::white() - fresnel) * base_color * ((1.0 - self.metallic) / PI);
+        (diffuse + specular) * n_dot_l
+    }
+
+    // Chance of sampling the specular lobe rather than the diffuse one
+    fn specular_probability(&self) -> f64 {
+        0.5 + 0.5 * self.metallic
+    }
+
+    /// Probability density of `sample` returning `light`, per solid angle.
+    pub fn pdf(&self, normal: Tuple, eye: Tuple, light: Tuple) -> f64 {
+        let n_dot_l = normal.dot(&light);
+        if n_dot_l <= 0.0 || normal.dot(&eye) <= 0.0 {
+            return 0.0;
+        }
+        let half = (eye + light).normalize();
+        let n_dot_h = normal.dot(&half);
+        let specular = ggx_distribution(n_dot_h, self.alpha()) * n_dot_h
+            / (4.0 * eye.dot(&half).abs().max(f64::MIN_POSITIVE));
+        let diffuse = n_dot_l / PI;
+        let p = self.specular_probability();
+        p * specular + (1.0 - p) * diffuse
+    }
+
+    /// Picks a direction to follow light back along, favoring those the
+    /// material reflects most, from three uniform numbers in `[0, 1)`.
+    /// Returns the direction with its weight, the BRDF times the cosine
+    /// divided by the probability density, or `None` if the sample went
+    /// below the surface.
+    pub fn sample(
+        &self,
+        base_color: Color,
+        normal: Tuple,
+        eye: Tuple,
+        (choice, u, v): (f64, f64, f64),
+    ) -> Option<(Tuple, Color)> {
+        let light = if choice < self.specular_probability() {
+            // Mirror the eye around a microfacet normal drawn from GGX
+            let half = sample_ggx(normal, self.alpha(), (u, v));
+            -eye.reflect(&half)
+        } else {
+            cosine_hemisphere(normal, (u, v))
+        };
+        let pdf = self.pdf(normal, eye, light);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some((light, self.evaluate(base_color, normal, eye, light) / pdf))
+    }
+}
+
+/// Share of microfacets facing along the half vector.
+fn ggx_distribution(n_dot_h: f64, alpha: f64) -> f64 {
+    if n_dot_h <= 0.0 {
+        return 0.0;
+    }
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+/// Share of microfacets visible from a direction at the given cosine, not
+/// hidden behind others.
+fn smith_g1(n_dot_x: f64, alpha: f64) -> f64 {
+    let alpha2 = alpha * alpha;
+    2.0 * n_dot_x / (n_dot_x + (alpha2 + (1.0 - alpha2) * n_dot_x * n_dot_x).sqrt())
+}
+
+fn fresnel_schlick(f0: Color, cosine: f64) -> Color {
+    f0 + (Color::white() - f0) * (1.0 - cosine).powi(5)
+}
+
+/// A microfacet normal around `normal`, distributed like GGX times the
+/// cosine.
+fn sample_ggx(normal: Tuple, alpha: f64, (u, v): (f64, f64)) -> Tuple {
+    let cos_theta = ((1.0 - u) / (1.0 + (alpha * alpha - 1.0) * u)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + normal * cos_theta)
+        .normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_float_equal;
+    use crate::sampling::pixel_rng;
+    use rand::Rng;
+
+    fn up() -> Tuple {
+        Tuple::vector(0.0, 1.0, 0.0)
+    }
+
+    #[test]
+    fn ggx_peaks_along_the_normal() {
+        assert!(is_float_equal(
+            ggx_distribution(1.0, 0.5),
+            1.0 / (PI * 0.25)
+        ));
+        assert!(ggx_distribution(0.9, 0.1) < ggx_distribution(1.0, 0.1));
+        assert_eq!(ggx_distribution(-0.5, 0.5), 0.0);
+    }
+
+    #[test]
+    fn smith_masking_vanishes_at_grazing_angles() {
+        assert!(is_float_equal(smith_g1(1.0, 0.7), 1.0));
+        assert_eq!(smith_g1(0.0, 0.7), 0.0);
+        assert!(smith_g1(0.3, 0.9) < smith_g1(0.3, 0.1));
+    }
+
+    #[test]
+    fn fresnel_rises_to_white_at_grazing_angles() {
+        let f0 = Color::new(0.04, 0.04, 0.04);
+        assert_eq!(fresnel_schlick(f0, 1.0), f0);
+        assert_eq!(fresnel_schlick(f0, 0.0), Color::white());
+    }
+
+    #[test]
+    fn rough_metal_viewed_and_lit_head_on() {
+        // D = 1/pi, G = 1 and F = base color, so the BRDF is base / (4 pi)
+        let base = Color::new(1.0, 0.8, 0.4);
+        let metal = Microfacet::new(1.0, 1.0);
+        let value = metal.evaluate(base, up(), up(), up());
+        assert!(value.is_equal(&(base / (4.0 * PI))));
+    }
+
+    #[test]
+    fn rough_dielectric_viewed_and_lit_head_on() {
+        // 4% reflects specularly, the rest scatters diffusely
+        let base = Color::new(0.5, 0.5, 0.5);
+        let plastic = Microfacet::new(0.0, 1.0);
+        let value = plastic.evaluate(base, up(), up(), up());
+        let expected = 0.96 * 0.5 / PI + 0.04 / (4.0 * PI);
+        assert!(value.is_equal(&Color::new(expected, expected, expected)));
+    }
+
+    #[test]
+    fn nothing_is_reflected_from_below_the_surface() {
+        let material = Microfacet::default();
+        let below = Tuple::vector(0.0, -1.0, 0.0);
+        assert_eq!(
+            material.evaluate(Color::white(), up(), up(), below),
+            Color::black()
+        );
+        assert_eq!(material.pdf(up(), up(), below), 0.0);
+    }
+
+    #[test]
+    fn samples_are_weighted_by_brdf_over_pdf() {
+        let material = Microfacet::new(0.3, 0.4);
+        let base = Color::new(0.9, 0.6, 0.3);
+        let eye = Tuple::vector(0.6, 0.8, 0.0);
+        let mut rng = pixel_rng(0, 1, 2);
+        for _ in 0..50 {
+            let numbers = (rng.gen(), rng.gen(), rng.gen());
+            if let Some((light, weight)) = material.sample(base, up(), eye, numbers) {
+                let expected =
+                    material.evaluate(base, up(), eye, light) / material.pdf(up(), eye, light);
+                assert!(weight.is_equal(&expected));
+            }
+        }
+    }
+
+    #[test]
+    fn sampling_estimates_how_much_light_a_white_metal_reflects() {
+        // References from integrating the BRDF over the hemisphere: nearly
+        // all the light for smooth metal, much less once rough microfacets
+        // start shadowing each other
+        let eye = Tuple::vector(0.0, 0.8, -0.6);
+        let mut rng = pixel_rng(4, 5, 6);
+        for (roughness, reference) in [(0.1, 1.0), (0.8, 0.565)] {
+            let metal = Microfacet::new(1.0, roughness);
+            let count = 4000;
+            let total: f64 = (0..count)
+                .filter_map(|_| {
+                    let numbers = (rng.gen(), rng.gen(), rng.gen());
+                    metal.sample(Color::white(), up(), eye, numbers)
+                })
+                .map(|(_, weight)| weight.red)
+                .sum();
+            let albedo = total / count as f64;
+            assert!((albedo - reference).abs() < 0.02, "{}", albedo);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Perlin's gradient noise: smooth, random-looking values that vary about
+//! once per unit, the same every time for the same point.
+
+use crate::tuple::Tuple;
+
+// The 12 directions from the center of a cube to the middles of its edges,
+// which Perlin's improved noise picks its gradients from
+const GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Noise at `point`, roughly between -1 and 1 and zero at whole coordinates.
+pub fn perlin(point: Tuple) -> f64 {
+    let cell = [point.x.floor(), point.y.floor(), point.z.floor()];
+    let offset = [point.x - cell[0], point.y - cell[1], point.z - cell[2]];
+    let cell = cell.map(|value| value as i64);
+
+    // How much the gradient at each corner of the cell pushes the value up
+    let corner = |dx: i64, dy: i64, dz: i64| {
+        let [gx, gy, gz] =
+            GRADIENTS[(hash(cell[0] + dx, cell[1] + dy, cell[2] + dz) % 12) as usize];
+        gx * (offset[0] - dx as f64) + gy * (offset[1] - dy as f64) + gz * (offset[2] - dz as f64)
+    };
+    let [u, v, w] = offset.map(fade);
+    let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+            lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+        ),
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+            lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+        ),
+    )
+}
+
+/// Layers of noise, each twice as fine and half as strong as the last, for
+/// detail at every scale.
+pub fn fractal(point: Tuple, octaves: usize) -> f64 {
+    let mut total = 0.0;
+    let (mut frequency, mut amplitude) = (1.0, 1.0);
+    for _ in 0..octaves {
+        let scaled = Tuple::point(
+            point.x * frequency,
+            point.y * frequency,
+            point.z * frequency,
+        );
+        total += perlin(scaled) * amplitude;
+        frequency *= 2.0;
+        amplitude /= 2.0;
+    }
+    total
+}
+
+// Eases in and out of each cell, so the noise has no creases at cell edges
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+// Scrambles a lattice point, standing in for Perlin's permutation table
+fn hash(x: i64, y: i64, z: i64) -> u64 {
+    let mut hash = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ (z as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash ^ (hash >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_vanishes_at_lattice_points() {
+        for point in [
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::point(3.0, -2.0, 7.0),
+            Tuple::point(-5.0, 1.0, -1.0),
+        ] {
+            assert_eq!(perlin(point), 0.0);
+        }
+    }
+
+    #[test]
+    fn noise_is_smooth_and_bounded() {
+        let mut previous = perlin(Tuple::point(0.0, 0.3, 0.7));
+        for step in 1..1000 {
+            let x = step as f64 * 0.01;
+            let value = perlin(Tuple::point(x, 0.3, 0.7));
+            assert!(value.abs() <= 1.5);
+            assert!((value - previous).abs() < 0.05);
+            previous = value;
+        }
+    }
+
+    #[test]
+    fn noise_varies_from_cell_to_cell() {
+        let values: Vec<f64> = (0..8)
+            .map(|x| perlin(Tuple::point(x as f64 + 0.5, 0.5, 0.5)))
+            .collect();
+        assert!(values.iter().any(|value| *value > 0.05));
+        assert!(values.iter().any(|value| *value < -0.05));
+    }
+
+    #[test]
+    fn fractal_noise_adds_finer_octaves() {
+        let point = Tuple::point(0.3, 0.6, 0.9);
+        assert_eq!(fractal(point, 1), perlin(point));
+        let finer = Tuple::point(0.6, 1.2, 1.8);
+        assert_eq!(fractal(point, 2), perlin(point) + perlin(finer) / 2.0);
+    }
+}
@@ -1,105 +1,105 @@
+use crate::bump::{Bump, HeightField, NormalMap};
+use crate::lights::Light;
 use crate::matrix::Matrix;
+use crate::microfacet::Microfacet;
+use crate::shapes::Object;
 use crate::tuple::{Color, Tuple};
+use crate::EPSILON;
+use std::f64::consts::PI;
 
 pub struct Ray {
-    origin: Tuple,
+    pub origin: Tuple,
     pub direction: Tuple,
+    /// When during the exposure the ray was cast, which decides where moving
+    /// objects are. Not to be confused with the `time` along the ray.
+    pub shutter_time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Tuple, direction: Tuple) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            shutter_time: 0.0,
+        }
+    }
+
+    pub fn at_shutter_time(mut self, shutter_time: f64) -> Self {
+        self.shutter_time = shutter_time;
+        self
     }
 
     pub fn position(&self, time: f64) -> Tuple {
         self.direction * time + self.origin
     }
 
-    pub fn intersect<'a>(&'a self, sphere: &'a Sphere) -> Option<Vec<Intersection>> {
-        // Hardcoded unit sphere
-        let sphere_center = Tuple::point(0.0, 0.0, 0.0);
-        // Transform the ray instead of the sphere - let the sphere stay at unit
-        let transform_inverse = match sphere.transform.inverse() {
-            Some(transform_inverse) => transform_inverse,
-            None => return None,
-        };
-        let new_ray = self.transform(transform_inverse);
-
-        // https://www.scratchapixel.com/lessons/3d-basic-rendering/minimal-ray-tracer-rendering-simple-shapes/ray-sphere-intersection.html
-        let center_to_origin = new_ray.origin - sphere_center;
-        let a = new_ray.direction.dot(&new_ray.direction);
-        let b = 2.0 * new_ray.direction.dot(&center_to_origin);
-        let c = center_to_origin.dot(&center_to_origin) - 1.0;
-        let discriminant = b * b - 4.0 * a * c;
-
-        if discriminant < 0.0 {
+    pub fn intersect<'a>(&self, object: &'a dyn Object) -> Option<Vec<Intersection<'a>>> {
+        // Transform the ray instead of the object - let the object stay at unit
+        // Nothing to hit while a moving object is scaled to nothing
+        let transform = object.transform().at(self.shutter_time)?;
+        let local_ray = self.transform(transform.inverse());
+        let times = object.local_intersect(&local_ray);
+        if times.is_empty() {
             return None;
         }
 
-        let sqrt = discriminant.sqrt();
-        Some(vec![
-            Intersection::new((-b - sqrt) / (2.0 * a), sphere),
-            Intersection::new((-b + sqrt) / (2.0 * a), sphere),
-        ])
+        Some(
+            times
+                .into_iter()
+                .map(|time| Intersection::new(time, object))
+                .collect(),
+        )
     }
 
-    fn transform(&self, transformation: Matrix) -> Self {
+    pub fn transform(&self, transformation: &Matrix) -> Self {
         Self {
-            origin: &transformation * self.origin,
-            direction: &transformation * self.direction,
+            origin: transformation * self.origin,
+            direction: transformation * self.direction,
+            shutter_time: self.shutter_time,
         }
     }
 }
 
-pub trait Object {
-    fn normal_at(&self, point: &Tuple) -> Option<Tuple>;
-}
-
-#[derive(Debug, PartialEq)]
-pub struct Sphere {
-    id: f64,
-    transform: Matrix,
-    pub material: Material,
-}
-
-impl Sphere {
-    fn new() -> Self {
-        Self::with_transform(Matrix::identity())
-    }
-
-    pub fn with_transform(transform: Matrix) -> Self {
-        use rand::Rng;
-        Self {
-            id: rand::thread_rng().gen(),
-            material: Material::new(),
-            transform,
-        }
-    }
+/// How a material responds to light.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ShadingModel {
+    /// Diffuse light plus a highlight around the mirror direction.
+    Phong,
+    /// Like Phong, but measuring the highlight with the vector halfway
+    /// between the light and the eye, which keeps it from being cut off at
+    /// grazing angles.
+    BlinnPhong,
+    /// Diffuse light only, for matte surfaces.
+    Lambert,
+    /// Diffuse light off a surface of tiny matte facets, which looks flatter
+    /// and brighter toward the light than Lambert, like clay or the moon.
+    /// `roughness` is how much the facets' slopes vary, in radians.
+    OrenNayar { roughness: f64 },
+    /// Just the material's color, whatever the lighting.
+    Unlit,
+    /// A physically based microfacet BRDF with `color` as the base color.
+    Microfacet(Microfacet),
+    /// Light scattered evenly in every direction whatever the normal, for
+    /// the smoke inside volumes.
+    Isotropic,
 }
 
-impl Object for Sphere {
-    fn normal_at(&self, world_point: &Tuple) -> Option<Tuple> {
-        match self.transform.inverse() {
-            Some(inverse) => {
-                let center = Tuple::point(0.0, 0.0, 0.0); // Hardcoded unit sphere
-                let object_point = &inverse * *world_point;
-                let object_normal = object_point - center;
-                let mut world_normal = inverse.transpose() * object_normal;
-                world_normal.w = 0.0; // hack - see page 82
-                Some(world_normal.normalize())
-            }
-            _ => None,
-        }
-    }
-}
+/// Share of the light a volume scatters toward any one direction: the
+/// isotropic phase function, 1/(4 pi), times pi since light intensities are
+/// scaled like Phong's diffuse term.
+pub(crate) const ISOTROPIC_SCATTER: f64 = 0.25;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Material {
-    color: Color,
+    pub(crate) color: Color,
     ambient: f64,
-    diffuse: f64,
+    pub(crate) diffuse: f64,
     specular: f64,
     shininess: f64,
+    pub(crate) reflective: f64,
+    pub(crate) emission: Color,
+    pub(crate) shading: ShadingModel,
+    pub(crate) bump: Option<Bump>,
 }
 
 impl Material {
@@ -110,6 +110,10 @@ impl Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            emission: Color::black(),
+            shading: ShadingModel::Phong,
+            bump: None,
         }
     }
 
@@ -117,6 +121,67 @@ impl Material {
         self.color = color;
         self
     }
+
+    pub fn ambient(mut self, ambient: f64) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn diffuse(mut self, diffuse: f64) -> Self {
+        self.diffuse = diffuse;
+        self
+    }
+
+    pub fn specular(mut self, specular: f64) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn shininess(mut self, shininess: f64) -> Self {
+        self.shininess = shininess;
+        self
+    }
+
+    /// How much of the scene the surface mirrors, from 0 (none) to 1 (all).
+    pub fn reflective(mut self, reflective: f64) -> Self {
+        self.reflective = reflective;
+        self
+    }
+
+    /// Light given off by the surface itself, seen directly by the camera.
+    /// Glowing spheres and triangles also light up the rest of the world.
+    pub fn emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    /// Phong by default. Models without a highlight ignore `specular` and
+    /// `shininess`, and `Microfacet` ignores `diffuse` too.
+    pub fn shading_model(mut self, shading: ShadingModel) -> Self {
+        self.shading = shading;
+        self
+    }
+
+    /// Bumps the surface by the slope of a height field, more so the larger
+    /// `scale`.
+    pub fn bump(mut self, field: HeightField, scale: f64) -> Self {
+        self.bump = Some(Bump::Height { field, scale });
+        self
+    }
+
+    /// Takes the surface's normals from a tangent-space normal map. Only
+    /// shapes with texture coordinates can use one.
+    pub fn normal_map(mut self, map: NormalMap) -> Self {
+        self.bump = Some(Bump::Map(map));
+        self
+    }
+
+    /// Shades with a physically based microfacet BRDF, see `shading_model`.
+    pub fn metallic_roughness(self, metallic: f64, roughness: f64) -> Self {
+        self.shading_model(ShadingModel::Microfacet(Microfacet::new(
+            metallic, roughness,
+        )))
+    }
 }
 
 impl Default for Material {
@@ -128,13 +193,57 @@ impl Default for Material {
 #[derive(Debug, PartialEq)]
 pub struct Intersection<'a> {
     pub time: f64,
-    pub object: &'a Sphere,
+    pub object: &'a dyn Object,
 }
 
 impl<'a> Intersection<'a> {
-    fn new(time: f64, object: &'a Sphere) -> Self {
+    pub fn new(time: f64, object: &'a dyn Object) -> Self {
         Self { time, object }
     }
+
+    /// Precompute the values needed to shade this intersection.
+    pub fn prepare_computations(&self, ray: &Ray) -> Computations<'a> {
+        let point = ray.position(self.time);
+        let eye = -ray.direction;
+        let mut normal = self.object.normal_at_time(&point, ray.shutter_time);
+        // A normal pointing away from the eye means the hit is inside the object
+        let inside = normal.dot(&eye) < 0.0;
+        if inside {
+            normal = -normal;
+        }
+        let over_point = point + normal * EPSILON;
+        // Bumps change how the surface is shaded, not where it is
+        if let Some(bump) = &self.object.material().bump {
+            normal = bump.perturb(self.object, point, normal, ray.shutter_time);
+        }
+
+        Computations {
+            time: self.time,
+            object: self.object,
+            point,
+            over_point,
+            eye,
+            normal,
+            reflect: ray.direction.reflect(&normal),
+            inside,
+            shutter_time: ray.shutter_time,
+        }
+    }
+}
+
+/// The state of an intersection that shading needs.
+/// `over_point` is nudged off the surface along the normal so shadow rays
+/// don't hit the surface they start on (acne).
+pub struct Computations<'a> {
+    pub time: f64,
+    pub object: &'a dyn Object,
+    pub point: Tuple,
+    pub over_point: Tuple,
+    pub eye: Tuple,
+    pub normal: Tuple,
+    pub reflect: Tuple,
+    pub inside: bool,
+    pub shutter_time: f64,
 }
 
 // Find the hit in a collection of intersections.
@@ -157,61 +266,343 @@ pub fn hit<'a>(intersections: &'a Vec<Intersection>) -> Option<&'a Intersection<
     hit
 }
 
-pub struct PointLight {
-    position: Tuple,
-    intensity: Color,
-}
-
-impl PointLight {
-    pub fn new(position: Tuple, intensity: Color) -> Self {
-        Self {
-            position,
-            intensity,
-        }
-    }
-}
-
+/// Shading of a point by the material's shading model, Phong unless it
+/// says otherwise. `light_intensity` is how much of the light reaches the
+/// point, from 0 (in shadow) to 1 (fully lit), and scales the diffuse and
+/// specular terms.
 pub fn lighting(
     material: &Material,
-    light: &PointLight,
+    light: &dyn Light,
     position: Tuple,
     eye: Tuple,
     normal: Tuple,
+    light_intensity: f64,
 ) -> Color {
+    if material.shading == ShadingModel::Unlit {
+        return material.color;
+    }
+
     // combine surface color with the light's color/intensity
-    let effective_color = &material.color * &light.intensity;
-    let light_direction = (light.position - position).normalize();
-    let ambient = &effective_color * material.ambient;
+    let ambient = material.color * light.ambient() * material.ambient;
+    if light_intensity <= 0.0 {
+        return ambient;
+    }
+
+    // Average the diffuse and specular terms over every point on the light
+    let samples = light.samples(position);
     let mut diffuse = Color::black();
     let mut specular = Color::black();
+    for sample in &samples {
+        let light_direction = sample.direction;
+        let intensity = sample.intensity * light.attenuation(sample.distance);
+        let effective_color = material.color * intensity;
+
+        // Times pi so a matte surface is as bright as Phong's diffuse term
+        if let ShadingModel::Microfacet(microfacet) = &material.shading {
+            let reflected = microfacet.evaluate(material.color, normal, eye, light_direction);
+            diffuse += reflected * intensity * PI;
+            continue;
+        }
 
-    // This is the cosine of the angle between the light vector and normal.
-    // A negative value means the light is on the other side of the surface.
-    let light_dot_normal = light_direction.dot(&normal);
-    if light_dot_normal > 0.0 {
-        diffuse = &(&effective_color * material.diffuse) * light_dot_normal;
-
-        // This is the cosine of the angle between the eye and reflection.
-        // A negative value means the light reflects away from the eye.
-        let reflection_direction = -light_direction.reflect(&normal);
-        let reflect_dot_eye = reflection_direction.dot(&eye);
-
-        if reflect_dot_eye <= 0.0 {
-            specular = Color::black();
-        } else {
-            let factor = reflect_dot_eye.powf(material.shininess);
-            specular = &(&light.intensity * material.specular) * factor;
+        if material.shading == ShadingModel::Isotropic {
+            diffuse += effective_color * material.diffuse * ISOTROPIC_SCATTER;
+            continue;
+        }
+
+        // This is the cosine of the angle between the light vector and normal.
+        // A negative value means the light is on the other side of the surface.
+        let light_dot_normal = light_direction.dot(&normal);
+        if light_dot_normal <= 0.0 {
+            continue;
+        }
+        let mut diffuse_term = effective_color * material.diffuse * light_dot_normal;
+        if let ShadingModel::OrenNayar { roughness } = material.shading {
+            diffuse_term *= oren_nayar(roughness, normal, eye, light_direction);
+        }
+        diffuse += diffuse_term;
+
+        // This is the cosine of the angle between the eye and reflection, or
+        // between the normal and the half vector for Blinn-Phong. A negative
+        // value means the light reflects away from the eye.
+        let highlight = match material.shading {
+            ShadingModel::Phong => (-light_direction.reflect(&normal)).dot(&eye),
+            ShadingModel::BlinnPhong => (light_direction + eye).normalize().dot(&normal),
+            _ => continue,
+        };
+        if highlight > 0.0 {
+            let factor = highlight.powf(material.shininess);
+            specular += intensity * material.specular * factor;
         }
     }
-    ambient + diffuse + specular
+    let scale = light_intensity / samples.len() as f64;
+    ambient + diffuse * scale + specular * scale
+}
+
+/// Oren-Nayar's correction to Lambert's diffuse term, for facets whose
+/// slopes vary by `roughness` radians. All vectors point away from the
+/// surface.
+pub(crate) fn oren_nayar(roughness: f64, normal: Tuple, eye: Tuple, light: Tuple) -> f64 {
+    let sigma2 = roughness * roughness;
+    let a = 1.0 - 0.5 * sigma2 / (sigma2 + 0.33);
+    let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+    let (cos_in, cos_out) = (light.dot(&normal), eye.dot(&normal));
+    let (theta_in, theta_out) = (
+        cos_in.clamp(-1.0, 1.0).acos(),
+        cos_out.clamp(-1.0, 1.0).acos(),
+    );
+    let (alpha, beta) = (theta_in.max(theta_out), theta_in.min(theta_out));
+
+    // Cosine of the angle between the light and the eye around the normal,
+    // which is undefined, and doesn't matter, when either is along it
+    let (light_across, eye_across) = (light - normal * cos_in, eye - normal * cos_out);
+    let cos_azimuth = if light_across.magnitude() < EPSILON || eye_across.magnitude() < EPSILON {
+        0.0
+    } else {
+        light_across.normalize().dot(&eye_across.normalize())
+    };
+    a + b * cos_azimuth.max(0.0) * alpha.sin() * beta.tan()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::is_float_equal;
+    use crate::lights::{AreaLight, Attenuation, PointLight};
     use crate::matrix::Matrix;
-    use core::f64::consts::{FRAC_1_SQRT_2, PI};
+    use crate::shapes::{Plane, Sphere};
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn lighting_samples_the_area_light() {
+        let mut area = AreaLight::new(
+            Tuple::point(-0.5, -0.5, -5.0),
+            Tuple::vector(1.0, 0.0, 0.0),
+            2,
+            Tuple::vector(0.0, 1.0, 0.0),
+            2,
+            Color::white(),
+        );
+        area.jitter = false;
+        let material = Material::new().ambient(0.1).diffuse(0.9).specular(0.0);
+        let eye = Tuple::point(0.0, 0.0, -5.0);
+        let cases = [
+            (Tuple::point(0.0, 0.0, -1.0), 0.9965),
+            (Tuple::point(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2), 0.62318),
+        ];
+        for (point, expected) in cases {
+            let eye = (eye - point).normalize();
+            let normal = Tuple::vector(point.x, point.y, point.z);
+            let color = lighting(&material, &area, point, eye, normal, 1.0);
+            assert!(color.is_equal(&Color::new(expected, expected, expected)));
+        }
+    }
+
+    #[test]
+    fn lighting_uses_light_intensity_to_attenuate_color() {
+        let material = Material::new()
+            .ambient(0.1)
+            .diffuse(0.9)
+            .specular(0.0)
+            .color(Color::white());
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::white());
+        let point = Tuple::point(0.0, 0.0, -1.0);
+        let eye = Tuple::vector(0.0, 0.0, -1.0);
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        for (intensity, expected) in [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)] {
+            let color = lighting(&material, &light, point, eye, normal, intensity);
+            assert!(color.is_equal(&Color::new(expected, expected, expected)));
+        }
+    }
+
+    #[test]
+    fn lighting_attenuates_point_lights_with_distance() {
+        let material = Material::new()
+            .ambient(0.1)
+            .diffuse(0.9)
+            .specular(0.0)
+            .color(Color::white());
+        let mut light = PointLight::new(Tuple::point(0.0, 0.0, -2.0), Color::white());
+        light.attenuation = Attenuation::InverseSquare;
+        let point = Tuple::point(0.0, 0.0, 0.0);
+        let eye = Tuple::vector(0.0, 0.0, -1.0);
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let color = lighting(&material, &light, point, eye, normal, 1.0);
+        // Ambient light isn't attenuated, only the diffuse term
+        assert!(color.is_equal(&Color::new(0.325, 0.325, 0.325)));
+    }
+
+    #[test]
+    fn lighting_a_metallic_roughness_material() {
+        let material = Material::new()
+            .ambient(0.1)
+            .color(Color::new(1.0, 0.8, 0.4))
+            .metallic_roughness(1.0, 1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::white());
+        let eye = Tuple::vector(0.0, 0.0, -1.0);
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let point = Tuple::point(0.0, 0.0, 0.0);
+        let color = lighting(&material, &light, point, eye, normal, 1.0);
+        // Rough metal lit head on reflects a quarter of its base color
+        let expected = Color::new(0.1, 0.08, 0.04) + Color::new(1.0, 0.8, 0.4) * 0.25;
+        assert!(color.is_equal(&expected));
+    }
+
+    // Lights a surface facing -z from 45 degrees above, like the Phong tests,
+    // as seen from `eye`
+    fn shade_with_light_above(material: &Material, eye: Tuple) -> f64 {
+        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::white());
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let point = Tuple::point(0.0, 0.0, 0.0);
+        let color = lighting(material, &light, point, eye, normal, 1.0);
+        assert!(is_float_equal(color.red, color.blue));
+        color.red
+    }
+
+    #[test]
+    fn blinn_phong_highlights_around_the_half_vector() {
+        // The half vector is 22.5 degrees off the normal
+        let material = Material::new()
+            .shininess(10.0)
+            .shading_model(ShadingModel::BlinnPhong);
+        let eye = Tuple::vector(0.0, 0.0, -1.0);
+        assert!(is_float_equal(
+            shade_with_light_above(&material, eye),
+            1.14415
+        ));
+    }
+
+    #[test]
+    fn lambert_shading_has_no_highlight() {
+        // The eye sits on the reflection, where Phong is brightest
+        let eye = Tuple::vector(0.0, -FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let phong = shade_with_light_above(&Material::new(), eye);
+        assert!(is_float_equal(phong, 1.6364));
+        let lambert = Material::new().shading_model(ShadingModel::Lambert);
+        assert!(is_float_equal(
+            shade_with_light_above(&lambert, eye),
+            0.7364
+        ));
+    }
+
+    #[test]
+    fn oren_nayar_brightens_rough_surfaces_seen_from_the_light() {
+        let material = Material::new().shading_model(ShadingModel::OrenNayar { roughness: 0.5 });
+        let cases = [
+            (Tuple::vector(0.0, 0.0, -1.0), 0.59924),
+            (Tuple::vector(0.0, 0.5, -(0.75_f64.sqrt())), 0.68521),
+        ];
+        for (eye, expected) in cases {
+            assert!(is_float_equal(
+                shade_with_light_above(&material, eye),
+                expected
+            ));
+        }
+    }
+
+    #[test]
+    fn smooth_oren_nayar_is_lambert() {
+        let smooth = Material::new().shading_model(ShadingModel::OrenNayar { roughness: 0.0 });
+        let eye = Tuple::vector(0.0, 0.5, -(0.75_f64.sqrt()));
+        assert!(is_float_equal(shade_with_light_above(&smooth, eye), 0.7364));
+    }
+
+    #[test]
+    fn unlit_materials_show_their_color() {
+        let material = Material::new()
+            .color(Color::new(0.2, 0.4, 0.6))
+            .shading_model(ShadingModel::Unlit);
+        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::white());
+        let eye = Tuple::vector(0.0, 0.0, -1.0);
+        let point = Tuple::point(0.0, 0.0, 0.0);
+        for intensity in [0.0, 1.0] {
+            let color = lighting(&material, &light, point, eye, eye, intensity);
+            assert_eq!(color, Color::new(0.2, 0.4, 0.6));
+        }
+    }
+
+    #[test]
+    fn isotropic_materials_scatter_the_same_whatever_the_normal() {
+        let material = Material::new().shading_model(ShadingModel::Isotropic);
+        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::white());
+        let eye = Tuple::vector(0.0, 0.0, -1.0);
+        let point = Tuple::point(0.0, 0.0, 0.0);
+        for normal in [eye, -eye, Tuple::vector(1.0, 0.0, 0.0)] {
+            let color = lighting(&material, &light, point, eye, normal, 1.0);
+            assert!(color.is_equal(&Color::new(0.325, 0.325, 0.325)));
+        }
+    }
+
+    #[test]
+    fn lighting_with_the_surface_in_shadow() {
+        let material = Material::new();
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let eye = Tuple::vector(0.0, 0.0, -1.0);
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::white());
+        let color = lighting(&material, &light, position, eye, normal, 0.0);
+        assert_eq!(color, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn hit_should_offset_the_point() {
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let sphere = Sphere::with_transform(Matrix::identity().translate(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(5.0, &sphere);
+        let comps = intersection.prepare_computations(&ray);
+        assert!(comps.over_point.z < -EPSILON / 2.0);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+
+    #[test]
+    fn bumps_tilt_the_shading_normal_but_not_the_offset() {
+        let mut plane = Plane::new();
+        plane.material = Material::new().bump(HeightField::Waves { frequency: 0.25 }, 1.0);
+        let ray = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let comps = Intersection::new(1.0, &plane).prepare_computations(&ray);
+        assert!(comps.normal.x < -0.5);
+        assert!(comps.over_point.is_equal(&Tuple::point(0.0, EPSILON, 0.0)));
+    }
+
+    #[test]
+    fn precomputing_a_hit_on_the_inside() {
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let sphere = Sphere::new();
+        let intersection = Intersection::new(1.0, &sphere);
+        let comps = intersection.prepare_computations(&ray);
+        assert!(comps.inside);
+        assert_eq!(comps.point, Tuple::point(0.0, 0.0, 1.0));
+        assert_eq!(comps.eye, Tuple::vector(0.0, 0.0, -1.0));
+        assert_eq!(comps.normal, Tuple::vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let plane = Plane::new();
+        let ray = Ray::new(
+            Tuple::point(0.0, 1.0, -1.0),
+            Tuple::vector(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let intersection = Intersection::new(2.0_f64.sqrt(), &plane);
+        let comps = intersection.prepare_computations(&ray);
+        assert!(comps
+            .reflect
+            .is_equal(&Tuple::vector(0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2)));
+    }
+
+    #[test]
+    fn precomputing_a_hit_on_the_outside() {
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let sphere = Sphere::new();
+        let intersection = Intersection::new(4.0, &sphere);
+        let comps = intersection.prepare_computations(&ray);
+        assert!(!comps.inside);
+        assert_eq!(comps.time, intersection.time);
+        assert_eq!(comps.object, &sphere as &dyn Object);
+        assert_eq!(comps.point, Tuple::point(0.0, 0.0, -1.0));
+        assert_eq!(comps.eye, Tuple::vector(0.0, 0.0, -1.0));
+        assert_eq!(comps.normal, Tuple::vector(0.0, 0.0, -1.0));
+    }
 
     #[test]
     fn lighting_with_light_behind_surface() {
@@ -220,7 +611,7 @@ mod tests {
         let eye = Tuple::vector(0.0, 0.0, -1.0);
         let normal = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 0.0, 10.0), Color::white());
-        let color = lighting(&material, &light, position, eye, normal);
+        let color = lighting(&material, &light, position, eye, normal, 1.0);
         assert_eq!(color, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -231,7 +622,7 @@ mod tests {
         let eye = Tuple::vector(0.0, 2.0_f64.sqrt() / -2.0, 2.0_f64.sqrt() / -2.0);
         let normal = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::white());
-        let color = lighting(&material, &light, position, eye, normal);
+        let color = lighting(&material, &light, position, eye, normal, 1.0);
         assert!(is_float_equal(color.red, 1.6364));
         assert!(is_float_equal(color.green, 1.6364));
         assert!(is_float_equal(color.blue, 1.6364));
@@ -244,7 +635,7 @@ mod tests {
         let eye = Tuple::vector(0.0, 0.0, -1.0);
         let normal = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::white());
-        let color = lighting(&material, &light, position, eye, normal);
+        let color = lighting(&material, &light, position, eye, normal, 1.0);
         assert!(is_float_equal(color.red, 0.7364));
         assert!(is_float_equal(color.green, 0.7364));
         assert!(is_float_equal(color.blue, 0.7364));
@@ -257,7 +648,7 @@ mod tests {
         let eye = Tuple::vector(0.0, 2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / -2.0);
         let normal = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::white());
-        let color = lighting(&material, &light, position, eye, normal);
+        let color = lighting(&material, &light, position, eye, normal, 1.0);
         assert_eq!(color, Color::new(1.0, 1.0, 1.0));
     }
 
@@ -268,24 +659,10 @@ mod tests {
         let eye = Tuple::vector(0.0, 0.0, -1.0);
         let normal = Tuple::vector(0.0, 0.0, -1.0);
         let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::white());
-        let color = lighting(&material, &light, position, eye, normal);
+        let color = lighting(&material, &light, position, eye, normal, 1.0);
         assert_eq!(color, Color::new(1.9, 1.9, 1.9));
     }
 
-    #[test]
-    fn sphere_can_have_material_assigned() {
-        let mut sphere = Sphere::new();
-        let mut material = Material::new();
-        material.ambient = 1.99;
-        sphere.material = material;
-        assert_eq!(sphere.material.ambient, 1.99);
-    }
-
-    #[test]
-    fn sphere_has_a_default_material() {
-        assert_eq!(Sphere::new().material, Material::new());
-    }
-
     #[test]
     fn material_default_values() {
         let material = Material::new();
@@ -296,76 +673,6 @@ mod tests {
         assert_eq!(material.shininess, 200.0);
     }
 
-    #[test]
-    fn point_light_has_position_and_intensity() {
-        let intensity = Color::black();
-        let position = Tuple::point(0.0, 0.0, 0.0);
-        let light = PointLight::new(position, intensity);
-        assert_eq!(light.position, Tuple::point(0.0, 0.0, 0.0));
-        assert_eq!(light.intensity, Color::black());
-    }
-
-    #[test]
-    fn calculate_normal_on_transformed_sphere() {
-        let sphere =
-            Sphere::with_transform(Matrix::identity().rotate_z(PI / 5.0).scale(1.0, 0.5, 1.0));
-        let value = 2.0_f64.sqrt() / 2.0;
-        let normal = sphere.normal_at(&Tuple::point(0.0, value, -value)).unwrap();
-        assert!(is_float_equal(normal.y, 0.97014));
-        assert!(is_float_equal(normal.z, -0.24254));
-    }
-
-    #[test]
-    fn calculate_normal_on_translated_sphere() {
-        let sphere = Sphere::with_transform(Matrix::identity().translate(0.0, 1.0, 0.0));
-        let normal = sphere
-            .normal_at(&Tuple::point(0.0, 1.70711, -FRAC_1_SQRT_2))
-            .unwrap();
-        assert!(is_float_equal(normal.y, FRAC_1_SQRT_2));
-        assert!(is_float_equal(normal.z, -FRAC_1_SQRT_2));
-    }
-
-    #[test]
-    fn normals_are_always_normalized() {
-        let sphere = Sphere::new();
-        let value = 3.0_f64.sqrt() / 3.0;
-        let normal = sphere
-            .normal_at(&Tuple::point(value, value, value))
-            .unwrap();
-        assert_eq!(normal, normal.normalize());
-    }
-
-    #[test]
-    fn normal_of_a_sphere_on_nonaxial_point() {
-        let sphere = Sphere::new();
-        let value = 3.0_f64.sqrt() / 3.0;
-        let normal = sphere
-            .normal_at(&Tuple::point(value, value, value))
-            .unwrap();
-        assert_eq!(normal, Tuple::vector(value, value, value));
-    }
-
-    #[test]
-    fn normal_of_a_sphere_on_z_axis() {
-        let sphere = Sphere::new();
-        let normal = sphere.normal_at(&Tuple::point(0.0, 0.0, 1.0)).unwrap();
-        assert_eq!(normal, Tuple::vector(0.0, 0.0, 1.0));
-    }
-
-    #[test]
-    fn normal_of_a_sphere_on_y_axis() {
-        let sphere = Sphere::new();
-        let normal = sphere.normal_at(&Tuple::point(0.0, 1.0, 0.0)).unwrap();
-        assert_eq!(normal, Tuple::vector(0.0, 1.0, 0.0));
-    }
-
-    #[test]
-    fn normal_of_a_sphere_on_x_axis() {
-        let sphere = Sphere::new();
-        let normal = sphere.normal_at(&Tuple::point(1.0, 0.0, 0.0)).unwrap();
-        assert_eq!(normal, Tuple::vector(1.0, 0.0, 0.0));
-    }
-
     #[test]
     fn intersecting_translated_sphere_with_ray() {
         let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
@@ -382,29 +689,11 @@ mod tests {
         assert_eq!(intersections[1].time, 7.0);
     }
 
-    #[test]
-    fn new_sphere_has_default_transform_and_can_be_changed() {
-        let mut sphere = Sphere::new();
-        assert_eq!(sphere.transform, Matrix::identity());
-        sphere.transform = Matrix::identity().translate(2.0, 0.0, 1.0);
-        assert_eq!(
-            sphere.transform,
-            Matrix::identity().translate(2.0, 0.0, 1.0)
-        );
-    }
-
-    #[test]
-    fn new_sphere_returns_unique_value() {
-        let one = Sphere::new();
-        let two = Sphere::new();
-        assert_ne!(one, two);
-    }
-
     #[test]
     fn scaling_a_ray() {
         let ray = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0));
         let scaling = Matrix::identity().scale(2.0, 3.0, 4.0);
-        let new_ray = ray.transform(scaling);
+        let new_ray = ray.transform(&scaling);
         assert_eq!(new_ray.origin, Tuple::point(2.0, 6.0, 12.0));
         assert_eq!(new_ray.direction, Tuple::vector(0.0, 3.0, 0.0));
     }
@@ -413,7 +702,7 @@ mod tests {
     fn translating_a_ray() {
         let ray = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0));
         let translation = Matrix::identity().translate(3.0, 4.0, 5.0);
-        let new_ray = ray.transform(translation);
+        let new_ray = ray.transform(&translation);
         assert_eq!(new_ray.origin, Tuple::point(4.0, 6.0, 8.0));
         assert_eq!(new_ray.direction, Tuple::vector(0.0, 1.0, 0.0));
     }
@@ -466,7 +755,7 @@ mod tests {
         let sphere = Sphere::new();
         let intersection = Intersection::new(3.5, &sphere);
         assert_eq!(intersection.time, 3.5);
-        assert_eq!(intersection.object, &sphere);
+        assert_eq!(intersection.object, &sphere as &dyn Object);
     }
 
     #[test]
@@ -475,9 +764,9 @@ mod tests {
         let sphere = Sphere::new();
         let ints = ray.intersect(&sphere).unwrap();
         assert_eq!(ints[0].time, -6.0);
-        assert_eq!(ints[0].object, &sphere);
+        assert_eq!(ints[0].object, &sphere as &dyn Object);
         assert_eq!(ints[1].time, -4.0);
-        assert_eq!(ints[1].object, &sphere);
+        assert_eq!(ints[1].object, &sphere as &dyn Object);
     }
 
     #[test]
@@ -0,0 +1,333 @@
+//! Where the rays for a pixel go, and how much each one counts.
+//!
+//! Each pixel gathers its samples from the footprint of the reconstruction
+//! filter, a square of side `2 * radius` around the pixel center, and
+//! averages them weighted by the filter.
+
+use crate::tuple::{Color, Tuple};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+/// How sample positions are spread over a pixel's footprint.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SampleStrategy {
+    /// The centers of an evenly spaced grid of cells.
+    Grid,
+    /// One random point inside each cell of the grid.
+    Jittered,
+    /// Uniformly random points, ignoring the grid.
+    Random,
+}
+
+/// How much a sample contributes based on its distance from the pixel center.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Filter {
+    /// Every sample inside the pixel counts the same.
+    Box,
+    /// Weight falls off linearly, reaching zero one pixel away.
+    Tent,
+    /// Bell curve with a standard deviation of half a pixel.
+    Gaussian,
+    /// Mitchell-Netravali cubic with B = C = 1/3, a little sharper than a
+    /// Gaussian thanks to its negative lobes.
+    Mitchell,
+}
+
+/// Settings for adaptive anti-aliasing, which only spends extra samples on
+/// pixels that differ from their neighbors.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Adaptive {
+    /// Largest per-channel difference still considered the same color.
+    pub threshold: f64,
+    /// How many times a pixel may be split into quadrants.
+    pub max_subdivisions: usize,
+}
+
+impl Adaptive {
+    pub fn differ(&self, a: &Color, b: &Color) -> bool {
+        let difference = *a - *b;
+        difference
+            .iter()
+            .any(|channel| channel.abs() > self.threshold)
+    }
+}
+
+const GAUSSIAN_SIGMA: f64 = 0.5;
+const MITCHELL_B: f64 = 1.0 / 3.0;
+const MITCHELL_C: f64 = 1.0 / 3.0;
+
+impl Filter {
+    /// Half the width of the square the filter covers, in pixels.
+    pub fn radius(&self) -> f64 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 3.0 * GAUSSIAN_SIGMA,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    /// Weight of a sample at the given offset from the pixel center.
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        let radius = self.radius();
+        if dx.abs() > radius || dy.abs() > radius {
+            return 0.0;
+        }
+
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => (1.0 - dx.abs()) * (1.0 - dy.abs()),
+            Filter::Gaussian => {
+                (-(dx * dx + dy * dy) / (2.0 * GAUSSIAN_SIGMA * GAUSSIAN_SIGMA)).exp()
+            }
+            Filter::Mitchell => mitchell(dx) * mitchell(dy),
+        }
+    }
+}
+
+fn mitchell(x: f64) -> f64 {
+    let (b, c) = (MITCHELL_B, MITCHELL_C);
+    let x = x.abs();
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+            + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+            + (6.0 - 2.0 * b)
+    } else if x < 2.0 {
+        (-b - 6.0 * c) * x.powi(3)
+            + (6.0 * b + 30.0 * c) * x.powi(2)
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        0.0
+    };
+    value / 6.0
+}
+
+/// Sample positions in the unit square, `[0, 1)` on both axes.
+pub fn sample_positions(
+    strategy: SampleStrategy,
+    count: usize,
+    rng: &mut StdRng,
+) -> Vec<(f64, f64)> {
+    // Cells are as close to square as the count allows
+    let columns = (count as f64).sqrt().ceil().max(1.0) as usize;
+    let rows = count.div_ceil(columns).max(1);
+    (0..count)
+        .map(|i| {
+            let (column, row) = ((i % columns) as f64, (i / columns) as f64);
+            match strategy {
+                SampleStrategy::Grid => {
+                    ((column + 0.5) / columns as f64, (row + 0.5) / rows as f64)
+                }
+                SampleStrategy::Jittered => (
+                    (column + rng.gen::<f64>()) / columns as f64,
+                    (row + rng.gen::<f64>()) / rows as f64,
+                ),
+                SampleStrategy::Random => (rng.gen(), rng.gen()),
+            }
+        })
+        .collect()
+}
+
+/// Maps a point in the unit square onto the unit disk, keeping evenly spread
+/// points evenly spread (Shirley and Chiu's concentric mapping).
+pub fn sample_disk((u, v): (f64, f64)) -> (f64, f64) {
+    let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (radius, angle) = if x.abs() > y.abs() {
+        (x, FRAC_PI_4 * (y / x))
+    } else {
+        (y, FRAC_PI_2 - FRAC_PI_4 * (x / y))
+    };
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+/// A direction in the hemisphere around `normal`, for a point in the unit
+/// square. Directions near the normal come up more often, in proportion to
+/// the cosine of their angle to it, which is how much light a matte surface
+/// reflects that way.
+pub fn cosine_hemisphere(normal: Tuple, sample: (f64, f64)) -> Tuple {
+    // Points spread evenly over a disk, projected up onto the hemisphere
+    let (x, y) = sample_disk(sample);
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
+
+/// A direction anywhere on the unit sphere, for a point in the unit square,
+/// with every direction as likely as any other.
+pub fn uniform_sphere((u, v): (f64, f64)) -> Tuple {
+    let z = 1.0 - 2.0 * u;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let angle = 2.0 * PI * v;
+    Tuple::vector(radius * angle.cos(), radius * angle.sin(), z)
+}
+
+/// Two unit vectors perpendicular to `normal` and to each other.
+pub fn orthonormal_basis(normal: Tuple) -> (Tuple, Tuple) {
+    let helper = if normal.x.abs() > 0.9 {
+        Tuple::vector(0.0, 1.0, 0.0)
+    } else {
+        Tuple::vector(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(&normal).normalize();
+    (tangent, normal.cross(&tangent))
+}
+
+/// A random number generator for one pixel. The same seed and pixel always
+/// give the same numbers, whatever order pixels are rendered in.
+pub fn pixel_rng(seed: u64, x: i32, y: i32) -> StdRng {
+    let pixel = ((y as u32 as u64) << 32) | x as u32 as u64;
+    StdRng::seed_from_u64(seed ^ pixel.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_float_equal;
+
+    #[test]
+    fn grid_samples_sit_at_cell_centers() {
+        let mut rng = pixel_rng(0, 0, 0);
+        let positions = sample_positions(SampleStrategy::Grid, 4, &mut rng);
+        assert_eq!(
+            positions,
+            vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+        );
+    }
+
+    #[test]
+    fn a_single_grid_sample_is_the_center() {
+        let mut rng = pixel_rng(0, 0, 0);
+        let positions = sample_positions(SampleStrategy::Grid, 1, &mut rng);
+        assert_eq!(positions, vec![(0.5, 0.5)]);
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells() {
+        let mut rng = pixel_rng(7, 3, 4);
+        let positions = sample_positions(SampleStrategy::Jittered, 9, &mut rng);
+        for (i, (x, y)) in positions.into_iter().enumerate() {
+            let (column, row) = ((i % 3) as f64, (i / 3) as f64);
+            assert!(x >= column / 3.0 && x < (column + 1.0) / 3.0);
+            assert!(y >= row / 3.0 && y < (row + 1.0) / 3.0);
+        }
+    }
+
+    #[test]
+    fn random_samples_are_in_the_unit_square() {
+        let mut rng = pixel_rng(7, 3, 4);
+        for (x, y) in sample_positions(SampleStrategy::Random, 16, &mut rng) {
+            assert!((0.0..1.0).contains(&x));
+            assert!((0.0..1.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn disk_samples_map_the_square_onto_the_unit_disk() {
+        assert_eq!(sample_disk((0.5, 0.5)), (0.0, 0.0));
+        let (x, y) = sample_disk((1.0, 0.5));
+        assert!(is_float_equal(x, 1.0) && is_float_equal(y, 0.0));
+        let (x, y) = sample_disk((0.5, 0.0));
+        assert!(is_float_equal(x, 0.0) && is_float_equal(y, -1.0));
+        let mut rng = pixel_rng(3, 1, 2);
+        for (u, v) in sample_positions(SampleStrategy::Random, 32, &mut rng) {
+            let (x, y) = sample_disk((u, v));
+            assert!(x * x + y * y <= 1.0 + 1e-12);
+        }
+    }
+
+    #[test]
+    fn cosine_samples_stay_in_the_normals_hemisphere() {
+        let normal = Tuple::vector(1.0, 2.0, -2.0).normalize();
+        let mut rng = pixel_rng(5, 0, 0);
+        let mut average = 0.0;
+        let positions = sample_positions(SampleStrategy::Jittered, 256, &mut rng);
+        for &position in &positions {
+            let direction = cosine_hemisphere(normal, position);
+            assert!(is_float_equal(direction.magnitude(), 1.0));
+            assert!(direction.dot(&normal) >= 0.0);
+            average += direction.dot(&normal) / positions.len() as f64;
+        }
+        // The cosine of cosine-weighted directions averages 2/3
+        assert!((average - 2.0 / 3.0).abs() < 0.01);
+        assert!(cosine_hemisphere(normal, (0.5, 0.5)).is_equal(&normal));
+    }
+
+    #[test]
+    fn sphere_samples_cover_both_hemispheres() {
+        let mut rng = pixel_rng(0, 0, 0);
+        let directions: Vec<Tuple> = (0..100)
+            .map(|_| uniform_sphere((rng.gen(), rng.gen())))
+            .collect();
+        assert!(directions
+            .iter()
+            .all(|direction| is_float_equal(direction.magnitude(), 1.0)));
+        assert!(directions.iter().any(|direction| direction.z > 0.5));
+        assert!(directions.iter().any(|direction| direction.z < -0.5));
+        assert_eq!(uniform_sphere((0.0, 0.0)), Tuple::vector(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn pixel_rngs_are_deterministic_per_seed_and_pixel() {
+        let positions =
+            |seed, x, y| sample_positions(SampleStrategy::Random, 4, &mut pixel_rng(seed, x, y));
+        assert_eq!(positions(1, 2, 3), positions(1, 2, 3));
+        assert_ne!(positions(1, 2, 3), positions(2, 2, 3));
+        assert_ne!(positions(1, 2, 3), positions(1, 3, 2));
+    }
+
+    #[test]
+    fn colors_differ_beyond_the_threshold() {
+        let adaptive = Adaptive {
+            threshold: 0.1,
+            max_subdivisions: 1,
+        };
+        let gray = Color::new(0.5, 0.5, 0.5);
+        assert!(!adaptive.differ(&gray, &Color::new(0.55, 0.45, 0.5)));
+        assert!(adaptive.differ(&gray, &Color::new(0.5, 0.5, 0.65)));
+    }
+
+    #[test]
+    fn box_filter_weighs_the_pixel_evenly() {
+        assert_eq!(Filter::Box.weight(0.0, 0.0), 1.0);
+        assert_eq!(Filter::Box.weight(0.4, -0.4), 1.0);
+        assert_eq!(Filter::Box.weight(0.6, 0.0), 0.0);
+    }
+
+    #[test]
+    fn tent_filter_falls_off_linearly() {
+        assert_eq!(Filter::Tent.weight(0.0, 0.0), 1.0);
+        assert_eq!(Filter::Tent.weight(0.5, 0.0), 0.5);
+        assert_eq!(Filter::Tent.weight(0.5, -0.5), 0.25);
+        assert_eq!(Filter::Tent.weight(1.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn gaussian_filter_is_symmetric() {
+        assert_eq!(Filter::Gaussian.weight(0.0, 0.0), 1.0);
+        assert!(is_float_equal(
+            Filter::Gaussian.weight(0.5, 0.0),
+            (-0.5_f64).exp()
+        ));
+        assert_eq!(
+            Filter::Gaussian.weight(0.3, -0.7),
+            Filter::Gaussian.weight(-0.7, 0.3)
+        );
+        assert_eq!(Filter::Gaussian.weight(1.6, 0.0), 0.0);
+    }
+
+    #[test]
+    fn mitchell_filter_has_negative_lobes() {
+        assert!(is_float_equal(
+            Filter::Mitchell.weight(0.0, 0.0),
+            64.0 / 81.0
+        ));
+        assert!(is_float_equal(mitchell(1.0), 1.0 / 18.0));
+        assert!(mitchell(1.5) < 0.0);
+        assert_eq!(mitchell(2.0), 0.0);
+    }
+}
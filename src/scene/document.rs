@@ -0,0 +1,374 @@
+//! Parses the YAML-like syntax of scene files into a tree of nodes.
+//! Only the small subset scene files need is supported: block lists (`- `),
+//! `key: value` maps, flow lists (`[ 1, 2, 3 ]`), scalars and `#` comments.
+
+use super::SceneError;
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Value {
+    Scalar(String),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+/// A value along with the line it started on, for error messages.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Node {
+    pub line: usize,
+    pub value: Value,
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+pub(crate) fn parse(source: &str) -> Result<Node, SceneError> {
+    let mut lines = Vec::new();
+    for (idx, raw) in source.lines().enumerate() {
+        let number = idx + 1;
+        let text = strip_comment(raw).trim_end();
+        if text.trim().is_empty() {
+            continue;
+        }
+        let indent = text.len() - text.trim_start_matches(' ').len();
+        if text[indent..].starts_with('\t') {
+            return Err(SceneError::new(
+                number,
+                "tabs can't be used for indentation",
+            ));
+        }
+        lines.push(Line {
+            number,
+            indent,
+            text: text[indent..].to_string(),
+        });
+    }
+
+    let mut parser = Parser { lines, pos: 0 };
+    let indent = match parser.peek() {
+        Some(line) => line.indent,
+        None => {
+            return Ok(Node {
+                line: 1,
+                value: Value::List(vec![]),
+            })
+        }
+    };
+    let document = parser.parse_block(indent)?;
+    match parser.peek() {
+        Some(line) => Err(SceneError::new(line.number, "unexpected indentation")),
+        None => Ok(document),
+    }
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Line> {
+        self.lines.get(self.pos)
+    }
+
+    fn parse_block(&mut self, indent: usize) -> Result<Node, SceneError> {
+        match self.peek() {
+            Some(line) if is_list_item(&line.text) => self.parse_list(indent),
+            _ => self.parse_map(indent),
+        }
+    }
+
+    fn parse_list(&mut self, indent: usize) -> Result<Node, SceneError> {
+        let start = self.lines[self.pos].number;
+        let mut items = Vec::new();
+
+        while let Some(line) = self.peek() {
+            if line.indent < indent || !is_list_item(&line.text) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(SceneError::new(line.number, "unexpected indentation"));
+            }
+
+            let number = line.number;
+            let rest = line.text[1..].trim_start().to_string();
+            let offset = line.text.len() - rest.len();
+
+            let item = if rest.is_empty() {
+                self.pos += 1;
+                match self.peek() {
+                    Some(next) if next.indent > indent => {
+                        let nested = next.indent;
+                        self.parse_block(nested)?
+                    }
+                    _ => return Err(SceneError::new(number, "list item has no value")),
+                }
+            } else if split_entry(&rest).is_some() {
+                // "- key: value" starts a map indented to where its key begins
+                let line = &mut self.lines[self.pos];
+                line.indent = indent + offset;
+                line.text = rest;
+                self.parse_map(indent + offset)?
+            } else {
+                self.pos += 1;
+                parse_inline(&rest, number)?
+            };
+            items.push(item);
+        }
+
+        Ok(Node {
+            line: start,
+            value: Value::List(items),
+        })
+    }
+
+    fn parse_map(&mut self, indent: usize) -> Result<Node, SceneError> {
+        let start = self.lines[self.pos].number;
+        let mut entries: Vec<(String, Node)> = Vec::new();
+
+        while let Some(line) = self.peek() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return Err(SceneError::new(line.number, "unexpected indentation"));
+            }
+
+            let number = line.number;
+            let entry = if is_list_item(&line.text) {
+                None
+            } else {
+                split_entry(&line.text)
+            };
+            let (key, rest) = match entry {
+                Some((key, rest)) => (key.to_string(), rest.to_string()),
+                None => {
+                    return Err(SceneError::new(number, "expected a `key: value` entry"));
+                }
+            };
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(SceneError::new(number, format!("duplicate key `{}`", key)));
+            }
+            self.pos += 1;
+
+            let value = if !rest.is_empty() {
+                parse_inline(&rest, number)?
+            } else {
+                match self.peek() {
+                    Some(next) if next.indent > indent => {
+                        let nested = next.indent;
+                        self.parse_block(nested)?
+                    }
+                    // Lists are allowed to line up with their key
+                    Some(next) if next.indent == indent && is_list_item(&next.text) => {
+                        self.parse_list(indent)?
+                    }
+                    _ => return Err(SceneError::new(number, format!("`{}` has no value", key))),
+                }
+            };
+            entries.push((key, value));
+        }
+
+        Ok(Node {
+            line: start,
+            value: Value::Map(entries),
+        })
+    }
+}
+
+/// A `#` starts a comment at the start of a line or after whitespace, so
+/// paths like `file#2.ppm` stay whole.
+fn strip_comment(line: &str) -> &str {
+    let mut after_space = true;
+    for (idx, c) in line.char_indices() {
+        if c == '#' && after_space {
+            return &line[..idx];
+        }
+        after_space = c.is_whitespace();
+    }
+    line
+}
+
+fn is_list_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// Splits "key: value" into its key and (possibly empty) value.
+fn split_entry(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with('[') || text.starts_with('"') {
+        return None;
+    }
+    let idx = text.find(':')?;
+    let rest = &text[idx + 1..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    let key = text[..idx].trim();
+    if key.is_empty() {
+        return None;
+    }
+    Some((key, rest.trim()))
+}
+
+fn parse_inline(text: &str, line: usize) -> Result<Node, SceneError> {
+    if !text.starts_with('[') {
+        return Ok(scalar(text, line));
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut pos = 0;
+    let node = parse_flow_list(&chars, &mut pos, line)?;
+    if chars[pos..].iter().any(|c| !c.is_whitespace()) {
+        return Err(SceneError::new(line, "unexpected text after `]`"));
+    }
+    Ok(node)
+}
+
+// Parses "[ a, [ b, c ], d ]" starting at the opening bracket.
+fn parse_flow_list(chars: &[char], pos: &mut usize, line: usize) -> Result<Node, SceneError> {
+    *pos += 1; // the opening bracket
+    let mut items = Vec::new();
+
+    loop {
+        skip_whitespace(chars, pos);
+        match chars.get(*pos) {
+            None => return Err(SceneError::new(line, "unclosed `[`")),
+            Some(']') if items.is_empty() => {
+                *pos += 1;
+                break;
+            }
+            Some('[') => items.push(parse_flow_list(chars, pos, line)?),
+            Some(_) => {
+                let start = *pos;
+                while *pos < chars.len() && chars[*pos] != ',' && chars[*pos] != ']' {
+                    *pos += 1;
+                }
+                let text: String = chars[start..*pos].iter().collect();
+                if text.trim().is_empty() {
+                    return Err(SceneError::new(line, "empty value in list"));
+                }
+                items.push(scalar(&text, line));
+            }
+        }
+
+        skip_whitespace(chars, pos);
+        match chars.get(*pos) {
+            Some(',') => *pos += 1,
+            Some(']') => {
+                *pos += 1;
+                break;
+            }
+            _ => return Err(SceneError::new(line, "unclosed `[`")),
+        }
+    }
+
+    Ok(Node {
+        line,
+        value: Value::List(items),
+    })
+}
+
+fn skip_whitespace(chars: &[char], pos: &mut usize) {
+    while *pos < chars.len() && chars[*pos].is_whitespace() {
+        *pos += 1;
+    }
+}
+
+fn scalar(text: &str, line: usize) -> Node {
+    let text = text.trim();
+    let unquoted = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text);
+    Node {
+        line,
+        value: Value::Scalar(unquoted.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indentation_errors_report_the_line() {
+        let error = parse("- add: sphere\n      material: red\n").unwrap_err();
+        assert_eq!(error, SceneError::new(2, "unexpected indentation"));
+    }
+
+    #[test]
+    fn unclosed_flow_lists_are_errors() {
+        let error = parse("- at: [ 1, 2\n").unwrap_err();
+        assert_eq!(error, SceneError::new(1, "unclosed `[`"));
+    }
+
+    #[test]
+    fn keys_without_values_are_errors() {
+        let error = parse("- add: camera\n  width:\n").unwrap_err();
+        assert_eq!(error, SceneError::new(2, "`width` has no value"));
+    }
+
+    #[test]
+    fn lists_can_line_up_with_their_key() {
+        let document = parse("transform:\n- [ scale, 2 ]\n").unwrap();
+        let Value::Map(entries) = document.value else {
+            panic!("expected a map");
+        };
+        let Value::List(items) = &entries[0].1.value else {
+            panic!("expected a list");
+        };
+        assert_eq!(items.len(), 1);
+    }
+
+    #[test]
+    fn hashes_inside_values_are_not_comments() {
+        let document = parse("name: file#2.ppm # the second\n#whole line\n").unwrap();
+        let expected = Node {
+            line: 1,
+            value: Value::Map(vec![("name".to_string(), scalar("file#2.ppm", 1))]),
+        };
+        assert_eq!(document, expected);
+    }
+
+    #[test]
+    fn parsing_nested_lists_and_maps() {
+        let source = "\
+# a comment
+- add: sphere   # trailing comment
+  transform:
+    - [ scale, 0.5, 0.5, 0.5 ]
+    - standard
+";
+        let document = parse(source).unwrap();
+        let expected = Node {
+            line: 2,
+            value: Value::List(vec![Node {
+                line: 2,
+                value: Value::Map(vec![
+                    ("add".to_string(), scalar("sphere", 2)),
+                    (
+                        "transform".to_string(),
+                        Node {
+                            line: 4,
+                            value: Value::List(vec![
+                                Node {
+                                    line: 4,
+                                    value: Value::List(vec![
+                                        scalar("scale", 4),
+                                        scalar("0.5", 4),
+                                        scalar("0.5", 4),
+                                        scalar("0.5", 4),
+                                    ]),
+                                },
+                                scalar("standard", 5),
+                            ]),
+                        },
+                    ),
+                ]),
+            }]),
+        };
+        assert_eq!(document, expected);
+    }
+}
@@ -0,0 +1,1621 @@
+//! Scenes are described in a YAML-like text format: a list of entries that
+//! either `add` something to the scene or `define` a reusable material or
+//! transform.
+//!
+//! ```text
+//! - add: camera
+//!   width: 100
+//!   height: 50
+//!   field-of-view: 1.047
+//!   from: [ 0, 1.5, -5 ]
+//!   to: [ 0, 1, 0 ]
+//!   up: [ 0, 1, 0 ]
+//!
+//! - add: light
+//!   at: [ -10, 10, -10 ]
+//!   intensity: [ 1, 1, 1 ]
+//!
+//! - define: red
+//!   value:
+//!     color: [ 1, 0.2, 0.2 ]
+//!     specular: 0.3
+//!
+//! - define: shiny-red
+//!   extend: red
+//!   value:
+//!     shininess: 300
+//!
+//! - add: group
+//!   transform:
+//!     - [ translate, 0, 1, 0 ]
+//!   children:
+//!     - add: sphere
+//!       material: shiny-red
+//!       transform:
+//!         - [ scale, 0.5, 0.5, 0.5 ]
+//!         - [ rotate-y, 0.5 ]
+//!     - add: plane
+//!       material:
+//!         color: [ 0.5, 0.5, 0.5 ]
+//! ```
+//!
+//! Materials are Phong shaded unless their `shading` says otherwise:
+//! `blinn-phong`, `lambert` (no highlight), `oren-nayar` (rough matte, with
+//! the facets' `roughness` in radians), `unlit` (just the `color`) or
+//! `microfacet`. A material that sets `metallic` or `roughness` is shaded
+//! physically based, with `microfacet`, taking its `color` as the base
+//! color. An `emission` makes it glow, and glowing spheres and triangles
+//! light the scene like area lights.
+//!
+//! A camera may also have an `aperture`, the diameter of its lens, to blur
+//! everything but what is `focal-distance` away.
+//!
+//! A material's `bump` tilts its normals by a height field, given by its
+//! `pattern` (`noise` or `waves`), `frequency` and `scale`. A `normal-map`
+//! instead takes them from a PPM image, relative to the scene file.
+//!
+//! A `background` is what rays that miss everything see: a `color`, a
+//! gradient from `bottom` (straight down) to `top` (straight up), or an
+//! equirectangular PPM or HDR `image`. With `lighting: true` it also lights
+//! the scene when path tracing.
+//!
+//! `fog` fades everything toward its `color` with distance, either
+//! exponentially by its `density` or linearly from `start` to `end`.
+//!
+//! A `triangle` is given by its corners `p1`, `p2` and `p3`.
+//!
+//! A `volume` fills its `boundary`, a single closed and convex shape like a
+//! sphere, with smoke of the given `density` that scatters light evenly in
+//! every direction. Its material's `color` is the smoke's color.
+//!
+//! Moving shapes blur while the camera's shutter is open, from
+//! `shutter-open` to `shutter-close`. A shape's `motion` says where it ends
+//! up at time 1, relative to where it is at time 0: it can `scale`, `rotate`
+//! (by the given angles around x, then y, then z) and `translate`.
+//!
+//! A `light` is a point light. It keeps its brightness at any distance
+//! unless given an `attenuation`: either `inverse-square` or the `[ constant,
+//! linear, quadratic ]` coefficients of the falloff.
+//!
+//! Besides point lights, `area-light` adds a rectangle of light with soft
+//! shadows, given by a `corner`, two edges `uvec` and `vvec` split into
+//! `usteps` and `vsteps` cells, an `intensity` and an optional `jitter`.
+//! A `directional-light` shines along its `direction` from infinitely far
+//! away, and a `spot-light` shines from `at` along `direction` in a cone that
+//! fades from `inner-angle` to `outer-angle` (in radians, below pi).
+//!
+//! Transforms are applied in the order they are listed. The available
+//! operations are `translate`, `scale`, `rotate-x`, `rotate-y`, `rotate-z` and
+//! `shear`, and a defined transform can be used by name. A group's transform
+//! and material apply to all of its children.
+
+mod document;
+
+use crate::background::{Background, EnvironmentMap};
+use crate::bump::{HeightField, NormalMap};
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::error::Error;
+use crate::fog::{Fog, FogFalloff};
+use crate::lights::{AreaLight, Attenuation, DirectionalLight, PointLight, SpotLight};
+use crate::matrix::Matrix;
+use crate::microfacet::Microfacet;
+use crate::rays::{Material, ShadingModel};
+use crate::shapes::{Keyframe, Motion, Object, Plane, Quaternion, Sphere, Triangle, Volume};
+use crate::tuple::{Color, Tuple};
+use crate::world::World;
+use document::{Node, Value};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SceneError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl SceneError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+/// Reads and parses the scene file at `path`.
+pub fn load(path: impl AsRef<Path>) -> crate::Result<Scene> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    Ok(parse_in(&source, base)?)
+}
+
+/// Parses a scene, reading any images it uses relative to the current
+/// directory.
+pub fn parse(source: &str) -> Result<Scene, SceneError> {
+    parse_in(source, Path::new(""))
+}
+
+fn parse_in(source: &str, base: &Path) -> Result<Scene, SceneError> {
+    let document = document::parse(source)?;
+    let mut builder = Builder {
+        base: base.to_path_buf(),
+        ..Builder::default()
+    };
+    for entry in document.as_list("a list of scene entries")? {
+        builder.add_entry(entry)?;
+    }
+
+    match builder.camera {
+        Some(camera) => Ok(Scene {
+            camera,
+            world: builder.world,
+        }),
+        None => Err(SceneError::new(
+            source.lines().count().max(1),
+            "scene has no camera",
+        )),
+    }
+}
+
+enum Definition {
+    Material(Material),
+    Transform(Matrix),
+}
+
+#[derive(Default)]
+struct Builder {
+    camera: Option<Camera>,
+    world: World,
+    definitions: HashMap<String, Definition>,
+    /// Where image paths are relative to.
+    base: PathBuf,
+    /// Images already read, so materials sharing one share it in memory too.
+    images: HashMap<PathBuf, Arc<Canvas>>,
+}
+
+impl Builder {
+    fn add_entry(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = entry.as_map("a scene entry")?;
+        if let Some(name) = fields.iter().find(|(key, _)| key == "define") {
+            return self.define(entry, name.1.as_str()?);
+        }
+
+        let kind = Fields::unchecked(entry, "entry")?.require("add")?;
+        match kind.as_str()? {
+            "camera" => self.add_camera(entry),
+            "light" => self.add_light(entry),
+            "area-light" => self.add_area_light(entry),
+            "directional-light" => self.add_directional_light(entry),
+            "spot-light" => self.add_spot_light(entry),
+            "background" => self.add_background(entry),
+            "fog" => self.add_fog(entry),
+            _ => self.add_shape(entry, &Matrix::identity(), &Material::new()),
+        }
+    }
+
+    fn add_camera(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = Fields::new(
+            entry,
+            "camera",
+            &[
+                "add",
+                "width",
+                "height",
+                "field-of-view",
+                "from",
+                "to",
+                "up",
+                "aperture",
+                "focal-distance",
+                "shutter-open",
+                "shutter-close",
+            ],
+        )?;
+        if self.camera.is_some() {
+            return Err(SceneError::new(entry.line, "scene already has a camera"));
+        }
+
+        let mut camera = Camera::new(
+            fields.require("width")?.as_size()?,
+            fields.require("height")?.as_size()?,
+            fields.require("field-of-view")?.as_f64()?,
+        );
+        let [x, y, z] = fields.require("from")?.as_triple()?;
+        let from = Tuple::point(x, y, z);
+        let [x, y, z] = fields.require("to")?.as_triple()?;
+        let to = Tuple::point(x, y, z);
+        let [x, y, z] = fields.require("up")?.as_triple()?;
+        let up = Tuple::vector(x, y, z);
+        camera
+            .set_transform(Matrix::view_transform(from, to, up))
+            .map_err(|_| SceneError::new(entry.line, "camera can't look along its `up` vector"))?;
+        if let Some(node) = fields.get("aperture") {
+            camera.aperture = node.as_f64()?;
+            if camera.aperture < 0.0 {
+                return Err(SceneError::new(node.line, "`aperture` can't be negative"));
+            }
+        }
+        if let Some(node) = fields.get("focal-distance") {
+            camera.focal_distance = node.as_f64()?;
+            if camera.focal_distance <= 0.0 {
+                return Err(SceneError::new(
+                    node.line,
+                    "`focal-distance` must be positive",
+                ));
+            }
+        }
+        if let Some(node) = fields.get("shutter-open") {
+            camera.shutter_open = node.as_f64()?;
+        }
+        if let Some(node) = fields.get("shutter-close") {
+            camera.shutter_close = node.as_f64()?;
+        }
+        if camera.shutter_close < camera.shutter_open {
+            let node = fields.get("shutter-close").or(fields.get("shutter-open"));
+            return Err(SceneError::new(
+                node.map_or(entry.line, |node| node.line),
+                "`shutter-close` can't come before `shutter-open`",
+            ));
+        }
+        self.camera = Some(camera);
+        Ok(())
+    }
+
+    fn add_background(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = Fields::new(
+            entry,
+            "background",
+            &["add", "color", "bottom", "top", "image", "lighting"],
+        )?;
+        let color = |key| -> Result<Option<Color>, SceneError> {
+            match fields.get(key) {
+                Some(node) => {
+                    let [red, green, blue] = node.as_triple()?;
+                    Ok(Some(Color::new(red, green, blue)))
+                }
+                None => Ok(None),
+            }
+        };
+        let image = fields.get("image");
+        let background = match (color("color")?, color("bottom")?, color("top")?, image) {
+            (Some(color), None, None, None) => Background::Color(color),
+            (None, Some(bottom), Some(top), None) => Background::Gradient { bottom, top },
+            (None, None, None, Some(node)) => {
+                Background::Environment(EnvironmentMap(self.image(node)?))
+            }
+            _ => {
+                return Err(SceneError::new(
+                    entry.line,
+                    "background needs either a `color`, a `bottom` and `top`, or an `image`",
+                ))
+            }
+        };
+        self.world.background = background;
+        if let Some(node) = fields.get("lighting") {
+            self.world.image_based_lighting = node.as_bool()?;
+        }
+        Ok(())
+    }
+
+    fn add_fog(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = Fields::new(entry, "fog", &["add", "color", "density", "start", "end"])?;
+        let [red, green, blue] = fields.require("color")?.as_triple()?;
+        let falloff = match (
+            fields.get("density"),
+            fields.get("start"),
+            fields.get("end"),
+        ) {
+            (Some(node), None, None) => {
+                let density = node.as_f64()?;
+                if density < 0.0 {
+                    return Err(SceneError::new(node.line, "`density` can't be negative"));
+                }
+                FogFalloff::Exponential { density }
+            }
+            (None, Some(start), Some(node)) => {
+                let (start, end) = (start.as_f64()?, node.as_f64()?);
+                if end <= start {
+                    return Err(SceneError::new(node.line, "`end` must come after `start`"));
+                }
+                FogFalloff::Linear { start, end }
+            }
+            _ => {
+                return Err(SceneError::new(
+                    entry.line,
+                    "fog needs either a `density`, or a `start` and `end`",
+                ))
+            }
+        };
+        self.world.fog = Some(Fog::new(Color::new(red, green, blue), falloff));
+        Ok(())
+    }
+
+    fn add_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = Fields::new(entry, "light", &["add", "at", "intensity", "attenuation"])?;
+        let [x, y, z] = fields.require("at")?.as_triple()?;
+        let [red, green, blue] = fields.require("intensity")?.as_triple()?;
+        let mut light = PointLight::new(Tuple::point(x, y, z), Color::new(red, green, blue));
+        if let Some(node) = fields.get("attenuation") {
+            light.attenuation = node.as_attenuation()?;
+        }
+        self.world.lights.push(Box::new(light));
+        Ok(())
+    }
+
+    fn add_directional_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = Fields::new(
+            entry,
+            "directional light",
+            &["add", "direction", "intensity"],
+        )?;
+        let [x, y, z] = fields.require("direction")?.as_triple()?;
+        let [red, green, blue] = fields.require("intensity")?.as_triple()?;
+        self.world.lights.push(Box::new(DirectionalLight::new(
+            Tuple::vector(x, y, z),
+            Color::new(red, green, blue),
+        )));
+        Ok(())
+    }
+
+    fn add_spot_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = Fields::new(
+            entry,
+            "spot light",
+            &[
+                "add",
+                "at",
+                "direction",
+                "inner-angle",
+                "outer-angle",
+                "intensity",
+            ],
+        )?;
+        let [x, y, z] = fields.require("at")?.as_triple()?;
+        let position = Tuple::point(x, y, z);
+        let [x, y, z] = fields.require("direction")?.as_triple()?;
+        let direction = Tuple::vector(x, y, z);
+        let [red, green, blue] = fields.require("intensity")?.as_triple()?;
+        let angle = |key: &str| -> Result<(f64, usize), SceneError> {
+            let node = fields.require(key)?;
+            let angle = node.as_f64()?;
+            if !(angle > 0.0 && angle < PI) {
+                return Err(SceneError::new(
+                    node.line,
+                    format!("`{}` must be between 0 and pi", key),
+                ));
+            }
+            Ok((angle, node.line))
+        };
+        let (inner_angle, line) = angle("inner-angle")?;
+        let (outer_angle, _) = angle("outer-angle")?;
+        if inner_angle > outer_angle {
+            return Err(SceneError::new(
+                line,
+                "`inner-angle` can't be wider than `outer-angle`",
+            ));
+        }
+        self.world.lights.push(Box::new(SpotLight::new(
+            position,
+            direction,
+            inner_angle,
+            outer_angle,
+            Color::new(red, green, blue),
+        )));
+        Ok(())
+    }
+
+    fn add_area_light(&mut self, entry: &Node) -> Result<(), SceneError> {
+        let fields = Fields::new(
+            entry,
+            "area light",
+            &[
+                "add",
+                "corner",
+                "uvec",
+                "usteps",
+                "vvec",
+                "vsteps",
+                "intensity",
+                "jitter",
+            ],
+        )?;
+        let [x, y, z] = fields.require("corner")?.as_triple()?;
+        let corner = Tuple::point(x, y, z);
+        let [x, y, z] = fields.require("uvec")?.as_triple()?;
+        let uvec = Tuple::vector(x, y, z);
+        let [x, y, z] = fields.require("vvec")?.as_triple()?;
+        let vvec = Tuple::vector(x, y, z);
+        let [red, green, blue] = fields.require("intensity")?.as_triple()?;
+        let mut light = AreaLight::new(
+            corner,
+            uvec,
+            fields.require("usteps")?.as_size()? as usize,
+            vvec,
+            fields.require("vsteps")?.as_size()? as usize,
+            Color::new(red, green, blue),
+        );
+        if let Some(node) = fields.get("jitter") {
+            light.jitter = node.as_bool()?;
+        }
+        self.world.lights.push(Box::new(light));
+        Ok(())
+    }
+
+    // Groups are flattened: their transform and material are pushed down
+    // onto each child as it is added to the world.
+    fn add_shape(
+        &mut self,
+        entry: &Node,
+        parent_transform: &Matrix,
+        parent_material: &Material,
+    ) -> Result<(), SceneError> {
+        let kind_node = Fields::unchecked(entry, "entry")?.require("add")?;
+        let kind = kind_node.as_str()?;
+        let allowed: &[&str] = match kind {
+            "group" => &["add", "transform", "material", "children"],
+            "sphere" | "plane" => &["add", "transform", "material", "motion"],
+            "triangle" => &["add", "p1", "p2", "p3", "transform", "material", "motion"],
+            "volume" => &["add", "boundary", "density", "transform", "material"],
+            "camera" | "light" | "area-light" | "directional-light" | "spot-light"
+            | "background" | "fog" => {
+                return Err(SceneError::new(
+                    kind_node.line,
+                    format!("a {} can't be part of a group", kind),
+                ))
+            }
+            _ => {
+                return Err(SceneError::new(
+                    kind_node.line,
+                    format!("unknown entry `{}`", kind),
+                ))
+            }
+        };
+        let fields = Fields::new(entry, kind, allowed)?;
+
+        let local = match fields.get("transform") {
+            Some(node) => self.transform(Matrix::identity(), node)?,
+            None => Matrix::identity(),
+        };
+        let transform = parent_transform * &local;
+        let material = match fields.get("material") {
+            Some(node) => self.material(parent_material.clone(), node)?,
+            None => parent_material.clone(),
+        };
+
+        if kind == "group" {
+            for child in fields.require("children")?.as_list("a list of shapes")? {
+                self.add_shape(child, &transform, &material)?;
+            }
+            return Ok(());
+        }
+
+        // The boundary is added like any shape, then taken back to wrap
+        if kind == "volume" {
+            let node = fields.require("density")?;
+            let density = node.as_f64()?;
+            if density <= 0.0 {
+                return Err(SceneError::new(node.line, "`density` must be positive"));
+            }
+            let boundary = fields.require("boundary")?;
+            let count = self.world.objects.len();
+            self.add_shape(boundary, &transform, &Material::new())?;
+            if self.world.objects.len() != count + 1 {
+                self.world.objects.truncate(count);
+                return Err(SceneError::new(
+                    boundary.line,
+                    "a volume's boundary must be a single shape",
+                ));
+            }
+            let boundary = self.world.objects.pop().unwrap();
+            let mut volume = Volume::new(boundary, density);
+            volume.material = material.shading_model(ShadingModel::Isotropic);
+            self.world.objects.push(Box::new(volume));
+            return Ok(());
+        }
+
+        let motion = match fields.get("motion") {
+            Some(node) => Some(motion(node)?),
+            None => None,
+        };
+        let not_invertible = |_| {
+            let line = fields.get("transform").map_or(entry.line, |node| node.line);
+            SceneError::new(line, "transform is not invertible")
+        };
+        let object: Box<dyn Object> = match kind {
+            "sphere" => {
+                let mut sphere = Sphere::try_with_transform(transform).map_err(not_invertible)?;
+                sphere.material = material;
+                if let Some(motion) = motion {
+                    sphere.set_motion(motion);
+                }
+                Box::new(sphere)
+            }
+            "triangle" => {
+                let point = |key| -> Result<Tuple, SceneError> {
+                    let [x, y, z] = fields.require(key)?.as_triple()?;
+                    Ok(Tuple::point(x, y, z))
+                };
+                let mut triangle = Triangle::try_with_transform(
+                    point("p1")?,
+                    point("p2")?,
+                    point("p3")?,
+                    transform,
+                )
+                .map_err(not_invertible)?;
+                triangle.material = material;
+                if let Some(motion) = motion {
+                    triangle.set_motion(motion);
+                }
+                Box::new(triangle)
+            }
+            _ => {
+                let mut plane = Plane::try_with_transform(transform).map_err(not_invertible)?;
+                plane.material = material;
+                if let Some(motion) = motion {
+                    plane.set_motion(motion);
+                }
+                Box::new(plane)
+            }
+        };
+        self.world.objects.push(object);
+        Ok(())
+    }
+
+    fn define(&mut self, entry: &Node, name: &str) -> Result<(), SceneError> {
+        let fields = Fields::new(entry, "definition", &["define", "extend", "value"])?;
+        let value = fields.require("value")?;
+        let extend = fields.get("extend");
+
+        let definition = match &value.value {
+            Value::Map(_) => {
+                let base = match extend {
+                    Some(node) => self.defined_material(node)?,
+                    None => Material::new(),
+                };
+                Definition::Material(self.material(base, value)?)
+            }
+            Value::List(_) => {
+                let base = match extend {
+                    Some(node) => self.defined_transform(node)?,
+                    None => Matrix::identity(),
+                };
+                Definition::Transform(self.transform(base, value)?)
+            }
+            Value::Scalar(_) => {
+                return Err(SceneError::new(
+                    value.line,
+                    "expected a material or a list of transforms",
+                ))
+            }
+        };
+        self.definitions.insert(name.to_string(), definition);
+        Ok(())
+    }
+
+    fn defined_material(&self, name: &Node) -> Result<Material, SceneError> {
+        match self.definitions.get(name.as_str()?) {
+            Some(Definition::Material(material)) => Ok(material.clone()),
+            _ => Err(SceneError::new(
+                name.line,
+                format!("undefined material `{}`", name.as_str()?),
+            )),
+        }
+    }
+
+    fn defined_transform(&self, name: &Node) -> Result<Matrix, SceneError> {
+        match self.definitions.get(name.as_str()?) {
+            Some(Definition::Transform(transform)) => Ok(transform.clone()),
+            _ => Err(SceneError::new(
+                name.line,
+                format!("undefined transform `{}`", name.as_str()?),
+            )),
+        }
+    }
+
+    /// Either the name of a defined material, or fields to set on `base`.
+    fn material(&mut self, base: Material, node: &Node) -> Result<Material, SceneError> {
+        if let Value::Scalar(_) = node.value {
+            return self.defined_material(node);
+        }
+
+        let fields = Fields::new(
+            node,
+            "material",
+            &[
+                "color",
+                "ambient",
+                "diffuse",
+                "specular",
+                "shininess",
+                "reflective",
+                "emission",
+                "bump",
+                "normal-map",
+                "shading",
+                "metallic",
+                "roughness",
+            ],
+        )?;
+        let mut material = base;
+        if let Some(node) = fields.get("color") {
+            let [red, green, blue] = node.as_triple()?;
+            material = material.color(Color::new(red, green, blue));
+        }
+        if let Some(node) = fields.get("ambient") {
+            material = material.ambient(node.as_f64()?);
+        }
+        if let Some(node) = fields.get("diffuse") {
+            material = material.diffuse(node.as_f64()?);
+        }
+        if let Some(node) = fields.get("specular") {
+            material = material.specular(node.as_f64()?);
+        }
+        if let Some(node) = fields.get("shininess") {
+            material = material.shininess(node.as_f64()?);
+        }
+        if let Some(node) = fields.get("reflective") {
+            material = material.reflective(node.as_f64()?);
+        }
+        if let Some(node) = fields.get("emission") {
+            let [red, green, blue] = node.as_triple()?;
+            material = material.emission(Color::new(red, green, blue));
+        }
+        if let Some(node) = fields.get("bump") {
+            let (field, scale) = bump(node)?;
+            material = material.bump(field, scale);
+        }
+        if let Some(node) = fields.get("normal-map") {
+            material = material.normal_map(NormalMap(self.image(node)?));
+        }
+        let mut shading = match fields.get("shading") {
+            Some(node) => node.as_shading()?,
+            None => material.shading,
+        };
+        let (metallic, roughness) = (fields.get("metallic"), fields.get("roughness"));
+        if let ShadingModel::OrenNayar { roughness: sigma } = &mut shading {
+            if let Some(node) = roughness {
+                *sigma = node.as_f64()?;
+            }
+        } else if metallic.is_some() || roughness.is_some() {
+            let base = match shading {
+                ShadingModel::Microfacet(microfacet) => microfacet,
+                _ => Microfacet::default(),
+            };
+            shading = ShadingModel::Microfacet(Microfacet::new(
+                metallic.map_or(Ok(base.metallic), Node::as_f64)?,
+                roughness.map_or(Ok(base.roughness), Node::as_f64)?,
+            ));
+        }
+        Ok(material.shading_model(shading))
+    }
+
+    /// The PPM image at the path in `node`.
+    fn image(&mut self, node: &Node) -> Result<Arc<Canvas>, SceneError> {
+        let path = self.base.join(node.as_str()?);
+        if let Some(image) = self.images.get(&path) {
+            return Ok(image.clone());
+        }
+        let image = crate::load_image(&path)
+            .map_err(|err| SceneError::new(node.line, format!("can't read image: {}", err)))?;
+        let image = Arc::new(image);
+        self.images.insert(path, image.clone());
+        Ok(image)
+    }
+
+    /// Applies each listed transform to `base`, in order.
+    fn transform(&self, base: Matrix, node: &Node) -> Result<Matrix, SceneError> {
+        let mut matrix = base;
+        for step in node.as_list("a list of transforms")? {
+            if let Value::Scalar(_) = step.value {
+                matrix = &self.defined_transform(step)? * &matrix;
+                continue;
+            }
+
+            let parts = step.as_list("a transform such as `[ translate, 1, 2, 3 ]`")?;
+            let Some((operation, args)) = parts.split_first() else {
+                return Err(SceneError::new(step.line, "empty transform"));
+            };
+            let operation = operation.as_str()?;
+            let args = args
+                .iter()
+                .map(Node::as_f64)
+                .collect::<Result<Vec<f64>, SceneError>>()?;
+            let expected = match operation {
+                "translate" | "scale" => 3,
+                "rotate-x" | "rotate-y" | "rotate-z" => 1,
+                "shear" => 6,
+                _ => {
+                    return Err(SceneError::new(
+                        step.line,
+                        format!("unknown transform `{}`", operation),
+                    ))
+                }
+            };
+            if args.len() != expected {
+                return Err(SceneError::new(
+                    step.line,
+                    format!(
+                        "`{}` takes {} values, got {}",
+                        operation,
+                        expected,
+                        args.len()
+                    ),
+                ));
+            }
+
+            matrix = match operation {
+                "translate" => matrix.translate(args[0], args[1], args[2]),
+                "scale" => matrix.scale(args[0], args[1], args[2]),
+                "rotate-x" => matrix.rotate_x(args[0]),
+                "rotate-y" => matrix.rotate_y(args[0]),
+                "rotate-z" => matrix.rotate_z(args[0]),
+                _ => matrix.shear(args[0], args[1], args[2], args[3], args[4], args[5]),
+            };
+        }
+        Ok(matrix)
+    }
+}
+
+/// Motion from where a shape starts to the keyframe described by `node`.
+fn motion(node: &Node) -> Result<Motion, SceneError> {
+    let fields = Fields::new(node, "motion", &["scale", "rotate", "translate"])?;
+    let mut end = Keyframe::identity();
+    if let Some(node) = fields.get("scale") {
+        let [x, y, z] = node.as_triple()?;
+        // Shrinking to nothing on the way leaves no inverse to trace rays by
+        if x <= 0.0 || y <= 0.0 || z <= 0.0 {
+            return Err(SceneError::new(
+                node.line,
+                "motion can't scale to zero or below",
+            ));
+        }
+        end.scale = Tuple::vector(x, y, z);
+    }
+    if let Some(node) = fields.get("rotate") {
+        let [x, y, z] = node.as_triple()?;
+        let around = |axis: Tuple, radians| Quaternion::from_axis_angle(axis, radians);
+        end.rotation = around(Tuple::vector(0.0, 0.0, 1.0), z)
+            .then(&around(Tuple::vector(0.0, 1.0, 0.0), y))
+            .then(&around(Tuple::vector(1.0, 0.0, 0.0), x));
+    }
+    if let Some(node) = fields.get("translate") {
+        let [x, y, z] = node.as_triple()?;
+        end.translation = Tuple::vector(x, y, z);
+    }
+    Ok(Motion::new(Keyframe::identity(), end))
+}
+
+/// A height field and how strongly it bumps the surface.
+fn bump(node: &Node) -> Result<(HeightField, f64), SceneError> {
+    let fields = Fields::new(node, "bump", &["pattern", "frequency", "scale"])?;
+    let frequency = fields.get("frequency").map_or(Ok(1.0), Node::as_f64)?;
+    let pattern = fields.require("pattern")?;
+    let field = match pattern.as_str()? {
+        "noise" => HeightField::Noise { frequency },
+        "waves" => HeightField::Waves { frequency },
+        name => {
+            return Err(SceneError::new(
+                pattern.line,
+                format!("unknown bump pattern `{}`", name),
+            ))
+        }
+    };
+    let scale = fields.get("scale").map_or(Ok(1.0), Node::as_f64)?;
+    Ok((field, scale))
+}
+
+/// The entries of a map, checked against the keys its kind allows.
+struct Fields<'a> {
+    node: &'a Node,
+    kind: &'a str,
+    entries: &'a [(String, Node)],
+}
+
+impl<'a> Fields<'a> {
+    fn new(node: &'a Node, kind: &'a str, allowed: &[&str]) -> Result<Self, SceneError> {
+        let fields = Self::unchecked(node, kind)?;
+        for (key, value) in fields.entries {
+            if !allowed.contains(&key.as_str()) {
+                return Err(SceneError::new(
+                    value.line,
+                    format!("unknown key `{}` for {}", key, kind),
+                ));
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Any keys at all, for a first look at an entry before its kind, and
+    /// so the keys it allows, is known.
+    fn unchecked(node: &'a Node, kind: &'a str) -> Result<Self, SceneError> {
+        Ok(Self {
+            node,
+            kind,
+            entries: node.as_map(kind)?,
+        })
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Node> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, node)| node)
+    }
+
+    fn require(&self, key: &str) -> Result<&'a Node, SceneError> {
+        self.get(key).ok_or_else(|| {
+            SceneError::new(
+                self.node.line,
+                format!("{} is missing `{}`", self.kind, key),
+            )
+        })
+    }
+}
+
+impl Node {
+    fn as_map(&self, what: &str) -> Result<&[(String, Node)], SceneError> {
+        match &self.value {
+            Value::Map(entries) => Ok(entries),
+            _ => Err(SceneError::new(self.line, format!("expected {}", what))),
+        }
+    }
+
+    fn as_list(&self, what: &str) -> Result<&[Node], SceneError> {
+        match &self.value {
+            Value::List(items) => Ok(items),
+            _ => Err(SceneError::new(self.line, format!("expected {}", what))),
+        }
+    }
+
+    fn as_str(&self) -> Result<&str, SceneError> {
+        match &self.value {
+            Value::Scalar(text) => Ok(text),
+            _ => Err(SceneError::new(self.line, "expected a name")),
+        }
+    }
+
+    fn as_f64(&self) -> Result<f64, SceneError> {
+        let text = self.as_str()?;
+        text.parse()
+            .map_err(|_| SceneError::new(self.line, format!("expected a number, got `{}`", text)))
+    }
+
+    fn as_bool(&self) -> Result<bool, SceneError> {
+        match self.as_str()? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            text => Err(SceneError::new(
+                self.line,
+                format!("expected true or false, got `{}`", text),
+            )),
+        }
+    }
+
+    fn as_size(&self) -> Result<i32, SceneError> {
+        let text = self.as_str()?;
+        match text.parse() {
+            Ok(size) if size > 0 => Ok(size),
+            _ => Err(SceneError::new(
+                self.line,
+                format!("expected a positive whole number, got `{}`", text),
+            )),
+        }
+    }
+
+    fn as_attenuation(&self) -> Result<Attenuation, SceneError> {
+        if let Value::Scalar(text) = &self.value {
+            return match text.as_str() {
+                "inverse-square" => Ok(Attenuation::InverseSquare),
+                _ => Err(SceneError::new(
+                    self.line,
+                    format!("unknown attenuation `{}`", text),
+                )),
+            };
+        }
+        let [constant, linear, quadratic] = self.as_triple()?;
+        Ok(Attenuation::Polynomial {
+            constant,
+            linear,
+            quadratic,
+        })
+    }
+
+    fn as_shading(&self) -> Result<ShadingModel, SceneError> {
+        match self.as_str()? {
+            "phong" => Ok(ShadingModel::Phong),
+            "blinn-phong" => Ok(ShadingModel::BlinnPhong),
+            "lambert" => Ok(ShadingModel::Lambert),
+            "oren-nayar" => Ok(ShadingModel::OrenNayar { roughness: 0.0 }),
+            "unlit" => Ok(ShadingModel::Unlit),
+            "microfacet" => Ok(ShadingModel::Microfacet(Microfacet::default())),
+            name => Err(SceneError::new(
+                self.line,
+                format!("unknown shading model `{}`", name),
+            )),
+        }
+    }
+
+    fn as_triple(&self) -> Result<[f64; 3], SceneError> {
+        let items = self.as_list("a list of 3 numbers")?;
+        match items {
+            [x, y, z] => Ok([x.as_f64()?, y.as_f64()?, z.as_f64()?]),
+            _ => Err(SceneError::new(self.line, "expected a list of 3 numbers")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bump::Bump;
+    use crate::is_float_equal;
+    use crate::lights::Light;
+    use crate::rays::Ray;
+
+    fn assert_light_eq(light: &dyn Light, expected: impl Light) {
+        assert_eq!(format!("{:?}", light), format!("{:?}", expected));
+    }
+
+    const CAMERA: &str = "\
+- add: camera
+  width: 20
+  height: 10
+  field-of-view: 1.5
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+";
+
+    fn parse_with_camera(source: &str) -> Result<Scene, SceneError> {
+        parse(&format!("{}{}", CAMERA, source))
+    }
+
+    #[test]
+    fn parsing_a_camera_and_a_light() {
+        let scene = parse_with_camera(
+            "\
+- add: light
+  at: [ -10, 10, -10 ]
+  intensity: [ 1, 0.5, 1 ]
+",
+        )
+        .unwrap();
+        assert_eq!(scene.camera.hsize, 20);
+        assert_eq!(scene.camera.vsize, 10);
+        assert_eq!(scene.camera.field_of_view, 1.5);
+        assert_eq!(
+            scene.camera.transform(),
+            &Matrix::identity()
+                .translate(0.0, 0.0, 5.0)
+                .scale(-1.0, 1.0, -1.0)
+        );
+        assert_light_eq(
+            scene.world.lights[0].as_ref(),
+            PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 0.5, 1.0)),
+        );
+    }
+
+    #[test]
+    fn parsing_a_camera_lens() {
+        let scene = parse(
+            "\
+- add: camera
+  width: 20
+  height: 10
+  field-of-view: 1.5
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+  aperture: 0.25
+  focal-distance: 4.5
+  shutter-open: 0.5
+  shutter-close: 1
+",
+        )
+        .unwrap();
+        assert_eq!(scene.camera.aperture, 0.25);
+        assert_eq!(scene.camera.focal_distance, 4.5);
+        assert_eq!(scene.camera.shutter_open, 0.5);
+        assert_eq!(scene.camera.shutter_close, 1.0);
+    }
+
+    #[test]
+    fn invalid_camera_lenses_and_shutters_are_errors() {
+        let cases = [
+            ("  aperture: -0.5\n", "`aperture` can't be negative"),
+            ("  focal-distance: 0\n", "`focal-distance` must be positive"),
+            (
+                "  shutter-open: 1\n  shutter-close: 0.5\n",
+                "`shutter-close` can't come before `shutter-open`",
+            ),
+        ];
+        for (lens, message) in cases {
+            let error = parse(&format!("{}{}", CAMERA, lens)).err().unwrap();
+            let line = 7 + lens.lines().count();
+            assert_eq!(error, SceneError::new(line, message));
+        }
+    }
+
+    #[test]
+    fn parsing_a_moving_shape() {
+        let scene = parse_with_camera(
+            "\
+- add: sphere
+  transform:
+    - [ translate, 1, 0, 0 ]
+  motion:
+    scale: [ 2, 2, 2 ]
+    rotate: [ 0, 1.5707963267948966, 0 ]
+    translate: [ 0, 3, 0 ]
+",
+        )
+        .unwrap();
+        let transform = scene.world.objects[0].transform();
+        let end = transform.at(1.0).unwrap();
+        // Moved on top of its own transform: scaled, turned, then lifted
+        let point = end.matrix() * Tuple::point(0.0, 0.0, 0.0);
+        assert!(point.is_equal(&Tuple::point(0.0, 3.0, -2.0)));
+        assert_eq!(transform.at(0.0).unwrap().matrix(), transform.matrix());
+    }
+
+    #[test]
+    fn parsing_an_area_light() {
+        let scene = parse_with_camera(
+            "\
+- add: area-light
+  corner: [ -1, 2, 4 ]
+  uvec: [ 2, 0, 0 ]
+  usteps: 4
+  vvec: [ 0, 2, 0 ]
+  vsteps: 2
+  intensity: [ 1.5, 1.5, 1.5 ]
+  jitter: false
+",
+        )
+        .unwrap();
+        let mut expected = AreaLight::new(
+            Tuple::point(-1.0, 2.0, 4.0),
+            Tuple::vector(2.0, 0.0, 0.0),
+            4,
+            Tuple::vector(0.0, 2.0, 0.0),
+            2,
+            Color::new(1.5, 1.5, 1.5),
+        );
+        expected.jitter = false;
+        assert_light_eq(scene.world.lights[0].as_ref(), expected);
+    }
+
+    #[test]
+    fn parsing_light_attenuation() {
+        let scene = parse_with_camera(
+            "\
+- add: light
+  at: [ 0, 0, 0 ]
+  intensity: [ 1, 1, 1 ]
+  attenuation: inverse-square
+- add: light
+  at: [ 0, 0, 0 ]
+  intensity: [ 1, 1, 1 ]
+  attenuation: [ 1, 0.5, 0.25 ]
+",
+        )
+        .unwrap();
+        assert_eq!(scene.world.lights[0].attenuation(4.0), 1.0 / 16.0);
+        assert_eq!(scene.world.lights[1].attenuation(2.0), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn parsing_directional_and_spot_lights() {
+        let scene = parse_with_camera(
+            "\
+- add: directional-light
+  direction: [ 0, -2, 0 ]
+  intensity: [ 0.5, 0.5, 0.5 ]
+- add: spot-light
+  at: [ 0, 5, 0 ]
+  direction: [ 0, -1, 0 ]
+  inner-angle: 0.2
+  outer-angle: 0.4
+  intensity: [ 1, 1, 1 ]
+",
+        )
+        .unwrap();
+        assert_light_eq(
+            scene.world.lights[0].as_ref(),
+            DirectionalLight::new(Tuple::vector(0.0, -1.0, 0.0), Color::new(0.5, 0.5, 0.5)),
+        );
+        assert_light_eq(
+            scene.world.lights[1].as_ref(),
+            SpotLight::new(
+                Tuple::point(0.0, 5.0, 0.0),
+                Tuple::vector(0.0, -1.0, 0.0),
+                0.2,
+                0.4,
+                Color::white(),
+            ),
+        );
+
+        let spot = |inner, outer| {
+            format!(
+                "- add: spot-light\n  at: [ 0, 5, 0 ]\n  direction: [ 0, -1, 0 ]\n  \
+                 inner-angle: {}\n  outer-angle: {}\n  intensity: [ 1, 1, 1 ]\n",
+                inner, outer
+            )
+        };
+        let cases = [
+            (
+                spot("0.4", "0.2"),
+                11,
+                "`inner-angle` can't be wider than `outer-angle`",
+            ),
+            (
+                spot("0", "0.2"),
+                11,
+                "`inner-angle` must be between 0 and pi",
+            ),
+            (
+                spot("0.2", "3.2"),
+                12,
+                "`outer-angle` must be between 0 and pi",
+            ),
+        ];
+        for (source, line, message) in cases {
+            let error = parse_with_camera(&source).err().unwrap();
+            assert_eq!((error.line, error.message.as_str()), (line, message));
+        }
+    }
+
+    #[test]
+    fn transforms_apply_in_listed_order() {
+        let scene = parse_with_camera(
+            "\
+- add: sphere
+  transform:
+    - [ scale, 2, 2, 2 ]
+    - [ translate, 1, 0, 0 ]
+",
+        )
+        .unwrap();
+        assert_eq!(
+            scene.world.objects[0].transform().matrix(),
+            &Matrix::identity()
+                .scale(2.0, 2.0, 2.0)
+                .translate(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn defined_materials_can_be_extended_and_reused() {
+        let scene = parse_with_camera(
+            "\
+- define: red
+  value:
+    color: [ 1, 0, 0 ]
+    diffuse: 0.5
+    reflective: 0.25
+    emission: [ 0.1, 0, 0 ]
+- define: shiny-red
+  extend: red
+  value:
+    shininess: 300
+- add: sphere
+  material: shiny-red
+",
+        )
+        .unwrap();
+        let expected = Material::new()
+            .color(Color::new(1.0, 0.0, 0.0))
+            .diffuse(0.5)
+            .reflective(0.25)
+            .emission(Color::new(0.1, 0.0, 0.0))
+            .shininess(300.0);
+        assert_eq!(scene.world.objects[0].material(), &expected);
+    }
+
+    #[test]
+    fn parsing_backgrounds() {
+        let scene = parse_with_camera(
+            "\
+- add: background
+  bottom: [ 1, 1, 1 ]
+  top: [ 0.2, 0.4, 1 ]
+  lighting: true
+",
+        )
+        .unwrap();
+        let sky = Background::Gradient {
+            bottom: Color::white(),
+            top: Color::new(0.2, 0.4, 1.0),
+        };
+        assert_eq!(scene.world.background, sky);
+        assert!(scene.world.image_based_lighting);
+
+        let error =
+            parse_with_camera("- add: background\n  color: [ 1, 1, 1 ]\n  top: [ 0, 0, 1 ]\n")
+                .err()
+                .unwrap();
+        assert_eq!(error.line, 8);
+        assert!(error.message.starts_with("background needs either"));
+    }
+
+    #[test]
+    fn parsing_fog_and_a_volume() {
+        let scene = parse_with_camera(
+            "\
+- add: fog
+  color: [ 0.5, 0.5, 0.5 ]
+  start: 2
+  end: 10
+- add: volume
+  density: 1000
+  transform:
+    - [ translate, 0, 1, 0 ]
+  material:
+    color: [ 0.2, 0.2, 0.2 ]
+  boundary:
+    add: sphere
+    transform:
+      - [ scale, 2, 2, 2 ]
+",
+        )
+        .unwrap();
+        let fog = Fog::new(
+            Color::new(0.5, 0.5, 0.5),
+            FogFalloff::Linear {
+                start: 2.0,
+                end: 10.0,
+            },
+        );
+        assert_eq!(scene.world.fog, Some(fog));
+        assert_eq!(scene.world.objects.len(), 1);
+        let volume = scene.world.objects[0].as_ref();
+        assert_eq!(volume.material().shading, ShadingModel::Isotropic);
+        assert_eq!(volume.material().color, Color::new(0.2, 0.2, 0.2));
+        // The boundary moves along with the volume, and the smoke is so
+        // thick rays scatter as soon as they enter it
+        let ray = Ray::new(Tuple::point(0.0, 2.5, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let entry = 5.0 - 1.75_f64.sqrt();
+        let time = scene.world.intersect(&ray)[0].time;
+        assert!(time > entry && time < entry + 0.1);
+
+        let cases = [
+            ("- add: fog\n  color: [ 1, 1, 1 ]\n  start: 2\n", 8),
+            ("- add: fog\n  color: [ 1, 1, 1 ]\n  density: -0.1\n", 10),
+            (
+                "- add: fog\n  color: [ 1, 1, 1 ]\n  start: 5\n  end: 5\n",
+                11,
+            ),
+            (
+                "- add: volume\n  density: -1\n  boundary:\n    add: sphere\n",
+                9,
+            ),
+            (
+                "- add: volume\n  density: 1\n  boundary:\n    add: group\n    children: []\n",
+                11,
+            ),
+            (
+                "- add: volume\n  density: 1\n  transform:\n    - [ scale, 0, 1, 1 ]\n  boundary:\n    add: sphere\n",
+                13,
+            ),
+        ];
+        for (source, line) in cases {
+            let error = parse_with_camera(source).err().unwrap();
+            assert_eq!(error.line, line, "{}", error.message);
+        }
+    }
+
+    #[test]
+    fn parsing_a_glowing_triangle() {
+        let scene = parse_with_camera(
+            "\
+- add: triangle
+  p1: [ 0, 1, 0 ]
+  p2: [ -1, 0, 0 ]
+  p3: [ 1, 0, 0 ]
+  transform:
+    - [ translate, 0, 0, 2 ]
+  material:
+    emission: [ 4, 4, 4 ]
+",
+        )
+        .unwrap();
+        let triangle = scene.world.objects[0].as_ref();
+        assert_eq!(
+            triangle.transform().matrix(),
+            &Matrix::identity().translate(0.0, 0.0, 2.0)
+        );
+        assert_eq!(
+            triangle.material(),
+            &Material::new().emission(Color::new(4.0, 4.0, 4.0))
+        );
+        // In object space, before the translation
+        let ray = Ray::new(Tuple::point(0.0, 0.5, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(triangle.local_intersect(&ray), vec![2.0]);
+    }
+
+    #[test]
+    fn parsing_bumpy_materials() {
+        let directory = std::env::temp_dir().join("ray_tracer_normal_map");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("flat.ppm"), "P3\n1 1\n255\n128 128 255\n").unwrap();
+        let scene_path = directory.join("scene.yml");
+        let source = format!(
+            "{}\
+- add: sphere
+  material:
+    bump:
+      pattern: waves
+      frequency: 2
+      scale: 0.5
+- add: sphere
+  material:
+    normal-map: flat.ppm
+- add: plane
+  material:
+    normal-map: flat.ppm
+",
+            CAMERA
+        );
+        std::fs::write(&scene_path, source).unwrap();
+        let scene = load(&scene_path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let objects = &scene.world.objects;
+        let waves = HeightField::Waves { frequency: 2.0 };
+        assert_eq!(objects[0].material(), &Material::new().bump(waves, 0.5));
+        let (sphere, plane) = (objects[1].material(), objects[2].material());
+        let Some(Bump::Map(map)) = &sphere.bump else {
+            panic!("expected a normal map, got {:?}", sphere.bump);
+        };
+        assert_eq!((map.0.width, map.0.height), (1, 1));
+        // Both share the image
+        assert_eq!(sphere, plane);
+    }
+
+    #[test]
+    fn parsing_a_metallic_roughness_material() {
+        let scene = parse_with_camera(
+            "\
+- define: gold
+  value:
+    color: [ 1, 0.8, 0.3 ]
+    metallic: 1
+- add: sphere
+  material: gold
+- add: sphere
+  material:
+    roughness: 0.2
+",
+        )
+        .unwrap();
+        let gold = Material::new()
+            .color(Color::new(1.0, 0.8, 0.3))
+            .metallic_roughness(1.0, 0.5);
+        assert_eq!(scene.world.objects[0].material(), &gold);
+        let plastic = Material::new().metallic_roughness(0.0, 0.2);
+        assert_eq!(scene.world.objects[1].material(), &plastic);
+    }
+
+    #[test]
+    fn parsing_shading_models() {
+        let scene = parse_with_camera(
+            "\
+- add: sphere
+  material:
+    shading: blinn-phong
+- add: sphere
+  material:
+    shading: oren-nayar
+    roughness: 0.3
+- add: sphere
+  material:
+    shading: unlit
+",
+        )
+        .unwrap();
+        let expected = [
+            ShadingModel::BlinnPhong,
+            ShadingModel::OrenNayar { roughness: 0.3 },
+            ShadingModel::Unlit,
+        ];
+        for (object, shading) in scene.world.objects.iter().zip(expected) {
+            assert_eq!(object.material(), &Material::new().shading_model(shading));
+        }
+    }
+
+    #[test]
+    fn defined_transforms_can_be_used_by_name() {
+        let scene = parse_with_camera(
+            "\
+- define: lift
+  value:
+    - [ translate, 0, 1, 0 ]
+- add: plane
+  transform:
+    - [ scale, 2, 2, 2 ]
+    - lift
+",
+        )
+        .unwrap();
+        assert_eq!(
+            scene.world.objects[0].transform().matrix(),
+            &Matrix::identity()
+                .scale(2.0, 2.0, 2.0)
+                .translate(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn groups_push_their_transform_and_material_onto_children() {
+        let scene = parse_with_camera(
+            "\
+- add: group
+  transform:
+    - [ translate, 0, 1, 0 ]
+  material:
+    color: [ 0, 0, 1 ]
+  children:
+    - add: sphere
+      transform:
+        - [ scale, 2, 2, 2 ]
+    - add: group
+      children:
+        - add: sphere
+          material:
+            ambient: 0.5
+",
+        )
+        .unwrap();
+        let objects = &scene.world.objects;
+        assert_eq!(objects.len(), 2);
+        assert_eq!(
+            objects[0].transform().matrix(),
+            &Matrix::identity()
+                .scale(2.0, 2.0, 2.0)
+                .translate(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            objects[1].transform().matrix(),
+            &Matrix::identity().translate(0.0, 1.0, 0.0)
+        );
+        let blue = Material::new().color(Color::new(0.0, 0.0, 1.0));
+        assert_eq!(objects[0].material(), &blue);
+        assert_eq!(objects[1].material(), &blue.ambient(0.5));
+    }
+
+    #[test]
+    fn rendering_a_parsed_scene() {
+        let scene = parse(
+            "\
+- add: camera
+  width: 11
+  height: 11
+  field-of-view: 1.5707963267948966
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: light
+  at: [ -10, 10, -10 ]
+  intensity: [ 1, 1, 1 ]
+- add: sphere
+  material:
+    color: [ 0.8, 1.0, 0.6 ]
+    diffuse: 0.7
+    specular: 0.2
+- add: sphere
+  transform:
+    - [ scale, 0.5, 0.5, 0.5 ]
+",
+        )
+        .unwrap();
+        let image = scene.camera.render(&scene.world);
+        let color = image.pixel_at(&Tuple::point(5.0, 5.0, 0.0)).unwrap();
+        assert!(is_float_equal(color.red, 0.38066));
+        assert!(is_float_equal(color.green, 0.47583));
+        assert!(is_float_equal(color.blue, 0.2855));
+    }
+
+    #[test]
+    fn loading_a_missing_scene_file_is_an_error() {
+        let result = load("scenes/does-not-exist.yml");
+        assert!(matches!(result, Err(Error::Io { .. })));
+    }
+
+    #[test]
+    fn loading_the_example_scene() {
+        let scene = load("scenes/spheres.yml").unwrap();
+        assert_eq!(scene.world.objects.len(), 4);
+        assert_eq!(scene.world.lights.len(), 1);
+    }
+
+    #[test]
+    fn missing_camera_is_an_error() {
+        let error = parse("- add: sphere\n").err().unwrap();
+        assert_eq!(error, SceneError::new(1, "scene has no camera"));
+    }
+
+    #[test]
+    fn errors_report_the_line_they_happened_on() {
+        let cases = [
+            ("- add: cube\n", 8, "unknown entry `cube`"),
+            (
+                "- add: sphere\n  material: blue\n",
+                9,
+                "undefined material `blue`",
+            ),
+            (
+                "- add: sphere\n  transform:\n    - [ scale, 1, 2 ]\n",
+                10,
+                "`scale` takes 3 values, got 2",
+            ),
+            (
+                "- add: sphere\n  transform:\n    - [ scale, 0, 1, 1 ]\n",
+                10,
+                "transform is not invertible",
+            ),
+            (
+                "- add: light\n  at: [ 1, 2, x ]\n  intensity: [ 1, 1, 1 ]\n",
+                9,
+                "expected a number, got `x`",
+            ),
+            (
+                "- add: sphere\n  colour: [ 1, 1, 1 ]\n",
+                9,
+                "unknown key `colour` for sphere",
+            ),
+            (
+                "- add: light\n  at: [ 1, 2, 3 ]\n",
+                8,
+                "light is missing `intensity`",
+            ),
+            (
+                "- add: light\n  at: [ 1, 2, 3 ]\n  intensity: [ 1, 1, 1 ]\n  attenuation: cubic\n",
+                11,
+                "unknown attenuation `cubic`",
+            ),
+            (
+                "- add: sphere\n  material:\n    shading: toon\n",
+                10,
+                "unknown shading model `toon`",
+            ),
+            (
+                "- add: sphere\n  material:\n    bump:\n      pattern: plaid\n",
+                11,
+                "unknown bump pattern `plaid`",
+            ),
+            (
+                "- add: sphere\n  motion:\n    scale: [ 0, 1, 1 ]\n",
+                10,
+                "motion can't scale to zero or below",
+            ),
+        ];
+        for (source, line, message) in cases {
+            let error = parse_with_camera(source).err().unwrap();
+            assert_eq!(error, SceneError::new(line, message), "{}", source);
+        }
+    }
+
+    #[test]
+    fn scene_errors_display_their_line() {
+        let error = SceneError::new(3, "unknown entry `cube`");
+        assert_eq!(error.to_string(), "line 3: unknown entry `cube`");
+    }
+}
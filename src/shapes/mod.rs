@@ -0,0 +1,306 @@
+mod motion;
+mod plane;
+mod sphere;
+mod triangle;
+mod volume;
+
+pub use motion::{Keyframe, Motion, Quaternion};
+pub use plane::Plane;
+pub use sphere::Sphere;
+pub use triangle::Triangle;
+pub use volume::Volume;
+
+use crate::error::Error;
+use crate::geometry::{Point3, Vector3};
+use crate::matrix::Matrix;
+use crate::rays::{Material, Ray};
+use crate::sampling::orthonormal_basis;
+use crate::tuple::Tuple;
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+/// Anything a ray can hit.
+/// Shapes only need to know how to intersect and find normals in their own
+/// object space; moving between world and object space is shared here.
+pub trait Object: Debug + Send + Sync {
+    fn material(&self) -> &Material;
+
+    fn transform(&self) -> &Transform;
+
+    /// Intersection times of a ray that is already in object space.
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64>;
+
+    fn local_normal_at(&self, point: Point3) -> Vector3;
+
+    /// Texture coordinates of a point on the surface in object space, and
+    /// the direction in which `u` grows there. `None` for shapes that can't
+    /// be textured.
+    fn local_uv(&self, _point: Point3) -> Option<(f64, f64, Vector3)> {
+        None
+    }
+
+    /// Like `local_uv`, for a point in world space at the given shutter
+    /// time.
+    fn surface_frame(&self, world_point: &Tuple, shutter_time: f64) -> Option<SurfaceFrame> {
+        let transform = self.transform().at(shutter_time)?;
+        let object_point =
+            transform.inverse() * Point3::try_from(*world_point).expect("world points have w = 1");
+        let (u, v, tangent) = self.local_uv(object_point)?;
+        Some(SurfaceFrame {
+            u,
+            v,
+            tangent: (transform.matrix() * tangent).normalize().into(),
+        })
+    }
+
+    /// A point spread evenly over the surface in object space, for a point
+    /// in the unit square, along with the surface's area. Shapes that can't
+    /// be sampled, like infinite planes, return `None` and can't be lights.
+    fn local_sample_surface(&self, _sample: (f64, f64)) -> Option<(Point3, f64)> {
+        None
+    }
+
+    /// Like `local_sample_surface`, but in world space at the given shutter
+    /// time.
+    fn sample_surface(&self, sample: (f64, f64), shutter_time: f64) -> Option<SurfaceSample> {
+        let (local_point, local_area) = self.local_sample_surface(sample)?;
+        let transform = self.transform().at(shutter_time)?;
+        let local_normal = self.local_normal_at(local_point).normalize();
+        // How much the transform stretches the surface around the point
+        let (tangent, bitangent) = orthonormal_basis(local_normal.into());
+        let matrix = transform.matrix();
+        let stretch = (matrix * tangent).cross(&(matrix * bitangent)).magnitude();
+        Some(SurfaceSample {
+            point: (matrix * local_point).into(),
+            normal: (transform.inverse_transpose() * local_normal)
+                .normalize()
+                .into(),
+            pdf: 1.0 / (local_area * stretch),
+        })
+    }
+
+    fn normal_at(&self, world_point: &Tuple) -> Tuple {
+        self.normal_at_time(world_point, 0.0)
+    }
+
+    /// Like `normal_at`, for a moving object at the given shutter time.
+    /// Rays never hit an object while it is scaled to nothing, so then any
+    /// normal will do and it is taken where the object is still.
+    fn normal_at_time(&self, world_point: &Tuple, shutter_time: f64) -> Tuple {
+        let transform = self
+            .transform()
+            .at(shutter_time)
+            .unwrap_or(Cow::Borrowed(self.transform()));
+        let object_point =
+            transform.inverse() * Point3::try_from(*world_point).expect("world points have w = 1");
+        let object_normal = self.local_normal_at(object_point);
+        // Multiplying a Vector3 skips the translation row, so w needs no fixing
+        let world_normal = transform.inverse_transpose() * object_normal;
+        world_normal.normalize().into()
+    }
+}
+
+// Objects are compared by identity, two identical spheres are still different
+// objects in a world.
+impl<'a> PartialEq for dyn Object + 'a {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+/// Where a point is on a surface's texture, for mapping images onto it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SurfaceFrame {
+    pub u: f64,
+    pub v: f64,
+    /// Unit vector along the surface in which `u` grows. Crossed with the
+    /// normal it gives the direction in which `v` grows.
+    pub tangent: Tuple,
+}
+
+/// A point on an object's surface, picked at random.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SurfaceSample {
+    pub point: Tuple,
+    /// Unit vector, facing out of the surface.
+    pub normal: Tuple,
+    /// Probability density of picking the point, per unit of area in world
+    /// space.
+    pub pdf: f64,
+}
+
+/// A transformation matrix along with its inverse and inverse-transpose.
+/// Every ray needs those, so they are computed once when the transform is set.
+/// A moving object also has a `Motion`, applied on top of the matrix.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Transform {
+    matrix: Matrix,
+    inverse: Matrix,
+    inverse_transpose: Matrix,
+    motion: Option<Motion>,
+}
+
+impl Transform {
+    /// Panics if the matrix can't be inverted, see `Transform::try_from`.
+    pub fn new(matrix: Matrix) -> Self {
+        Self::try_from(matrix).expect("transform must be invertible")
+    }
+
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Matrix {
+        &self.inverse_transpose
+    }
+
+    pub fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    /// The transform at the given shutter time. Still objects don't need a
+    /// new one, moving ones have it worked out for every ray, combining the
+    /// cached inverse with the motion's. `None` if the motion scales the
+    /// object to nothing at that time.
+    pub fn at(&self, shutter_time: f64) -> Option<Cow<'_, Transform>> {
+        let Some(motion) = &self.motion else {
+            return Some(Cow::Borrowed(self));
+        };
+        let inverse = &self.inverse * &motion.inverse_at(shutter_time)?;
+        Some(Cow::Owned(Transform {
+            matrix: &motion.at(shutter_time) * &self.matrix,
+            inverse_transpose: inverse.transpose(),
+            inverse,
+            motion: None,
+        }))
+    }
+}
+
+impl TryFrom<Matrix> for Transform {
+    type Error = Error;
+
+    fn try_from(matrix: Matrix) -> Result<Self, Error> {
+        let inverse = matrix.inverse().ok_or(Error::NonInvertibleMatrix)?;
+        Ok(Self {
+            inverse_transpose: inverse.transpose(),
+            inverse,
+            matrix,
+            motion: None,
+        })
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::new(Matrix::identity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_caches_its_inverse_and_inverse_transpose() {
+        let matrix = Matrix::identity()
+            .scale(2.0, 3.0, 4.0)
+            .translate(1.0, 0.0, 0.0);
+        let transform = Transform::new(matrix.clone());
+        let inverse = matrix.inverse().unwrap();
+        assert_eq!(transform.matrix(), &matrix);
+        assert_eq!(transform.inverse_transpose(), &inverse.transpose());
+        assert_eq!(transform.inverse(), &inverse);
+    }
+
+    #[test]
+    fn converting_a_non_invertible_matrix_is_an_error() {
+        let result = Transform::try_from(Matrix::identity().scale(1.0, 0.0, 1.0));
+        assert!(matches!(result, Err(Error::NonInvertibleMatrix)));
+    }
+
+    #[test]
+    #[should_panic(expected = "transform must be invertible")]
+    fn non_invertible_transform_panics() {
+        Transform::new(Matrix::identity().scale(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn still_transforms_are_the_same_at_any_time() {
+        let transform = Transform::new(Matrix::identity().scale(2.0, 2.0, 2.0));
+        assert!(matches!(transform.at(0.7), Some(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn moving_transforms_apply_the_motion_after_the_matrix() {
+        let mut sphere = Sphere::with_transform(Matrix::identity().scale(2.0, 2.0, 2.0));
+        let end = Keyframe {
+            translation: Tuple::vector(4.0, 0.0, 0.0),
+            ..Keyframe::identity()
+        };
+        sphere.set_motion(Motion::new(Keyframe::identity(), end));
+        let halfway = sphere.transform().at(0.5).unwrap();
+        assert_eq!(
+            halfway.matrix(),
+            &Matrix::identity()
+                .scale(2.0, 2.0, 2.0)
+                .translate(2.0, 0.0, 0.0)
+        );
+        let normal = sphere.normal_at_time(&Tuple::point(4.0, 0.0, 0.0), 0.5);
+        assert!(normal.is_equal(&Tuple::vector(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn objects_moving_through_zero_scale_vanish_without_panicking() {
+        let mut sphere = Sphere::new();
+        let end = Keyframe {
+            scale: Tuple::vector(-1.0, 1.0, 1.0),
+            ..Keyframe::identity()
+        };
+        sphere.set_motion(Motion::new(Keyframe::identity(), end));
+        assert!(sphere.transform().at(0.5).is_none());
+        let ray = |time| {
+            Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0))
+                .at_shutter_time(time)
+        };
+        assert!(ray(0.5).intersect(&sphere).is_none());
+        assert!(ray(0.25).intersect(&sphere).is_some());
+        let normal = sphere.normal_at_time(&Tuple::point(0.0, 0.0, -1.0), 0.5);
+        assert!(normal.is_equal(&Tuple::vector(0.0, 0.0, -1.0)));
+    }
+
+    #[test]
+    fn surface_samples_account_for_the_transform() {
+        let mut triangle = Triangle::new(
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 0.0),
+            Tuple::point(0.0, 1.0, 0.0),
+        );
+        triangle
+            .set_transform(
+                Matrix::identity()
+                    .scale(2.0, 3.0, 1.0)
+                    .translate(0.0, 0.0, 5.0),
+            )
+            .unwrap();
+        let sample = triangle.sample_surface((0.5, 0.5), 0.0).unwrap();
+        assert!(sample.point.z == 5.0 && sample.point.x >= 0.0 && sample.point.y >= 0.0);
+        assert!(sample.normal.is_equal(&Tuple::vector(0.0, 0.0, -1.0)));
+        // A right triangle with legs of 2 and 3
+        assert!(crate::is_float_equal(sample.pdf, 1.0 / 3.0));
+        assert_eq!(Plane::new().sample_surface((0.5, 0.5), 0.0), None);
+    }
+
+    #[test]
+    fn objects_are_compared_by_identity() {
+        let one = Plane::new();
+        let two = Plane::new();
+        let one_ref: &dyn Object = &one;
+        let two_ref: &dyn Object = &two;
+        assert!(one_ref == one_ref);
+        assert!(one_ref != two_ref);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::matrix::Matrix;
+use crate::tuple::Tuple;
+
+/// A rotation, stored as a unit quaternion so it can be interpolated
+/// smoothly.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// A rotation of `radians` around `axis`, turning the same way as
+    /// `Matrix::rotate_x` and friends.
+    pub fn from_axis_angle(axis: Tuple, radians: f64) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (radians / 2.0).sin_cos();
+        Self {
+            w: cos,
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+        }
+    }
+
+    /// `self` applied after `other`.
+    pub fn then(&self, other: &Self) -> Self {
+        let (a, b) = (other, self);
+        Self {
+            w: b.w * a.w - b.x * a.x - b.y * a.y - b.z * a.z,
+            x: b.w * a.x + b.x * a.w + b.y * a.z - b.z * a.y,
+            y: b.w * a.y - b.x * a.z + b.y * a.w + b.z * a.x,
+            z: b.w * a.z + b.x * a.y - b.y * a.x + b.z * a.w,
+        }
+    }
+
+    /// The opposite rotation.
+    pub fn inverse(&self) -> Self {
+        Self {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    fn dot(&self, other: &Self) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    fn normalize(&self) -> Self {
+        let length = self.dot(self).sqrt();
+        Self {
+            w: self.w / length,
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+        }
+    }
+
+    /// Spherical interpolation, turning at a constant speed along the
+    /// shortest way from `self` (at 0) to `other` (at 1).
+    pub fn slerp(&self, other: &Self, t: f64) -> Self {
+        let mut dot = self.dot(other);
+        // q and -q are the same rotation, pick the one that's closer
+        let other = if dot < 0.0 {
+            dot = -dot;
+            Self {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            }
+        } else {
+            *other
+        };
+
+        let (from, to) = if dot > 0.9995 {
+            // Nearly the same rotation, where the sines below vanish
+            (1.0 - t, t)
+        } else {
+            let angle = dot.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Self {
+            w: self.w * from + other.w * to,
+            x: self.x * from + other.x * to,
+            y: self.y * from + other.y * to,
+            z: self.z * from + other.z * to,
+        }
+        .normalize()
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        let Self { w, x, y, z } = self.normalize();
+        let mut matrix = Matrix::identity();
+        matrix[0][0] = 1.0 - 2.0 * (y * y + z * z);
+        matrix[0][1] = 2.0 * (x * y - w * z);
+        matrix[0][2] = 2.0 * (x * z + w * y);
+        matrix[1][0] = 2.0 * (x * y + w * z);
+        matrix[1][1] = 1.0 - 2.0 * (x * x + z * z);
+        matrix[1][2] = 2.0 * (y * z - w * x);
+        matrix[2][0] = 2.0 * (x * z - w * y);
+        matrix[2][1] = 2.0 * (y * z + w * x);
+        matrix[2][2] = 1.0 - 2.0 * (x * x + y * y);
+        matrix
+    }
+}
+
+/// Where an object is at one end of the exposure: scaled, then rotated, then
+/// translated.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Keyframe {
+    pub translation: Tuple,
+    pub rotation: Quaternion,
+    pub scale: Tuple,
+}
+
+impl Keyframe {
+    /// Leaves the object where it is.
+    pub fn identity() -> Self {
+        Self {
+            translation: Tuple::vector(0.0, 0.0, 0.0),
+            rotation: Quaternion::identity(),
+            scale: Tuple::vector(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix {
+        let scale = Matrix::identity().scale(self.scale.x, self.scale.y, self.scale.z);
+        (&self.rotation.to_matrix() * &scale).translate(
+            self.translation.x,
+            self.translation.y,
+            self.translation.z,
+        )
+    }
+
+    /// The inverse of `matrix`, worked out from the parts rather than by
+    /// inverting it. `None` if the keyframe scales anything to nothing.
+    pub fn inverse_matrix(&self) -> Option<Matrix> {
+        let Tuple { x, y, z, .. } = self.scale;
+        if x == 0.0 || y == 0.0 || z == 0.0 {
+            return None;
+        }
+        let translation = Matrix::identity().translate(
+            -self.translation.x,
+            -self.translation.y,
+            -self.translation.z,
+        );
+        Some((&self.rotation.inverse().to_matrix() * &translation).scale(1.0 / x, 1.0 / y, 1.0 / z))
+    }
+}
+
+/// An object moving while the shutter is open. It sits at `start` at time 0
+/// and at `end` at time 1, translating and scaling linearly and rotating at
+/// a constant speed in between. Outside those times it holds still.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Motion {
+    pub start: Keyframe,
+    pub end: Keyframe,
+}
+
+impl Motion {
+    pub fn new(start: Keyframe, end: Keyframe) -> Self {
+        Self { start, end }
+    }
+
+    /// The transform at `time`, applied on top of the object's own.
+    pub fn at(&self, time: f64) -> Matrix {
+        self.keyframe_at(time).matrix()
+    }
+
+    /// The inverse of `at`, or `None` if the object is scaled to nothing at
+    /// `time`.
+    pub fn inverse_at(&self, time: f64) -> Option<Matrix> {
+        self.keyframe_at(time).inverse_matrix()
+    }
+
+    fn keyframe_at(&self, time: f64) -> Keyframe {
+        let t = time.clamp(0.0, 1.0);
+        let lerp = |a: Tuple, b: Tuple| a + (b - a) * t;
+        Keyframe {
+            translation: lerp(self.start.translation, self.end.translation),
+            rotation: self.start.rotation.slerp(&self.end.rotation, t),
+            scale: lerp(self.start.scale, self.end.scale),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    fn assert_matrix_eq(actual: &Matrix, expected: &Matrix) {
+        let point = Tuple::point(1.0, 2.0, 3.0);
+        let vector = Tuple::vector(-1.0, 0.5, 2.0);
+        assert!((actual * point).is_equal(&(expected * point)));
+        assert!((actual * vector).is_equal(&(expected * vector)));
+    }
+
+    #[test]
+    fn quaternions_rotate_like_matrices() {
+        let cases = [
+            (
+                Tuple::vector(1.0, 0.0, 0.0),
+                Matrix::identity().rotate_x(0.7),
+            ),
+            (
+                Tuple::vector(0.0, 1.0, 0.0),
+                Matrix::identity().rotate_y(0.7),
+            ),
+            (
+                Tuple::vector(0.0, 0.0, 1.0),
+                Matrix::identity().rotate_z(0.7),
+            ),
+        ];
+        for (axis, expected) in cases {
+            let rotation = Quaternion::from_axis_angle(axis, 0.7);
+            assert_matrix_eq(&rotation.to_matrix(), &expected);
+        }
+    }
+
+    #[test]
+    fn combining_quaternions_applies_them_in_order() {
+        let x = Quaternion::from_axis_angle(Tuple::vector(1.0, 0.0, 0.0), 0.3);
+        let y = Quaternion::from_axis_angle(Tuple::vector(0.0, 1.0, 0.0), 1.1);
+        let expected = Matrix::identity().rotate_x(0.3).rotate_y(1.1);
+        assert_matrix_eq(&y.then(&x).to_matrix(), &expected);
+    }
+
+    #[test]
+    fn slerp_turns_at_a_constant_speed() {
+        let axis = Tuple::vector(0.0, 1.0, 0.0);
+        let start = Quaternion::identity();
+        let end = Quaternion::from_axis_angle(axis, FRAC_PI_2);
+        for t in [0.0, 0.25, 0.5, 1.0] {
+            let expected = Quaternion::from_axis_angle(axis, FRAC_PI_2 * t);
+            assert_matrix_eq(&start.slerp(&end, t).to_matrix(), &expected.to_matrix());
+        }
+    }
+
+    #[test]
+    fn slerp_takes_the_short_way_around() {
+        let axis = Tuple::vector(0.0, 0.0, 1.0);
+        let start = Quaternion::from_axis_angle(axis, -PI * 0.9);
+        let end = Quaternion::from_axis_angle(axis, PI * 0.9);
+        // The short way crosses half a turn rather than passing through zero
+        let halfway = start.slerp(&end, 0.5).to_matrix();
+        assert_matrix_eq(&halfway, &Matrix::identity().rotate_z(PI));
+    }
+
+    #[test]
+    fn keyframes_scale_then_rotate_then_translate() {
+        let keyframe = Keyframe {
+            translation: Tuple::vector(1.0, 2.0, 3.0),
+            rotation: Quaternion::from_axis_angle(Tuple::vector(0.0, 1.0, 0.0), FRAC_PI_4),
+            scale: Tuple::vector(2.0, 1.0, 1.0),
+        };
+        let expected = Matrix::identity()
+            .scale(2.0, 1.0, 1.0)
+            .rotate_y(FRAC_PI_4)
+            .translate(1.0, 2.0, 3.0);
+        assert_matrix_eq(&keyframe.matrix(), &expected);
+    }
+
+    #[test]
+    fn motion_interpolates_between_keyframes() {
+        let end = Keyframe {
+            translation: Tuple::vector(2.0, 0.0, 0.0),
+            rotation: Quaternion::from_axis_angle(Tuple::vector(0.0, 0.0, 1.0), FRAC_PI_2),
+            ..Keyframe::identity()
+        };
+        let motion = Motion::new(Keyframe::identity(), end);
+        assert_matrix_eq(&motion.at(0.0), &Matrix::identity());
+        assert_matrix_eq(
+            &motion.at(0.5),
+            &Matrix::identity()
+                .rotate_z(FRAC_PI_4)
+                .translate(1.0, 0.0, 0.0),
+        );
+        assert_matrix_eq(&motion.at(2.0), &motion.at(1.0));
+    }
+
+    #[test]
+    fn inverses_undo_the_interpolated_transform() {
+        let end = Keyframe {
+            translation: Tuple::vector(2.0, -1.0, 3.0),
+            rotation: Quaternion::from_axis_angle(Tuple::vector(1.0, 1.0, 0.0), 1.2),
+            scale: Tuple::vector(2.0, 0.5, 3.0),
+        };
+        let motion = Motion::new(Keyframe::identity(), end);
+        for time in [0.0, 0.3, 1.0] {
+            let undone = &motion.inverse_at(time).unwrap() * &motion.at(time);
+            assert_matrix_eq(&undone, &Matrix::identity());
+        }
+    }
+
+    #[test]
+    fn objects_scaled_to_nothing_have_no_inverse() {
+        let end = Keyframe {
+            scale: Tuple::vector(-1.0, 1.0, 1.0),
+            ..Keyframe::identity()
+        };
+        let motion = Motion::new(Keyframe::identity(), end);
+        assert_eq!(motion.inverse_at(0.5), None);
+        assert!(motion.inverse_at(0.25).is_some());
+    }
+}
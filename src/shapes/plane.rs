@@ -0,0 +1,131 @@
+use super::{Motion, Object, Transform};
+use crate::error::Error;
+use crate::geometry::{Point3, Vector3};
+use crate::matrix::Matrix;
+use crate::rays::{Material, Ray};
+use crate::EPSILON;
+
+/// An infinite plane spanning x and z in object space.
+#[derive(Debug, PartialEq)]
+pub struct Plane {
+    transform: Transform,
+    pub material: Material,
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Self::with_transform(Matrix::identity())
+    }
+
+    /// Panics if the transform can't be inverted, see `try_with_transform`.
+    pub fn with_transform(transform: Matrix) -> Self {
+        Self::try_with_transform(transform).expect("transform must be invertible")
+    }
+
+    /// Fails with `Error::NonInvertibleMatrix` if the transform can't be
+    /// inverted.
+    pub fn try_with_transform(transform: Matrix) -> Result<Self, Error> {
+        Ok(Self {
+            transform: Transform::try_from(transform)?,
+            material: Material::new(),
+        })
+    }
+
+    /// Clears any motion. Fails with `Error::NonInvertibleMatrix`, leaving
+    /// the transform as it was, if the new one can't be inverted.
+    pub fn set_transform(&mut self, transform: Matrix) -> Result<(), Error> {
+        self.transform = Transform::try_from(transform)?;
+        Ok(())
+    }
+
+    /// Moves the plane while the shutter is open, on top of its transform.
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.transform.motion = Some(motion);
+    }
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Plane::new()
+    }
+}
+
+impl Object for Plane {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        // A ray parallel to the plane never hits it, and a coplanar ray is
+        // treated as a miss since the plane is infinitely thin.
+        if ray.direction.y.abs() < EPSILON {
+            return vec![];
+        }
+        vec![-ray.origin.y / ray.direction.y]
+    }
+
+    fn local_normal_at(&self, _point: Point3) -> Vector3 {
+        Vector3::new(0.0, 1.0, 0.0)
+    }
+
+    // Textures repeat every unit along x and z
+    fn local_uv(&self, point: Point3) -> Option<(f64, f64, Vector3)> {
+        let (u, v) = (point.x.rem_euclid(1.0), point.z.rem_euclid(1.0));
+        Some((u, v, Vector3::new(1.0, 0.0, 0.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuple::Tuple;
+
+    #[test]
+    fn ray_intersecting_plane_from_below() {
+        let plane = Plane::new();
+        let ray = Ray::new(Tuple::point(0.0, -1.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+        assert_eq!(plane.local_intersect(&ray), vec![1.0]);
+    }
+
+    #[test]
+    fn ray_intersecting_plane_from_above() {
+        let plane = Plane::new();
+        let ray = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        assert_eq!(plane.local_intersect(&ray), vec![1.0]);
+    }
+
+    #[test]
+    fn intersect_with_coplanar_ray() {
+        let plane = Plane::new();
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert!(plane.local_intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_with_parallel_ray() {
+        let plane = Plane::new();
+        let ray = Ray::new(Tuple::point(0.0, 10.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert!(plane.local_intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn textures_tile_across_a_plane() {
+        let plane = Plane::new();
+        let (u, v, tangent) = plane.local_uv(Point3::new(2.25, 0.0, -0.5)).unwrap();
+        assert_eq!((u, v), (0.25, 0.5));
+        assert_eq!(tangent, Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn normal_of_a_plane_is_constant_everywhere() {
+        let plane = Plane::new();
+        let normal = Vector3::new(0.0, 1.0, 0.0);
+        assert_eq!(plane.local_normal_at(Point3::new(0.0, 0.0, 0.0)), normal);
+        assert_eq!(plane.local_normal_at(Point3::new(10.0, 0.0, -10.0)), normal);
+        assert_eq!(plane.local_normal_at(Point3::new(-5.0, 0.0, 150.0)), normal);
+    }
+}
@@ -0,0 +1,321 @@
+use super::{Motion, Object, Transform};
+use crate::error::Error;
+use crate::geometry::{Point3, Vector3};
+use crate::matrix::Matrix;
+use crate::rays::{Material, Ray};
+use crate::simd::{F64x4, RayPacket, TuplePacket};
+use crate::tuple::Tuple;
+use crate::EPSILON;
+use std::f64::consts::PI;
+
+/// A unit sphere centered at the origin of its object space.
+#[derive(Debug, PartialEq)]
+pub struct Sphere {
+    id: f64,
+    transform: Transform,
+    pub material: Material,
+}
+
+impl Sphere {
+    pub fn new() -> Self {
+        Self::with_transform(Matrix::identity())
+    }
+
+    /// Panics if the transform can't be inverted, see `try_with_transform`.
+    pub fn with_transform(transform: Matrix) -> Self {
+        Self::try_with_transform(transform).expect("transform must be invertible")
+    }
+
+    /// Fails with `Error::NonInvertibleMatrix` if the transform can't be
+    /// inverted.
+    pub fn try_with_transform(transform: Matrix) -> Result<Self, Error> {
+        use rand::Rng;
+        Ok(Self {
+            id: rand::thread_rng().gen(),
+            material: Material::new(),
+            transform: Transform::try_from(transform)?,
+        })
+    }
+
+    /// Clears any motion. Fails with `Error::NonInvertibleMatrix`, leaving
+    /// the transform as it was, if the new one can't be inverted.
+    pub fn set_transform(&mut self, transform: Matrix) -> Result<(), Error> {
+        self.transform = Transform::try_from(transform)?;
+        Ok(())
+    }
+
+    /// Like `Ray::intersect` on each of the four rays: both times each ray
+    /// crosses the sphere, or `None` where it misses.
+    pub fn intersect_packet(&self, rays: &RayPacket) -> [Option<(f64, f64)>; 4] {
+        let Some(transform) = self.transform.at(rays.shutter_time) else {
+            return [None; 4];
+        };
+        let ray = rays.transform(transform.inverse());
+
+        // The same unit sphere math as `local_intersect`, lane by lane
+        let zero = F64x4::splat(0.0);
+        let center_to_origin = TuplePacket {
+            w: zero,
+            ..ray.origin
+        };
+        let a = ray.direction.dot(&ray.direction);
+        let b = F64x4::splat(2.0) * ray.direction.dot(&center_to_origin);
+        let c = center_to_origin.dot(&center_to_origin) - F64x4::splat(1.0);
+        let discriminant = b * b - F64x4::splat(4.0) * a * c;
+
+        // A negative discriminant's root is NaN, but that lane is a miss anyway
+        let sqrt = discriminant.sqrt();
+        let two_a = F64x4::splat(2.0) * a;
+        let (near, far) = ((-b - sqrt) / two_a, (-b + sqrt) / two_a);
+        std::array::from_fn(|lane| {
+            (discriminant.0[lane] >= 0.0).then(|| (near.0[lane], far.0[lane]))
+        })
+    }
+
+    /// Moves the sphere while the shutter is open, on top of its transform.
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.transform.motion = Some(motion);
+    }
+}
+
+impl Default for Sphere {
+    fn default() -> Self {
+        Sphere::new()
+    }
+}
+
+impl Object for Sphere {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        // Hardcoded unit sphere
+        let sphere_center = Tuple::point(0.0, 0.0, 0.0);
+
+        // https://www.scratchapixel.com/lessons/3d-basic-rendering/minimal-ray-tracer-rendering-simple-shapes/ray-sphere-intersection.html
+        let center_to_origin = ray.origin - sphere_center;
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2.0 * ray.direction.dot(&center_to_origin);
+        let c = center_to_origin.dot(&center_to_origin) - 1.0;
+        let discriminant = b * b - 4.0 * a * c;
+
+        if discriminant < 0.0 {
+            return vec![];
+        }
+
+        let sqrt = discriminant.sqrt();
+        vec![(-b - sqrt) / (2.0 * a), (-b + sqrt) / (2.0 * a)]
+    }
+
+    fn local_normal_at(&self, point: Point3) -> Vector3 {
+        point - Point3::origin()
+    }
+
+    // Longitude runs around y starting behind the sphere, latitude from the
+    // south pole to the north
+    fn local_uv(&self, point: Point3) -> Option<(f64, f64, Vector3)> {
+        let theta = point.x.atan2(point.z);
+        let radius = (point - Point3::origin()).magnitude();
+        let phi = (point.y / radius).clamp(-1.0, 1.0).acos();
+        let u = 1.0 - (theta / (2.0 * PI) + 0.5);
+        let v = 1.0 - phi / PI;
+        // Along the circle of latitude, anything will do at the poles
+        let tangent = Vector3::new(-point.z, 0.0, point.x);
+        let tangent = if tangent.magnitude() < EPSILON {
+            Vector3::new(1.0, 0.0, 0.0)
+        } else {
+            tangent.normalize()
+        };
+        Some((u, v, tangent))
+    }
+
+    fn local_sample_surface(&self, (u, v): (f64, f64)) -> Option<(Point3, f64)> {
+        // Even heights make even areas on a sphere, as Archimedes found
+        let z = 1.0 - 2.0 * u;
+        let radius = (1.0 - z * z).max(0.0).sqrt();
+        let (sin, cos) = (2.0 * PI * v).sin_cos();
+        Some((Point3::new(radius * cos, radius * sin, z), 4.0 * PI))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_float_equal;
+    use core::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn sphere_can_have_material_assigned() {
+        let mut sphere = Sphere::new();
+        let material = Material::new().ambient(1.99);
+        sphere.material = material.clone();
+        assert_eq!(sphere.material, material);
+    }
+
+    #[test]
+    fn sphere_has_a_default_material() {
+        assert_eq!(Sphere::new().material, Material::new());
+    }
+
+    #[test]
+    fn calculate_normal_on_transformed_sphere() {
+        let sphere =
+            Sphere::with_transform(Matrix::identity().rotate_z(PI / 5.0).scale(1.0, 0.5, 1.0));
+        let value = 2.0_f64.sqrt() / 2.0;
+        let normal = sphere.normal_at(&Tuple::point(0.0, value, -value));
+        assert!(is_float_equal(normal.y, 0.97014));
+        assert!(is_float_equal(normal.z, -0.24254));
+    }
+
+    #[test]
+    fn calculate_normal_on_translated_sphere() {
+        let sphere = Sphere::with_transform(Matrix::identity().translate(0.0, 1.0, 0.0));
+        let normal = sphere.normal_at(&Tuple::point(0.0, 1.70711, -FRAC_1_SQRT_2));
+        assert!(is_float_equal(normal.y, FRAC_1_SQRT_2));
+        assert!(is_float_equal(normal.z, -FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn normals_are_always_normalized() {
+        let sphere = Sphere::new();
+        let value = 3.0_f64.sqrt() / 3.0;
+        let normal = sphere.normal_at(&Tuple::point(value, value, value));
+        assert_eq!(normal, normal.normalize());
+    }
+
+    #[test]
+    fn normal_of_a_sphere_on_nonaxial_point() {
+        let sphere = Sphere::new();
+        let value = 3.0_f64.sqrt() / 3.0;
+        let normal = sphere.normal_at(&Tuple::point(value, value, value));
+        assert_eq!(normal, Tuple::vector(value, value, value));
+    }
+
+    #[test]
+    fn normal_of_a_sphere_on_z_axis() {
+        let sphere = Sphere::new();
+        let normal = sphere.normal_at(&Tuple::point(0.0, 0.0, 1.0));
+        assert_eq!(normal, Tuple::vector(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normal_of_a_sphere_on_y_axis() {
+        let sphere = Sphere::new();
+        let normal = sphere.normal_at(&Tuple::point(0.0, 1.0, 0.0));
+        assert_eq!(normal, Tuple::vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn normal_of_a_sphere_on_x_axis() {
+        let sphere = Sphere::new();
+        let normal = sphere.normal_at(&Tuple::point(1.0, 0.0, 0.0));
+        assert_eq!(normal, Tuple::vector(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn new_sphere_has_default_transform_and_can_be_changed() {
+        let mut sphere = Sphere::new();
+        assert_eq!(sphere.transform().matrix(), &Matrix::identity());
+        sphere
+            .set_transform(Matrix::identity().translate(2.0, 0.0, 1.0))
+            .unwrap();
+        assert_eq!(
+            sphere.transform().matrix(),
+            &Matrix::identity().translate(2.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn setting_a_non_invertible_transform_is_an_error() {
+        let mut sphere = Sphere::with_transform(Matrix::identity().scale(2.0, 2.0, 2.0));
+        let result = sphere.set_transform(Matrix::identity().scale(0.0, 1.0, 1.0));
+        assert!(matches!(result, Err(Error::NonInvertibleMatrix)));
+        assert_eq!(
+            sphere.transform().matrix(),
+            &Matrix::identity().scale(2.0, 2.0, 2.0)
+        );
+        let result = Sphere::try_with_transform(Matrix::identity().scale(0.0, 1.0, 1.0));
+        assert!(matches!(result, Err(Error::NonInvertibleMatrix)));
+    }
+
+    #[test]
+    fn surface_samples_of_a_scaled_sphere() {
+        let sphere = Sphere::with_transform(Matrix::identity().scale(2.0, 2.0, 2.0));
+        for sample in [(0.0, 0.0), (0.25, 0.5), (0.7, 0.9)] {
+            let surface = sphere.sample_surface(sample, 0.0).unwrap();
+            let from_center = surface.point - Tuple::point(0.0, 0.0, 0.0);
+            assert!(is_float_equal(from_center.magnitude(), 2.0));
+            assert!(surface.normal.is_equal(&(from_center / 2.0)));
+            assert!(is_float_equal(surface.pdf, 1.0 / (16.0 * PI)));
+        }
+    }
+
+    #[test]
+    fn texture_coordinates_on_a_sphere() {
+        let sphere = Sphere::new();
+        let cases = [
+            (Point3::new(0.0, 0.0, -1.0), 0.0, 0.5),
+            (Point3::new(1.0, 0.0, 0.0), 0.25, 0.5),
+            (Point3::new(0.0, 0.0, 1.0), 0.5, 0.5),
+            (Point3::new(-1.0, 0.0, 0.0), 0.75, 0.5),
+            (Point3::new(0.0, 1.0, 0.0), 0.5, 1.0),
+            (Point3::new(0.0, -1.0, 0.0), 0.5, 0.0),
+            (Point3::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), 0.25, 0.75),
+        ];
+        for (point, u, v) in cases {
+            let (actual_u, actual_v, _) = sphere.local_uv(point).unwrap();
+            assert!(is_float_equal(actual_u, u) && is_float_equal(actual_v, v));
+        }
+    }
+
+    #[test]
+    fn sphere_tangents_follow_growing_u() {
+        let sphere = Sphere::with_transform(Matrix::identity().scale(2.0, 2.0, 2.0));
+        let frame = sphere
+            .surface_frame(&Tuple::point(0.0, 0.0, -2.0), 0.0)
+            .unwrap();
+        assert!(frame.tangent.is_equal(&Tuple::vector(1.0, 0.0, 0.0)));
+        // v grows along the tangent crossed with the normal
+        let normal = sphere.normal_at(&Tuple::point(0.0, 0.0, -2.0));
+        assert!(frame
+            .tangent
+            .cross(&normal)
+            .is_equal(&Tuple::vector(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn new_sphere_returns_unique_value() {
+        let one = Sphere::new();
+        let two = Sphere::new();
+        assert_ne!(one, two);
+    }
+
+    #[test]
+    fn packets_hit_a_sphere_like_single_rays() {
+        let sphere = Sphere::with_transform(
+            Matrix::identity()
+                .scale(1.0, 0.8, 1.0)
+                .translate(0.2, 0.0, 0.0),
+        );
+        let origin = Tuple::point(0.0, 0.0, -5.0);
+        let rays = [-1.5, -0.3, 0.4, 1.1].map(|x| {
+            let target = Tuple::point(x, 0.25, 0.0);
+            Ray::new(origin, (target - origin).normalize())
+        });
+        let packet =
+            sphere.intersect_packet(&RayPacket::new([&rays[0], &rays[1], &rays[2], &rays[3]]));
+        for (ray, times) in rays.iter().zip(packet) {
+            let expected = ray
+                .intersect(&sphere)
+                .map(|hits| (hits[0].time, hits[1].time));
+            assert_eq!(times, expected);
+        }
+        assert!(packet[0].is_none());
+        assert!(packet[1].is_some());
+    }
+}
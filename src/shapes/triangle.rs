@@ -0,0 +1,228 @@
+use super::{Motion, Object, Transform};
+use crate::error::Error;
+use crate::geometry::{Point3, Vector3};
+use crate::matrix::Matrix;
+use crate::rays::{Material, Ray};
+use crate::tuple::Tuple;
+use crate::EPSILON;
+
+/// A flat triangle between three points in object space, the building block
+/// of meshes.
+#[derive(Debug, PartialEq)]
+pub struct Triangle {
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+    normal: Tuple,
+    transform: Transform,
+    pub material: Material,
+}
+
+impl Triangle {
+    /// The normal faces the side from which the points go counterclockwise.
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let (e1, e2) = (p2 - p1, p3 - p1);
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalize(),
+            transform: Transform::new(Matrix::identity()),
+            material: Material::new(),
+        }
+    }
+
+    /// Like `new`, placed by a transform. Fails with
+    /// `Error::NonInvertibleMatrix` if it can't be inverted.
+    pub fn try_with_transform(
+        p1: Tuple,
+        p2: Tuple,
+        p3: Tuple,
+        transform: Matrix,
+    ) -> Result<Self, Error> {
+        let transform = Transform::try_from(transform)?;
+        Ok(Self {
+            transform,
+            ..Self::new(p1, p2, p3)
+        })
+    }
+
+    pub fn vertices(&self) -> [Tuple; 3] {
+        [self.p1, self.p2, self.p3]
+    }
+
+    /// Clears any motion. Fails with `Error::NonInvertibleMatrix`, leaving
+    /// the transform as it was, if the new one can't be inverted.
+    pub fn set_transform(&mut self, transform: Matrix) -> Result<(), Error> {
+        self.transform = Transform::try_from(transform)?;
+        Ok(())
+    }
+
+    /// Moves the triangle while the shutter is open, on top of its transform.
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.transform.motion = Some(motion);
+    }
+}
+
+impl Object for Triangle {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    // Möller-Trumbore: solves for the barycentric coordinates of the hit and
+    // the time together
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        let direction_cross_e2 = ray.direction.cross(&self.e2);
+        let determinant = self.e1.dot(&direction_cross_e2);
+        // Parallel rays miss, like with planes
+        if determinant.abs() < EPSILON {
+            return vec![];
+        }
+
+        let f = 1.0 / determinant;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(&direction_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return vec![];
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * ray.direction.dot(&origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return vec![];
+        }
+        vec![f * self.e2.dot(&origin_cross_e1)]
+    }
+
+    fn local_normal_at(&self, _point: Point3) -> Vector3 {
+        Vector3::new(self.normal.x, self.normal.y, self.normal.z)
+    }
+
+    // Barycentric coordinates: how far along each edge from p1 the point is
+    fn local_uv(&self, point: Point3) -> Option<(f64, f64, Vector3)> {
+        let to_point = Tuple::from(point) - self.p1;
+        let (d11, d12, d22) = (
+            self.e1.dot(&self.e1),
+            self.e1.dot(&self.e2),
+            self.e2.dot(&self.e2),
+        );
+        let (d1p, d2p) = (self.e1.dot(&to_point), self.e2.dot(&to_point));
+        let denominator = d11 * d22 - d12 * d12;
+        let u = (d22 * d1p - d12 * d2p) / denominator;
+        let v = (d11 * d2p - d12 * d1p) / denominator;
+        let tangent = self.e1.normalize();
+        Some((u, v, Vector3::new(tangent.x, tangent.y, tangent.z)))
+    }
+
+    fn local_sample_surface(&self, (u, v): (f64, f64)) -> Option<(Point3, f64)> {
+        // The square root keeps points from bunching up around p1
+        let root = u.sqrt();
+        let (b1, b2) = (root * (1.0 - v), root * v);
+        let point = self.p1 + self.e1 * b1 + self.e2 * b2;
+        let area = self.e1.cross(&self.e2).magnitude() / 2.0;
+        Some((Point3::new(point.x, point.y, point.z), area))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Tuple::point(0.0, 1.0, 0.0),
+            Tuple::point(-1.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let triangle = triangle();
+        assert_eq!(triangle.e1, Tuple::vector(-1.0, -1.0, 0.0));
+        assert_eq!(triangle.e2, Tuple::vector(1.0, -1.0, 0.0));
+        assert_eq!(triangle.normal, Tuple::vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal_of_a_triangle_is_constant_everywhere() {
+        let triangle = triangle();
+        let normal = Vector3::new(0.0, 0.0, -1.0);
+        for point in [
+            Point3::new(0.0, 0.5, 0.0),
+            Point3::new(-0.5, 0.75, 0.0),
+            Point3::new(0.5, 0.25, 0.0),
+        ] {
+            assert_eq!(triangle.local_normal_at(point), normal);
+        }
+    }
+
+    #[test]
+    fn ray_parallel_to_a_triangle_misses() {
+        let ray = Ray::new(Tuple::point(0.0, -1.0, -2.0), Tuple::vector(0.0, 1.0, 0.0));
+        assert!(triangle().local_intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn rays_past_each_edge_miss() {
+        let direction = Tuple::vector(0.0, 0.0, 1.0);
+        for origin in [
+            Tuple::point(1.0, 1.0, -2.0),
+            Tuple::point(-1.0, 1.0, -2.0),
+            Tuple::point(0.0, -1.0, -2.0),
+        ] {
+            let ray = Ray::new(origin, direction);
+            assert!(triangle().local_intersect(&ray).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_triangle() {
+        let ray = Ray::new(Tuple::point(0.0, 0.5, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(triangle().local_intersect(&ray), vec![2.0]);
+    }
+
+    #[test]
+    fn texture_coordinates_on_a_triangle_are_barycentric() {
+        let triangle = triangle();
+        let cases = [
+            (Point3::new(0.0, 1.0, 0.0), 0.0, 0.0),
+            (Point3::new(-1.0, 0.0, 0.0), 1.0, 0.0),
+            (Point3::new(1.0, 0.0, 0.0), 0.0, 1.0),
+            (Point3::new(0.0, 0.5, 0.0), 0.25, 0.25),
+        ];
+        for (point, u, v) in cases {
+            let (actual_u, actual_v, tangent) = triangle.local_uv(point).unwrap();
+            assert!(crate::is_float_equal(actual_u, u) && crate::is_float_equal(actual_v, v));
+            assert!(tangent.is_equal(&Vector3::new(-1.0, -1.0, 0.0).normalize()));
+        }
+    }
+
+    #[test]
+    fn surface_samples_stay_inside_the_triangle() {
+        let triangle = triangle();
+        for sample in [
+            (0.01, 0.5),
+            (0.99, 0.01),
+            (0.99, 0.99),
+            (0.3, 0.6),
+            (0.9, 0.2),
+        ] {
+            let (point, area) = triangle.local_sample_surface(sample).unwrap();
+            assert_eq!(area, 1.0);
+            let ray = Ray::new(
+                Tuple::point(point.x, point.y, -1.0),
+                Tuple::vector(0.0, 0.0, 1.0),
+            );
+            assert_eq!(triangle.local_intersect(&ray).len(), 1, "{:?}", sample);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use super::{Object, Transform};
+use crate::geometry::{Point3, Vector3};
+use crate::rays::{Material, Ray, ShadingModel};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Smoke or mist of the same density everywhere inside a boundary shape,
+/// which must be closed and convex, like a sphere. Rays passing through
+/// scatter at a random depth, more likely the denser the volume, and are
+/// shaded as if they hit something there. Its material is isotropic, with
+/// the smoke's `color`.
+#[derive(Debug)]
+pub struct Volume {
+    boundary: Box<dyn Object>,
+    density: f64,
+    // Always the identity: the volume is wherever its boundary is
+    transform: Transform,
+    pub material: Material,
+}
+
+impl Volume {
+    /// `density` is how many times per unit of distance a ray scatters on
+    /// average.
+    pub fn new(boundary: Box<dyn Object>, density: f64) -> Self {
+        Self {
+            boundary,
+            density,
+            transform: Transform::default(),
+            material: Material::new().shading_model(ShadingModel::Isotropic),
+        }
+    }
+
+    pub fn boundary(&self) -> &dyn Object {
+        self.boundary.as_ref()
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+}
+
+impl Object for Volume {
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        let Some(intersections) = ray.intersect(self.boundary.as_ref()) else {
+            return vec![];
+        };
+        let times = intersections.iter().map(|intersection| intersection.time);
+        // Only the stretch of the ray ahead of its origin passes through
+        let enter = times.clone().fold(f64::INFINITY, f64::min).max(0.0);
+        let exit = times.fold(f64::NEG_INFINITY, f64::max);
+        if exit <= enter {
+            return vec![];
+        }
+
+        // The chance of getting through falls off exponentially with depth
+        let speed = ray.direction.magnitude();
+        let depth = -(1.0 - ray_rng(ray).gen::<f64>()).ln() / self.density;
+        let time = enter + depth / speed;
+        if time < exit {
+            vec![time]
+        } else {
+            vec![]
+        }
+    }
+
+    // Isotropic scattering doesn't care which way this points
+    fn local_normal_at(&self, _point: Point3) -> Vector3 {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
+}
+
+/// A random number generator for the scattering depth along `ray`. Seeding
+/// from the ray keeps renders reproducible while still varying the depth
+/// from ray to ray.
+fn ray_rng(ray: &Ray) -> StdRng {
+    let seed = [ray.origin, ray.direction]
+        .iter()
+        .flat_map(|tuple| [tuple.x, tuple.y, tuple.z])
+        .fold(0u64, |seed, value| seed.rotate_left(21) ^ value.to_bits());
+    StdRng::seed_from_u64(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix;
+    use crate::shapes::Sphere;
+    use crate::tuple::Tuple;
+
+    fn smoke(density: f64) -> Volume {
+        let boundary = Sphere::with_transform(Matrix::identity().scale(2.0, 2.0, 2.0));
+        Volume::new(Box::new(boundary), density)
+    }
+
+    #[test]
+    fn rays_that_miss_the_boundary_miss_the_volume() {
+        let ray = Ray::new(Tuple::point(0.0, 3.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert!(smoke(100.0).local_intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn dense_volumes_scatter_just_inside_the_boundary() {
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 2.0));
+        let times = smoke(1e6).local_intersect(&ray);
+        assert_eq!(times.len(), 1);
+        assert!(times[0] >= 1.5 && times[0] < 1.5001);
+    }
+
+    #[test]
+    fn thin_volumes_let_rays_through() {
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert!(smoke(1e-9).local_intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn rays_from_inside_scatter_ahead_of_their_origin() {
+        let volume = smoke(1e6);
+        let ahead = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let times = volume.local_intersect(&ahead);
+        assert!(times.len() == 1 && times[0] >= 0.0 && times[0] < 0.001);
+        // Leaving the boundary behind, there is nothing left to scatter off
+        let behind = Ray::new(Tuple::point(0.0, 0.0, 3.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert!(volume.local_intersect(&behind).is_empty());
+    }
+
+    #[test]
+    fn scattering_depths_average_one_over_the_density() {
+        let volume = Volume::new(
+            Box::new(Sphere::with_transform(
+                Matrix::identity().scale(100.0, 100.0, 100.0),
+            )),
+            2.0,
+        );
+        let count = 2000;
+        let total: f64 = (0..count)
+            .map(|i| {
+                let ray = Ray::new(
+                    Tuple::point(i as f64 * 0.01, 0.0, 0.0),
+                    Tuple::vector(0.0, 0.0, 1.0),
+                );
+                volume.local_intersect(&ray)[0]
+            })
+            .sum();
+        assert!((total / count as f64 - 0.5).abs() < 0.05);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::is_float_equal;
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Tuple {
@@ -18,7 +19,7 @@ impl Tuple {
         Self { x, y, z, w: 0.0 }
     }
 
-    fn is_equal(&self, other: &Self) -> bool {
+    pub fn is_equal(&self, other: &Self) -> bool {
         is_float_equal(self.x, other.x)
             && is_float_equal(self.y, other.y)
             && is_float_equal(self.z, other.z)
@@ -36,7 +37,7 @@ impl Tuple {
 
     /// The distance of a vector.
     /// It's the length of a straight line from end to end of the vector.
-    fn magnitude(&self) -> f64 {
+    pub fn magnitude(&self) -> f64 {
         (self.x.powf(2.0) + self.y.powf(2.0) + self.z.powf(2.0) + self.w.powf(2.0)).sqrt()
     }
 
@@ -51,7 +52,7 @@ impl Tuple {
     /// The returned vector is perpendicular to the other two.
     /// Order is important. `other.cross(&self)` would return a vector in the
     /// opposite direction.
-    fn cross(&self, other: &Self) -> Self {
+    pub fn cross(&self, other: &Self) -> Self {
         Self::vector(
             self.y * other.z - self.z * other.y,
             self.z * other.x - self.x * other.z,
@@ -65,7 +66,7 @@ impl Tuple {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Color {
     pub red: f64,
     pub green: f64,
@@ -89,18 +90,37 @@ impl Color {
         Self::new(1.0, 0.0, 0.0)
     }
 
-    pub fn iter(&self) -> ColorIter {
+    pub fn iter(&self) -> ColorIter<'_> {
         ColorIter {
             current: 0,
             color: self,
         }
     }
 
-    fn is_equal(&self, other: &Self) -> bool {
+    pub fn is_equal(&self, other: &Self) -> bool {
         is_float_equal(self.red, other.red)
             && is_float_equal(self.green, other.green)
             && is_float_equal(self.blue, other.blue)
     }
+
+    /// Perceived brightness using the Rec. 709 weights.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
+    /// Linear interpolation: `t = 0` returns this color, `t = 1` returns `other`.
+    pub fn lerp(&self, other: &Color, t: f64) -> Color {
+        *self + (*other - *self) * t
+    }
+
+    /// Clamp each channel between `min` and `max`.
+    pub fn clamp(&self, min: f64, max: f64) -> Color {
+        Color::new(
+            self.red.clamp(min, max),
+            self.green.clamp(min, max),
+            self.blue.clamp(min, max),
+        )
+    }
 }
 
 pub struct ColorIter<'a> {
@@ -200,18 +220,6 @@ impl Add for Color {
     }
 }
 
-impl Add for &Color {
-    type Output = Color;
-
-    fn add(self, other: Self) -> Self::Output {
-        Color {
-            red: self.red + other.red,
-            green: self.green + other.green,
-            blue: self.blue + other.blue,
-        }
-    }
-}
-
 impl Sub for Color {
     type Output = Self;
 
@@ -224,11 +232,24 @@ impl Sub for Color {
     }
 }
 
-impl Mul<f64> for &Color {
-    type Output = Color;
+// Hadamard product, used to blend a surface color with a light's color
+impl Mul for Color {
+    type Output = Self;
 
-    fn mul(self, scalar: f64) -> Self::Output {
-        Self::Output {
+    fn mul(self, other: Self) -> Self {
+        Self {
+            red: self.red * other.red,
+            green: self.green * other.green,
+            blue: self.blue * other.blue,
+        }
+    }
+}
+
+impl Mul<f64> for Color {
+    type Output = Self;
+
+    fn mul(self, scalar: f64) -> Self {
+        Self {
             red: self.red * scalar,
             green: self.green * scalar,
             blue: self.blue * scalar,
@@ -236,18 +257,142 @@ impl Mul<f64> for &Color {
     }
 }
 
-impl Mul for &Color {
+impl Mul<Color> for f64 {
     type Output = Color;
 
-    fn mul(self, other: &Color) -> Self::Output {
-        Self::Output {
-            red: self.red * other.red,
-            green: self.green * other.green,
-            blue: self.blue * other.blue,
+    fn mul(self, color: Color) -> Self::Output {
+        color * self
+    }
+}
+
+impl Div<f64> for Color {
+    type Output = Self;
+
+    fn div(self, scalar: f64) -> Self {
+        Self {
+            red: self.red / scalar,
+            green: self.green / scalar,
+            blue: self.blue / scalar,
         }
     }
 }
 
+impl Neg for Color {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self * -1.0
+    }
+}
+
+impl Sum for Color {
+    fn sum<I: Iterator<Item = Color>>(iter: I) -> Self {
+        iter.fold(Color::black(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Color> for Color {
+    fn sum<I: Iterator<Item = &'a Color>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl Sum for Tuple {
+    fn sum<I: Iterator<Item = Tuple>>(iter: I) -> Self {
+        iter.fold(Tuple::vector(0.0, 0.0, 0.0), Add::add)
+    }
+}
+
+// The borrowed and assigning variants all defer to the owned impls above.
+// Both types are `Copy`, so dereferencing is free.
+macro_rules! forward_binary_op {
+    ($type:ty, $trait:ident, $method:ident, $rhs:ty) => {
+        impl $trait<$rhs> for &$type {
+            type Output = $type;
+
+            fn $method(self, other: $rhs) -> Self::Output {
+                (*self).$method(other)
+            }
+        }
+
+        impl $trait<&$rhs> for $type {
+            type Output = $type;
+
+            fn $method(self, other: &$rhs) -> Self::Output {
+                self.$method(*other)
+            }
+        }
+
+        impl $trait<&$rhs> for &$type {
+            type Output = $type;
+
+            fn $method(self, other: &$rhs) -> Self::Output {
+                (*self).$method(*other)
+            }
+        }
+    };
+}
+
+macro_rules! forward_assign_op {
+    ($type:ty, $trait:ident, $method:ident, $op:ident, $rhs:ty) => {
+        impl $trait<$rhs> for $type {
+            fn $method(&mut self, other: $rhs) {
+                *self = (*self).$op(other);
+            }
+        }
+
+        impl $trait<&$rhs> for $type {
+            fn $method(&mut self, other: &$rhs) {
+                *self = (*self).$op(*other);
+            }
+        }
+    };
+}
+
+forward_binary_op!(Color, Add, add, Color);
+forward_binary_op!(Color, Sub, sub, Color);
+forward_binary_op!(Color, Mul, mul, Color);
+forward_binary_op!(Color, Mul, mul, f64);
+forward_binary_op!(Color, Div, div, f64);
+forward_assign_op!(Color, AddAssign, add_assign, add, Color);
+forward_assign_op!(Color, SubAssign, sub_assign, sub, Color);
+forward_assign_op!(Color, MulAssign, mul_assign, mul, Color);
+forward_assign_op!(Color, MulAssign, mul_assign, mul, f64);
+forward_assign_op!(Color, DivAssign, div_assign, div, f64);
+
+forward_binary_op!(Tuple, Add, add, Tuple);
+forward_binary_op!(Tuple, Sub, sub, Tuple);
+forward_binary_op!(Tuple, Mul, mul, f64);
+forward_binary_op!(Tuple, Div, div, f64);
+forward_assign_op!(Tuple, AddAssign, add_assign, add, Tuple);
+forward_assign_op!(Tuple, SubAssign, sub_assign, sub, Tuple);
+forward_assign_op!(Tuple, MulAssign, mul_assign, mul, f64);
+forward_assign_op!(Tuple, DivAssign, div_assign, div, f64);
+
+impl Neg for &Color {
+    type Output = Color;
+
+    fn neg(self) -> Self::Output {
+        -*self
+    }
+}
+
+impl Neg for &Tuple {
+    type Output = Tuple;
+
+    fn neg(self) -> Self::Output {
+        -*self
+    }
+}
+
+impl Mul<Tuple> for f64 {
+    type Output = Tuple;
+
+    fn mul(self, tuple: Tuple) -> Self::Output {
+        tuple * self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -273,6 +418,90 @@ mod tests {
         assert!(is_float_equal(reflected.z, 0.0));
     }
 
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn color_operators_accept_owned_and_borrowed_operands() {
+        let a = Color::new(0.2, 0.4, 0.6);
+        let b = Color::new(0.1, 0.1, 0.2);
+        let expected = Color::new(0.3, 0.5, 0.8);
+        assert!((a + b).is_equal(&expected));
+        assert!((&a + b).is_equal(&expected));
+        assert!((a + &b).is_equal(&expected));
+        assert!((&a + &b).is_equal(&expected));
+        assert!((2.0 * a).is_equal(&(&a * 2.0)));
+        assert!((a / 2.0).is_equal(&Color::new(0.1, 0.2, 0.3)));
+        assert!((a * b).is_equal(&Color::new(0.02, 0.04, 0.12)));
+    }
+
+    #[test]
+    fn color_assignment_operators() {
+        let mut color = Color::new(0.2, 0.4, 0.6);
+        color += Color::new(0.2, 0.2, 0.2);
+        assert!(color.is_equal(&Color::new(0.4, 0.6, 0.8)));
+        color -= &Color::new(0.2, 0.2, 0.2);
+        assert!(color.is_equal(&Color::new(0.2, 0.4, 0.6)));
+        color *= 2.0;
+        assert!(color.is_equal(&Color::new(0.4, 0.8, 1.2)));
+        color *= Color::new(0.5, 0.5, 0.0);
+        assert!(color.is_equal(&Color::new(0.2, 0.4, 0.0)));
+        color /= 4.0;
+        assert!(color.is_equal(&Color::new(0.05, 0.1, 0.0)));
+    }
+
+    #[test]
+    fn summing_colors() {
+        let colors = vec![Color::new(0.1, 0.2, 0.3), Color::new(0.4, 0.5, 0.6)];
+        let total: Color = colors.iter().sum();
+        assert!(total.is_equal(&Color::new(0.5, 0.7, 0.9)));
+        let total: Color = colors.into_iter().sum();
+        assert!(total.is_equal(&Color::new(0.5, 0.7, 0.9)));
+        assert_eq!(
+            Vec::<Color>::new().into_iter().sum::<Color>(),
+            Color::black()
+        );
+    }
+
+    #[test]
+    fn luminance_of_colors() {
+        assert!(is_float_equal(Color::white().luminance(), 1.0));
+        assert!(is_float_equal(Color::black().luminance(), 0.0));
+        assert!(is_float_equal(
+            Color::new(0.0, 1.0, 0.0).luminance(),
+            0.7152
+        ));
+    }
+
+    #[test]
+    fn interpolating_between_colors() {
+        let a = Color::new(0.0, 0.5, 1.0);
+        let b = Color::new(1.0, 0.5, 0.0);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert!(a.lerp(&b, 0.25).is_equal(&Color::new(0.25, 0.5, 0.75)));
+    }
+
+    #[test]
+    fn clamping_a_color() {
+        let color = Color::new(-0.5, 0.4, 1.7);
+        assert_eq!(color.clamp(0.0, 1.0), Color::new(0.0, 0.4, 1.0));
+    }
+
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn tuple_assignment_and_borrowed_operators() {
+        let mut point = Tuple::point(1.0, 2.0, 3.0);
+        let vector = Tuple::vector(1.0, 1.0, 1.0);
+        assert_eq!(&point + &vector, Tuple::point(2.0, 3.0, 4.0));
+        point += vector;
+        assert_eq!(point, Tuple::point(2.0, 3.0, 4.0));
+        point -= &vector;
+        assert_eq!(point, Tuple::point(1.0, 2.0, 3.0));
+        let mut vector = vector * 4.0;
+        vector /= 2.0;
+        assert_eq!(vector, 2.0 * Tuple::vector(1.0, 1.0, 1.0));
+        assert_eq!(-&vector, Tuple::vector(-2.0, -2.0, -2.0));
+    }
+
     #[test]
     fn color_is_iterable() {
         let color = Color::new(1.0, 0.1, 0.4);
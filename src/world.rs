@@ -0,0 +1,510 @@
+use crate::background::Background;
+use crate::fog::Fog;
+use crate::lights::{Light, LightSample, ShapeLight};
+use crate::rays::{hit, lighting, Computations, Intersection, Ray, ShadingModel};
+use crate::shapes::Object;
+use crate::tuple::{Color, Tuple};
+
+/// How many times a ray may bounce between reflective surfaces by default.
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// Points sampled on each glowing shape when shading a point.
+pub const EMITTER_SAMPLES: usize = 16;
+
+/// Everything in a scene that can be hit or that gives off light.
+#[derive(Default)]
+pub struct World {
+    pub objects: Vec<Box<dyn Object>>,
+    pub lights: Vec<Box<dyn Light>>,
+    /// What rays that miss everything see.
+    pub background: Background,
+    /// Whether the background lights the world when path tracing, like a
+    /// sky would, instead of only being seen directly and in reflections.
+    pub image_based_lighting: bool,
+    /// Haze between the eye and whatever it sees. Rays that miss everything
+    /// see the background as it is.
+    pub fog: Option<Fog>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All intersections of the ray with every object, sorted by time.
+    pub fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut intersections: Vec<Intersection> = self
+            .objects
+            .iter()
+            .filter_map(|object| ray.intersect(object.as_ref()))
+            .flatten()
+            .collect();
+        intersections.sort_by(|a, b| a.time.total_cmp(&b.time));
+        intersections
+    }
+
+    /// Color of the hit described by the computations, summed over every
+    /// light and glowing shape, plus its own glow.
+    /// `remaining` is how many more reflections may be followed.
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        let material = comps.object.material();
+        // Lit the same by any number of lights, or none
+        if material.shading == ShadingModel::Unlit {
+            return material.color + self.reflected_color(comps, remaining);
+        }
+        let emitters = self.emitters(comps.shutter_time);
+        let surface: Color = self
+            .lights
+            .iter()
+            .map(AsRef::as_ref)
+            .chain(emitters.iter().map(|emitter| emitter as &dyn Light))
+            .map(|light| {
+                lighting(
+                    material,
+                    light,
+                    comps.over_point,
+                    comps.eye,
+                    comps.normal,
+                    self.intensity_at(light, comps.over_point, comps.shutter_time),
+                )
+            })
+            .sum();
+        material.emission + surface + self.reflected_color(comps, remaining)
+    }
+
+    /// The glowing shapes that light the world like its lights do.
+    pub fn emitters(&self, shutter_time: f64) -> Vec<ShapeLight<'_>> {
+        self.objects
+            .iter()
+            .filter_map(|object| ShapeLight::new(object.as_ref(), EMITTER_SAMPLES, shutter_time))
+            .collect()
+    }
+
+    /// Color seen along the ray, the background's if it hits nothing.
+    pub fn color_at(&self, ray: &Ray) -> Color {
+        self.color_at_depth(ray, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Like `color_at`, but following at most `remaining` reflections.
+    pub fn color_at_depth(&self, ray: &Ray, remaining: usize) -> Color {
+        let intersections = self.intersect(ray);
+        match hit(&intersections) {
+            Some(intersection) => {
+                let color = self.shade_hit(&intersection.prepare_computations(ray), remaining);
+                match &self.fog {
+                    Some(fog) => fog.apply(color, intersection.time * ray.direction.magnitude()),
+                    None => color,
+                }
+            }
+            None => self.background.color(ray.direction),
+        }
+    }
+
+    /// Color mirrored by the hit's surface, black once no reflections remain.
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let reflective = comps.object.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::black();
+        }
+
+        let ray = Ray::new(comps.over_point, comps.reflect).at_shutter_time(comps.shutter_time);
+        self.color_at_depth(&ray, remaining - 1) * reflective
+    }
+
+    /// How much of the light reaches the point, from 0 (fully shadowed) to 1
+    /// (nothing in the way), with moving objects where they are at
+    /// `shutter_time`.
+    pub fn intensity_at(&self, light: &dyn Light, point: Tuple, shutter_time: f64) -> f64 {
+        let samples = light.samples(point);
+        let lit = samples
+            .iter()
+            .filter(|sample| !self.is_shadowed(point, sample, shutter_time))
+            .count();
+        lit as f64 / samples.len() as f64
+    }
+
+    /// Whether anything sits between the point and the light sample.
+    pub fn is_shadowed(&self, point: Tuple, sample: &LightSample, shutter_time: f64) -> bool {
+        let ray = Ray::new(point, sample.direction).at_shutter_time(shutter_time);
+        let intersections = self.intersect(&ray);
+        match hit(&intersections) {
+            Some(intersection) => intersection.time < sample.distance,
+            None => false,
+        }
+    }
+
+    /// The world most of the book's tests are written against: two concentric
+    /// spheres lit from the upper left.
+    #[cfg(test)]
+    pub(crate) fn default_world() -> Self {
+        use crate::lights::PointLight;
+        use crate::matrix::Matrix;
+        use crate::rays::Material;
+        use crate::shapes::Sphere;
+
+        let mut outer = Sphere::new();
+        outer.material = Material::new()
+            .color(Color::new(0.8, 1.0, 0.6))
+            .diffuse(0.7)
+            .specular(0.2);
+        let inner = Sphere::with_transform(Matrix::identity().scale(0.5, 0.5, 0.5));
+
+        Self {
+            objects: vec![Box::new(outer), Box::new(inner)],
+            background: Background::default(),
+            image_based_lighting: false,
+            fog: None,
+            lights: vec![Box::new(PointLight::new(
+                Tuple::point(-10.0, 10.0, -10.0),
+                Color::white(),
+            ))],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fog::FogFalloff;
+    use crate::lights::{AreaLight, DirectionalLight, PointLight};
+    use crate::matrix::Matrix;
+    use crate::rays::Material;
+    use crate::shapes::{Plane, Sphere};
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    fn world_with_reflective_floor() -> World {
+        let mut world = World::default_world();
+        let mut floor = Plane::with_transform(Matrix::identity().translate(0.0, -1.0, 0.0));
+        floor.material = Material::new().reflective(0.5);
+        world.objects.push(Box::new(floor));
+        world
+    }
+
+    fn ray_toward_floor() -> Ray {
+        Ray::new(
+            Tuple::point(0.0, 0.0, -3.0),
+            Tuple::vector(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        )
+    }
+
+    #[test]
+    fn reflected_color_for_a_nonreflective_material() {
+        let mut world = World::default_world();
+        let mut inner = Sphere::with_transform(Matrix::identity().scale(0.5, 0.5, 0.5));
+        inner.material = Material::new().ambient(1.0);
+        world.objects[1] = Box::new(inner);
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(1.0, world.objects[1].as_ref());
+        let comps = intersection.prepare_computations(&ray);
+        assert_eq!(
+            world.reflected_color(&comps, DEFAULT_MAX_DEPTH),
+            Color::black()
+        );
+    }
+
+    #[test]
+    fn reflected_color_for_a_reflective_material() {
+        let world = world_with_reflective_floor();
+        let ray = ray_toward_floor();
+        let intersection = Intersection::new(2.0_f64.sqrt(), world.objects[2].as_ref());
+        let comps = intersection.prepare_computations(&ray);
+        let color = world.reflected_color(&comps, DEFAULT_MAX_DEPTH);
+        assert!(color.is_equal(&Color::new(0.19033, 0.23791, 0.14274)));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_material() {
+        let world = world_with_reflective_floor();
+        let ray = ray_toward_floor();
+        let intersection = Intersection::new(2.0_f64.sqrt(), world.objects[2].as_ref());
+        let comps = intersection.prepare_computations(&ray);
+        let color = world.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+        assert!(color.is_equal(&Color::new(0.87676, 0.92434, 0.82917)));
+    }
+
+    #[test]
+    fn reflected_color_at_the_maximum_recursive_depth() {
+        let world = world_with_reflective_floor();
+        let ray = ray_toward_floor();
+        let intersection = Intersection::new(2.0_f64.sqrt(), world.objects[2].as_ref());
+        let comps = intersection.prepare_computations(&ray);
+        assert_eq!(world.reflected_color(&comps, 0), Color::black());
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut world = World::new();
+        world.lights.push(Box::new(PointLight::new(
+            Tuple::point(0.0, 0.0, 0.0),
+            Color::white(),
+        )));
+        let mut lower = Plane::with_transform(Matrix::identity().translate(0.0, -1.0, 0.0));
+        lower.material = Material::new().reflective(1.0);
+        let mut upper = Plane::with_transform(Matrix::identity().translate(0.0, 1.0, 0.0));
+        upper.material = Material::new().reflective(1.0);
+        world.objects.push(Box::new(lower));
+        world.objects.push(Box::new(upper));
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+        // Terminates instead of bouncing forever
+        world.color_at(&ray);
+    }
+
+    #[test]
+    fn shade_hit_is_given_an_intersection_in_shadow() {
+        let mut world = World::new();
+        world.lights.push(Box::new(PointLight::new(
+            Tuple::point(0.0, 0.0, -10.0),
+            Color::white(),
+        )));
+        world.objects.push(Box::new(Sphere::new()));
+        world.objects.push(Box::new(Sphere::with_transform(
+            Matrix::identity().translate(0.0, 0.0, 10.0),
+        )));
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(4.0, world.objects[1].as_ref());
+        let color = world.shade_hit(&intersection.prepare_computations(&ray), DEFAULT_MAX_DEPTH);
+        assert_eq!(color, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn unlit_surfaces_ignore_how_many_lights_there_are() {
+        let mut world = World::new();
+        let mut sphere = Sphere::new();
+        sphere.material = Material::new()
+            .color(Color::new(0.2, 0.4, 0.6))
+            .shading_model(ShadingModel::Unlit);
+        world.objects.push(Box::new(sphere));
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        for _ in 0..2 {
+            assert_eq!(world.color_at(&ray), Color::new(0.2, 0.4, 0.6));
+            world.lights.push(Box::new(PointLight::new(
+                Tuple::point(-10.0, 10.0, -10.0),
+                Color::white(),
+            )));
+        }
+    }
+
+    #[test]
+    fn rays_that_miss_see_the_background() {
+        let mut world = World::new();
+        let sky = Color::new(0.4, 0.6, 1.0);
+        world.background = Background::Color(sky);
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+        assert_eq!(world.color_at(&ray), sky);
+    }
+
+    #[test]
+    fn fog_fades_what_rays_hit_by_distance() {
+        let mut world = World::new();
+        let mut lamp = Sphere::new();
+        lamp.material = Material::new()
+            .color(Color::new(1.0, 0.0, 0.0))
+            .shading_model(ShadingModel::Unlit);
+        world.objects.push(Box::new(lamp));
+        let sky = Color::new(0.4, 0.6, 1.0);
+        world.background = Background::Color(sky);
+        world.fog = Some(Fog::new(
+            Color::white(),
+            FogFalloff::Linear {
+                start: 0.0,
+                end: 8.0,
+            },
+        ));
+        // Hits 4 units away, whatever the length of the direction
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 2.0));
+        assert_eq!(world.color_at(&ray), Color::new(1.0, 0.5, 0.5));
+        let up = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+        assert_eq!(world.color_at(&up), sky);
+    }
+
+    #[test]
+    fn glowing_objects_are_seen_directly() {
+        let mut world = World::new();
+        let mut lamp = Sphere::new();
+        lamp.material = Material::new()
+            .color(Color::black())
+            .emission(Color::new(2.0, 1.0, 0.5));
+        world.objects.push(Box::new(lamp));
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(world.color_at(&ray), Color::new(2.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn glowing_objects_do_not_light_what_they_cannot_reach() {
+        let mut world = World::new();
+        let mut lamp = Sphere::with_transform(Matrix::identity().translate(0.0, 0.0, -50.0));
+        lamp.material = Material::new().emission(Color::white());
+        world.objects.push(Box::new(lamp));
+        world.objects.push(Box::new(Sphere::with_transform(
+            Matrix::identity()
+                .translate(0.0, 0.0, -10.0)
+                .scale(3.0, 3.0, 3.0),
+        )));
+        world.objects.push(Box::new(Sphere::new()));
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(4.0, world.objects[2].as_ref());
+        let color = world.shade_hit(&intersection.prepare_computations(&ray), DEFAULT_MAX_DEPTH);
+        assert_eq!(color, Color::black());
+    }
+
+    #[test]
+    fn directional_lights_cast_shadows_from_any_distance() {
+        let mut world = World::new();
+        world.lights.push(Box::new(DirectionalLight::new(
+            Tuple::vector(0.0, -1.0, 0.0),
+            Color::white(),
+        )));
+        world.objects.push(Box::new(Sphere::with_transform(
+            Matrix::identity().translate(0.0, 1000.0, 0.0),
+        )));
+        let light = world.lights[0].as_ref();
+        assert_eq!(
+            world.intensity_at(light, Tuple::point(0.0, 0.0, 0.0), 0.0),
+            0.0
+        );
+        assert_eq!(
+            world.intensity_at(light, Tuple::point(5.0, 0.0, 0.0), 0.0),
+            1.0
+        );
+    }
+
+    #[test]
+    fn point_lights_evaluate_the_light_intensity_at_a_point() {
+        let world = World::default_world();
+        let light = world.lights[0].as_ref();
+        let cases = [
+            (Tuple::point(0.0, 1.0001, 0.0), 1.0),
+            (Tuple::point(-1.0001, 0.0, 0.0), 1.0),
+            (Tuple::point(0.0, 0.0, -1.0001), 1.0),
+            (Tuple::point(0.0, 0.0, 1.0001), 0.0),
+            (Tuple::point(1.0001, 0.0, 0.0), 0.0),
+            (Tuple::point(0.0, -1.0001, 0.0), 0.0),
+            (Tuple::point(0.0, 0.0, 0.0), 0.0),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(world.intensity_at(light, point, 0.0), expected);
+        }
+    }
+
+    #[test]
+    fn area_lights_evaluate_the_light_intensity_at_a_point() {
+        let world = World::default_world();
+        let mut light = AreaLight::new(
+            Tuple::point(-0.5, -0.5, -5.0),
+            Tuple::vector(1.0, 0.0, 0.0),
+            2,
+            Tuple::vector(0.0, 1.0, 0.0),
+            2,
+            Color::white(),
+        );
+        light.jitter = false;
+        let cases = [
+            (Tuple::point(0.0, 0.0, 2.0), 0.0),
+            (Tuple::point(1.0, -1.0, 2.0), 0.25),
+            (Tuple::point(1.5, 0.0, 2.0), 0.5),
+            (Tuple::point(1.25, 1.25, 3.0), 0.75),
+            (Tuple::point(0.0, 0.0, -2.0), 1.0),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(world.intensity_at(&light, point, 0.0), expected);
+        }
+    }
+
+    #[test]
+    fn no_shadow_when_an_object_is_behind_the_point() {
+        let world = World::default_world();
+        let point = Tuple::point(-2.0, 2.0, -2.0);
+        let sample = &world.lights[0].samples(point)[0];
+        assert!(!world.is_shadowed(point, sample, 0.0));
+    }
+
+    #[test]
+    fn no_shadow_when_an_object_is_behind_the_light() {
+        let world = World::default_world();
+        let point = Tuple::point(-20.0, 20.0, -20.0);
+        let sample = &world.lights[0].samples(point)[0];
+        assert!(!world.is_shadowed(point, sample, 0.0));
+    }
+
+    #[test]
+    fn shadow_when_an_object_is_between_the_point_and_the_light() {
+        let world = World::default_world();
+        let point = Tuple::point(10.0, -10.0, 10.0);
+        let sample = &world.lights[0].samples(point)[0];
+        assert!(world.is_shadowed(point, sample, 0.0));
+    }
+
+    #[test]
+    fn no_shadow_when_nothing_is_collinear_with_point_and_light() {
+        let world = World::default_world();
+        let point = Tuple::point(0.0, 10.0, 0.0);
+        let sample = &world.lights[0].samples(point)[0];
+        assert!(!world.is_shadowed(point, sample, 0.0));
+    }
+
+    #[test]
+    fn color_with_an_intersection_behind_the_ray() {
+        let mut outer = Sphere::new();
+        outer.material = Material::new()
+            .color(Color::new(0.8, 1.0, 0.6))
+            .ambient(1.0);
+        let mut inner = Sphere::with_transform(Matrix::identity().scale(0.5, 0.5, 0.5));
+        inner.material = Material::new()
+            .color(Color::new(0.3, 0.4, 0.5))
+            .ambient(1.0);
+        let mut world = World::default_world();
+        world.objects = vec![Box::new(outer), Box::new(inner)];
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.75), Tuple::vector(0.0, 0.0, -1.0));
+        assert_eq!(world.color_at(&ray), Color::new(0.3, 0.4, 0.5));
+    }
+
+    #[test]
+    fn color_when_a_ray_hits() {
+        let world = World::default_world();
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert!(world
+            .color_at(&ray)
+            .is_equal(&Color::new(0.38066, 0.47583, 0.2855)));
+    }
+
+    #[test]
+    fn color_when_a_ray_misses() {
+        let world = World::default_world();
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+        assert_eq!(world.color_at(&ray), Color::black());
+    }
+
+    #[test]
+    fn shading_an_intersection_from_the_inside() {
+        let mut world = World::default_world();
+        world.lights = vec![Box::new(PointLight::new(
+            Tuple::point(0.0, 0.25, 0.0),
+            Color::white(),
+        ))];
+        let ray = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(0.5, world.objects[1].as_ref());
+        let color = world.shade_hit(&intersection.prepare_computations(&ray), DEFAULT_MAX_DEPTH);
+        assert!(color.is_equal(&Color::new(0.90498, 0.90498, 0.90498)));
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let world = World::default_world();
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(4.0, world.objects[0].as_ref());
+        let color = world.shade_hit(&intersection.prepare_computations(&ray), DEFAULT_MAX_DEPTH);
+        assert!(color.is_equal(&Color::new(0.38066, 0.47583, 0.2855)));
+    }
+
+    #[test]
+    fn intersecting_a_world_with_a_ray() {
+        let world = World::default_world();
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let times: Vec<f64> = world
+            .intersect(&ray)
+            .iter()
+            .map(|intersection| intersection.time)
+            .collect();
+        assert_eq!(times, vec![4.0, 4.5, 5.5, 6.0]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Golden image tests: small scenes in `tests/golden` rendered end to end
+//! and compared with the reference images next to them, so a change
+//! anywhere in the pipeline that alters what gets rendered can't go
+//! unnoticed.
+//!
+//! Renders may differ from their reference by a little, for rounding, but
+//! not by so much overall that the PSNR drops below `MIN_PSNR`, and only a
+//! few stray pixels may have a channel off by more than `PIXEL_TOLERANCE`.
+//! Those happen where rounding in the last bit flips a sample from one side
+//! of an edge to the other, or reseeds random sampling. A failing test saves
+//! the render and a difference image under the target directory.
+//!
+//! After a deliberate change in output, render new references with
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --test golden
+//! ```
+//!
+//! and look them over before committing them.
+
+use ray_tracer::camera::RenderSettings;
+use ray_tracer::canvas::Canvas;
+use ray_tracer::integrator::Integrator;
+use ray_tracer::sampling::SampleStrategy;
+use ray_tracer::tuple::Color;
+use std::path::{Path, PathBuf};
+
+/// Largest difference allowed in any channel of any pixel, out of 255.
+const PIXEL_TOLERANCE: u8 = 8;
+/// Share of pixels that may be off by more than `PIXEL_TOLERANCE`.
+const MAX_BAD_PIXELS: f64 = 0.01;
+/// Lowest peak signal-to-noise ratio allowed over the whole image, in dB.
+const MIN_PSNR: f64 = 40.0;
+/// Difference shown at full heat in difference images, twice
+/// `PIXEL_TOLERANCE`.
+const DIFF_SCALE: f64 = 16.0 / 255.0;
+
+/// How far apart two images of the same size are, in 8-bit levels.
+#[derive(Debug, PartialEq)]
+struct Comparison {
+    /// Largest difference in any channel of any pixel.
+    max_error: u8,
+    /// Pixels with a channel off by more than `PIXEL_TOLERANCE`.
+    bad_pixels: usize,
+    pixels: usize,
+    /// Peak signal-to-noise ratio in dB, infinite for identical images.
+    psnr: f64,
+}
+
+impl Comparison {
+    fn new(actual: &Canvas, expected: &Canvas) -> Self {
+        let levels = |error: f64| (error * 255.0).round() as u8;
+        let bad_pixels = actual
+            .pixels()
+            .zip(expected.pixels())
+            .filter(|((_, _, a), (_, _, b))| {
+                a.iter()
+                    .zip(b.iter())
+                    .any(|(a, b)| levels((a - b).abs()) > PIXEL_TOLERANCE)
+            })
+            .count();
+        Self {
+            max_error: levels(actual.max_abs_error(expected)),
+            bad_pixels,
+            pixels: (actual.width * actual.height) as usize,
+            psnr: actual.psnr(expected),
+        }
+    }
+
+    fn passes(&self) -> bool {
+        self.bad_pixels as f64 <= self.pixels as f64 * MAX_BAD_PIXELS && self.psnr >= MIN_PSNR
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// Renders `tests/golden/<name>.yml` and checks it against `<name>.ppm`.
+fn check(name: &str, settings: RenderSettings) {
+    let scene = ray_tracer::scene::load(golden_dir().join(format!("{}.yml", name)))
+        .unwrap_or_else(|err| panic!("can't load the {} scene: {}", name, err));
+    let rendered = scene.camera.render_with(&scene.world, &settings, |_| {});
+    let reference = golden_dir().join(format!("{}.ppm", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        ray_tracer::save_image(&rendered, &reference).unwrap();
+        return;
+    }
+
+    // Compared as stored, rounded to 8 bits
+    let actual = Canvas::from_ppm(rendered.to_ppm().as_bytes()).unwrap();
+    let expected = ray_tracer::load_image(&reference)
+        .unwrap_or_else(|err| panic!("can't read {}, render it with UPDATE_GOLDEN=1", err));
+    assert_eq!(
+        (actual.width, actual.height),
+        (expected.width, expected.height),
+        "{} rendered at the wrong size",
+        name
+    );
+
+    let comparison = Comparison::new(&actual, &expected);
+    if !comparison.passes() {
+        let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        std::fs::create_dir_all(&out).unwrap();
+        let render_path = out.join(format!("{}.ppm", name));
+        let diff_path = out.join(format!("{}-diff.ppm", name));
+        ray_tracer::save_image(&rendered, &render_path).unwrap();
+        let difference = expected.difference(&actual, DIFF_SCALE);
+        ray_tracer::save_image(&difference, &diff_path).unwrap();
+        panic!(
+            "{} differs from its reference: {} of {} pixels off by more than {}, \
+             at most by {}, PSNR {:.1} dB (at least {} needed).\n\
+             Render saved to {}, differences to {}",
+            name,
+            comparison.bad_pixels,
+            comparison.pixels,
+            PIXEL_TOLERANCE,
+            comparison.max_error,
+            comparison.psnr,
+            MIN_PSNR,
+            render_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+// Fixed so references don't depend on the machine's core count, though the
+// output doesn't either
+fn settings() -> RenderSettings {
+    RenderSettings {
+        threads: 2,
+        ..RenderSettings::default()
+    }
+}
+
+#[test]
+fn spheres_on_a_reflective_floor() {
+    check("spheres", settings());
+}
+
+#[test]
+fn shading_models_and_lights() {
+    check(
+        "materials",
+        RenderSettings {
+            samples: 4,
+            strategy: SampleStrategy::Jittered,
+            ..settings()
+        },
+    );
+}
+
+#[test]
+fn path_traced_glow_and_sky() {
+    check(
+        "path_traced",
+        RenderSettings {
+            samples: 32,
+            strategy: SampleStrategy::Jittered,
+            integrator: Integrator::PathTracing,
+            ..settings()
+        },
+    );
+}
+
+#[test]
+fn fog_and_smoke() {
+    check(
+        "atmosphere",
+        RenderSettings {
+            samples: 64,
+            ..settings()
+        },
+    );
+}
+
+#[test]
+fn comparisons_allow_small_errors_and_a_few_stray_pixels() {
+    let level = |value: u8| value as f64 / 255.0;
+    let gray = |value: u8| Color::new(level(value), level(value), level(value));
+    let image = |value: u8| {
+        let mut canvas = Canvas::new(100, 2);
+        canvas.fill(gray(value));
+        canvas
+    };
+    let expected = image(100);
+    let same = Comparison::new(&expected, &expected);
+    assert!(same.passes() && same.psnr.is_infinite());
+    let close = Comparison::new(&image(102), &expected);
+    assert!(close.passes(), "{:?}", close);
+
+    let mut stray = image(100);
+    let red = |value: u8| Color::new(level(value), level(100), level(100));
+    stray.set_pixel(1, 0, red(130));
+    stray.set_pixel(1, 1, red(70));
+    let comparison = Comparison::new(&stray, &expected);
+    assert_eq!((comparison.bad_pixels, comparison.max_error), (2, 30));
+    assert!(comparison.passes(), "{:?}", comparison);
+    stray.set_pixel(2, 0, red(160));
+    assert!(!Comparison::new(&stray, &expected).passes());
+    let dim = Comparison::new(&image(95), &expected);
+    assert!(dim.bad_pixels == 0 && !dim.passes(), "{:?}", dim);
+}